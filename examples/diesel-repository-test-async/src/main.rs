@@ -1,7 +1,10 @@
 // Bring in the macros and traits:
-use diesel::result::Error;
-use diesel::{table, AsChangeset, Identifiable, Insertable, QueryDsl, Queryable, Selectable};
-use diesel_repository::{Count, FindAll, FindByQuery, Repo};
+use diesel::{table, AsChangeset, Identifiable, Insertable, Queryable, Selectable};
+use diesel_async::pooled_connection::deadpool::Pool;
+use diesel_async::pooled_connection::AsyncDieselConnectionManager;
+use diesel_async::{AsyncPgConnection, SimpleAsyncConnection};
+use diesel_repository::asynchronous::{Delete, FindAll, FindAllPaged, Save, Update};
+use diesel_repository::Repo;
 use std::sync::Arc;
 
 table! {
@@ -12,29 +15,38 @@ table! {
     }
 }
 
-// Define a dummy DB pool type for async (usually this would be defined in your app).
+// The pool the repositories check their connections out of.
 pub mod db {
-    // In your app, you’d likely have:
-    // pub type DbPool = diesel_async::pooled_connection::bb8::Pool<
-    //     diesel_async::AsyncConnectionManager<diesel_async::AsyncPgConnection>
-    // >;
-    //
-    // For testing, we create a stub.
-    #[allow(dead_code)]
-    pub struct DummyPool;
-    pub type DbPool = DummyPool;
+    use diesel::result::{Error, QueryResult};
+    use diesel_async::pooled_connection::deadpool::{Object, Pool};
+    use diesel_async::AsyncPgConnection;
+    use diesel_repository::AsyncConnectionSource;
+
+    pub struct DbPool(pub Pool<AsyncPgConnection>);
+
+    #[diesel_repository::async_trait]
+    impl AsyncConnectionSource for DbPool {
+        type Connection = AsyncPgConnection;
+        type Guard<'a> = Object<AsyncPgConnection>;
+
+        async fn connection(&self) -> QueryResult<Self::Guard<'_>> {
+            self.0
+                .get()
+                .await
+                .map_err(|err| Error::QueryBuilderError(err.into()))
+        }
+    }
 }
 
 // An entity with a derive macro.
 #[derive(
     Debug, Eq, PartialEq, Queryable, Identifiable, Selectable, Insertable, AsChangeset, Repo,
 )]
-#[repository(pool = "db::DbPool")]
 #[diesel(table_name = crate::accounts)]
-#[crud_repo(find_all, find_one, insert, update, delete)]
+#[repository(mode = "async", pool = db::DbPool, table_name = crate::accounts)]
+#[repo_type(id_type = String)]
+#[crud_repo(find_all, find_one, save, update, delete)]
 #[paging_repo(find_all)]
-#[repo_table_name("crate::accounts")]
-#[id_type("String")]
 pub struct Account {
     pub id: String,
     pub sub: String,
@@ -44,41 +56,33 @@ pub struct Account {
 // For async tests we use Tokio.
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Create a dummy pool. (Replace with your real pool creation.)
-    let pool = Arc::new(dummy_pool());
-    let repo = AccountRepo::new(pool);
+    let url = std::env::var("DATABASE_URL")?;
+    let manager = AsyncDieselConnectionManager::<AsyncPgConnection>::new(url);
+    let pool = Pool::builder(manager).max_size(4).build()?;
+    pool.get().await?.batch_execute(
+        "CREATE TABLE IF NOT EXISTS accounts (id TEXT PRIMARY KEY, sub TEXT NOT NULL, name TEXT NOT NULL)",
+    )
+    .await?;
+    let repo = AccountRepo::new(Arc::new(db::DbPool(pool)));
 
-    // The following calls will trigger the unimplemented!() (as placeholders)
-    // You can later replace these with actual implementations.
-    // For example:
-    let _result = repo.find_all().await?;
-    let _paged = repo.find_all_paging(1, 10).await?;
+    let mut account = repo
+        .save(Account {
+            id: format!("async-account-{}", std::process::id()),
+            sub: "sub".into(),
+            name: "Alice".into(),
+        })
+        .await?;
+    account.name = "Alicia".into();
+    let account = repo.update(account).await?;
+    let accounts = repo.find_all().await?;
+    let paged = repo.find_all_paged(1, 10).await?;
+    repo.delete(account.id).await?;
 
-    println!("Async test run completed.");
+    println!(
+        "Async test run completed: {} accounts, {} on the first page.",
+        accounts.len(),
+        paged.items.len()
+    );
 
     Ok(())
 }
-
-// Dummy pool creation function.
-fn dummy_pool() -> db::DbPool {
-    // In a real async setup, you’d initialize your diesel_async pool.
-    // For testing, we simply return an instance of our dummy type.
-    db::DummyPool
-}
-
-use diesel_async::pooled_connection::deadpool::Pool;
-use diesel_async::{AsyncPgConnection, RunQueryDsl};
-
-pub struct Miaou {
-    pool: Arc<Pool<AsyncPgConnection>>,
-}
-
-pub struct Waff;
-
-impl FindAll<Waff> for Miaou {
-    async fn find_all(&self) -> Result<Vec<Waff>, Error> {
-        let mut conn = self.pool.get().await?;
-        let res = accounts::table.load(&mut conn).await?;
-        Ok(res)
-    }
-}
@@ -1,8 +1,10 @@
 // Bring in the macros and traits.
+use diesel::connection::SimpleConnection;
 use diesel::{table, AsChangeset, Identifiable, Insertable, Queryable, Selectable};
 use diesel_repository::FindAll;
 use diesel_repository::FindAllPaged;
 use diesel_repository::Repo;
+use diesel_repository::Save;
 use std::sync::Arc;
 
 table! {
@@ -13,24 +15,35 @@ table! {
     }
 }
 
-// Define a dummy DB pool type for synchronous use.
+// The pool the repositories check their connections out of.
 pub mod db {
-    // Typically, you’d define:
-    // pub type DbPool = diesel::r2d2::Pool<diesel::r2d2::ConnectionManager<diesel::PgConnection>>;
-    // For testing, we create a stub.
-    #[allow(dead_code)]
-    pub struct DummyPool;
-    pub type DbPool = DummyPool;
+    use diesel::r2d2::{ConnectionManager, Pool, PooledConnection};
+    use diesel::result::{Error, QueryResult};
+    use diesel::PgConnection;
+    use diesel_repository::ConnectionSource;
+
+    pub struct DbPool(pub Pool<ConnectionManager<PgConnection>>);
+
+    impl ConnectionSource for DbPool {
+        type Connection = PgConnection;
+        type Guard<'a> = PooledConnection<ConnectionManager<PgConnection>>;
+
+        fn connection(&self) -> QueryResult<Self::Guard<'_>> {
+            self.0
+                .get()
+                .map_err(|err| Error::QueryBuilderError(err.into()))
+        }
+    }
 }
 
 // Define an entity with a derive macro.
 #[derive(
     Debug, Eq, PartialEq, Queryable, Identifiable, Selectable, Insertable, AsChangeset, Repo,
 )]
-#[diesel(table_name = crate::accounts)]
+#[diesel(table_name = crate::accounts, check_for_backend(diesel::pg::Pg))]
 #[repository(pool = db::DbPool, table_name = crate::accounts)]
 #[repo_type(id_type = String)]
-#[crud_repo(find_one, find_one_query, find_all)]
+#[crud_repo(find_one, find_one_query, find_all, save)]
 #[paging_repo(find_all)]
 pub struct Account {
     pub id: String,
@@ -39,20 +52,26 @@ pub struct Account {
 }
 
 fn main() -> anyhow::Result<()> {
-    // Create a dummy pool (replace with your actual pool creation logic).
-    let pool = Arc::new(dummy_pool());
-    let repo = AccountRepo::new(pool);
-    println!("Sync test run completed.");
+    let url = std::env::var("DATABASE_URL")?;
+    let manager = diesel::r2d2::ConnectionManager::<diesel::PgConnection>::new(url);
+    let pool = diesel::r2d2::Pool::builder().max_size(4).build(manager)?;
+    pool.get()?.batch_execute(
+        "CREATE TABLE IF NOT EXISTS accounts (id TEXT PRIMARY KEY, sub TEXT NOT NULL, name TEXT NOT NULL)",
+    )?;
+    let repo = AccountRepo::new(Arc::new(db::DbPool(pool)));
 
-    let _result: Vec<Account> = repo.find_all()?;
-    let _paged = repo.find_all_paged(1, 10)?;
+    repo.save(Account {
+        id: format!("account-{}", std::process::id()),
+        sub: "sub".into(),
+        name: "Alice".into(),
+    })?;
+    let accounts: Vec<Account> = repo.find_all()?;
+    let paged = repo.find_all_paged(1, 10)?;
+    println!(
+        "Sync test run completed: {} accounts, {} on the first page.",
+        accounts.len(),
+        paged.items.len()
+    );
 
     Ok(())
 }
-
-// Dummy pool creation function.
-fn dummy_pool() -> db::DbPool {
-    // In a real setup, you would create and configure your diesel pool.
-    // For testing, simply return an instance of our dummy pool.
-    db::DummyPool
-}
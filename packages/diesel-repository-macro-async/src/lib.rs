@@ -1,3 +0,0 @@
-mod derive;
-
-pub use derive::derive;
@@ -0,0 +1,1705 @@
+use proc_macro2::TokenStream;
+use proc_macro_error2::abort;
+use quote::{format_ident, quote, quote_spanned};
+
+use crate::{diagnostics, infer};
+use crate::{CountStrategy, Mode, Options, SqlReturns};
+
+/// One pass of the generator, implementing either the blocking or the `async` traits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Flavor {
+    Sync,
+    Async,
+}
+
+/// What one pass of the generator emits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pass {
+    /// The struct, its builder and constructors alongside the methods.
+    Declare,
+    /// Only the methods, for the async flavor of `mode = "both"`.
+    Methods,
+    /// The trait impls and inherent methods again on the `WithContext` view, for
+    /// `#[repository(session_context)]`.
+    Context,
+}
+
+/// Generates the repository described by `opts`, in the mode of `#[repository(mode = ...)]`.
+pub fn derive(opts: Options) -> syn::Result<TokenStream> {
+    let flavors: &[Flavor] = match opts.repo.mode {
+        Mode::Sync => &[Flavor::Sync],
+        Mode::Async => &[Flavor::Async],
+        Mode::Both => &[Flavor::Sync, Flavor::Async],
+    };
+
+    // The struct is declared once; the async pass of `mode = "both"` only adds its trait
+    // impls and `_async` variants of the inherent methods.
+    let mut expanded = expand(opts.clone(), flavors[0], Pass::Declare)?;
+    for flavor in &flavors[1..] {
+        expanded.extend(expand(opts.clone(), *flavor, Pass::Methods)?);
+    }
+    if opts.repo.session_context {
+        for flavor in flavors {
+            expanded.extend(expand(opts.clone(), *flavor, Pass::Context)?);
+        }
+    }
+    Ok(expanded)
+}
+
+/// Both flavors share one model and only differ in the trait family, `async fn`
+/// signatures, `#[async_trait]` impls and `.await` on delegated calls.
+fn expand(opts: Options, flavor: Flavor, pass: Pass) -> syn::Result<TokenStream> {
+    let declare = pass == Pass::Declare;
+    let context = pass == Pass::Context;
+    let (traits, asyncness, awaited, async_trait) = match flavor {
+        Flavor::Sync => (
+            quote! { diesel_repository::sync },
+            quote! {},
+            quote! {},
+            quote! {},
+        ),
+        Flavor::Async => (
+            quote! { diesel_repository::asynchronous },
+            quote! { async },
+            quote! { .await },
+            quote! { #[diesel_repository::async_trait] },
+        ),
+    };
+    // Inherent methods cannot be overloaded, so the async pass of `mode = "both"` renames them.
+    let suffix = match (opts.repo.mode, flavor) {
+        (Mode::Both, Flavor::Async) => "_async",
+        _ => "",
+    };
+    // Query methods take a closure refining the boxed query of the table.
+    let query_fn = quote! {
+        &diesel_repository::QueryFn<'_, <Self as diesel_repository::QueryRepository>::Query>
+    };
+
+    let struct_name = opts.repo.ident;
+    let generics = opts.repo.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let entity = quote! { #struct_name #ty_generics };
+    let id_type = opts.repo_type.id_type.unwrap_or_else(|| {
+        abort!(
+            struct_name,
+            "Missing 'id_type' attribute in #[repo_type]";
+            help = "add an `id` field or declare `#[diesel(primary_key(...))]` to infer it"
+        )
+    });
+
+    // Point the record checks at the declared types, or at the entity they default to.
+    let new_span = opts
+        .repo_type
+        .new_type
+        .as_ref()
+        .unwrap_or(&struct_name)
+        .span();
+    let update_span = opts
+        .repo_type
+        .update_type
+        .as_ref()
+        .unwrap_or(&struct_name)
+        .span();
+
+    let new_type = match opts.repo_type.new_type {
+        None => entity.clone(),
+        Some(v) => quote! { #v },
+    };
+
+    let update_type = match opts.repo_type.update_type {
+        None => entity.clone(),
+        Some(v) => quote! { #v },
+    };
+
+    let patch_type = match opts.repo_type.patch_type {
+        None => format_ident!("{}Patch", struct_name),
+        Some(v) => v,
+    };
+
+    // Generate repository type name: e.g. `AccountRepo` for struct `Account`
+    let repo_name = match opts.repo.name {
+        Some(name) => name,
+        None => syn::Ident::new(&format!("{}Repo", struct_name), struct_name.span()),
+    };
+    let builder_name = format_ident!("{}Builder", repo_name);
+    let context_name = format_ident!("{}WithContext", repo_name);
+
+    // The context pass implements the traits and methods on the view, whose hooks and actor are
+    // those of the repository, and runs every operation in a transaction applying the context
+    // first.
+    let mut context_generics = generics.clone();
+    context_generics.params.insert(0, syn::parse_quote!('ctx));
+    let (context_impl_generics, context_ty_generics, _) = context_generics.split_for_impl();
+    let trait_impl_generics = if context {
+        quote! { #context_impl_generics }
+    } else {
+        quote! { #impl_generics }
+    };
+    let (self_ty, this) = if context {
+        (
+            quote! { #context_name #context_ty_generics },
+            quote! { self.repo() },
+        )
+    } else {
+        (quote! { #repo_name #ty_generics }, quote! { self })
+    };
+    // Executing methods check a connection out of the pool and run their queries on `conn`,
+    // calling `RunQueryDsl` by path since both Diesel's and diesel-async's may be in scope.
+    let (checkout, run_query_dsl) = match flavor {
+        Flavor::Sync => (
+            quote! { diesel_repository::ConnectionSource::connection(&*#this.pool)? },
+            quote! { diesel::RunQueryDsl },
+        ),
+        Flavor::Async => (
+            quote! { diesel_repository::AsyncConnectionSource::connection(&*#this.pool).await? },
+            quote! { diesel_repository::diesel_async::RunQueryDsl },
+        ),
+    };
+    // Views of `#[repository(session_context)]` run every operation in a transaction applying
+    // their context first, as the settings only last until the end of a transaction.
+    let apply_context = match (context, flavor) {
+        (false, _) => TokenStream::new(),
+        (true, Flavor::Sync) => quote! {
+            diesel_repository::SessionContext::apply(self.context(), conn)?;
+        },
+        (true, Flavor::Async) => quote! {
+            diesel_repository::SessionContext::apply_async(self.context(), conn).await?;
+        },
+    };
+    // Writes with hooks, events or history run them in one transaction with the statement.
+    let transact = |body: TokenStream| match flavor {
+        Flavor::Sync => quote! {
+            let mut connection = #checkout;
+            diesel::Connection::transaction::<_, diesel::result::Error, _>(&mut *connection, |conn| {
+                use diesel::{ExpressionMethods, QueryDsl};
+                #apply_context
+                #body
+            })
+        },
+        Flavor::Async => quote! {
+            let mut connection = #checkout;
+            diesel_repository::diesel_async::AsyncConnection::transaction::<_, diesel::result::Error, _>(&mut *connection, |conn| {
+                diesel_repository::diesel_async::scoped_futures::ScopedFutureExt::scope_boxed(async move {
+                    use diesel::{ExpressionMethods, QueryDsl};
+                    #apply_context
+                    #body
+                })
+            })
+            .await
+        },
+    };
+    let run = |body: TokenStream| {
+        if context {
+            return transact(body);
+        }
+        quote! {
+            let mut connection = #checkout;
+            let conn = &mut *connection;
+            use diesel::{ExpressionMethods, QueryDsl};
+            #body
+        }
+    };
+    let repo_vis = match opts.repo.visibility {
+        Some(vis) => quote! { #vis },
+        None => quote! { pub },
+    };
+    let repo_derives = opts.repo.derive.iter();
+
+    // Ensure the repository attribute provided a pool type.
+    let pool_type = match opts.repo.pool {
+        Some(path) => path,
+        None => abort!(struct_name, "Missing 'pool' attribute in #[repository]"),
+    };
+
+    let diesel_table = match opts.repo.table_name {
+        Some(path) => path,
+        None => abort!(
+            struct_name,
+            "Missing 'table_name' attribute in #[repository]";
+            help = "declare `#[diesel(table_name = ...)]` to infer it"
+        ),
+    };
+
+    // Query methods are object-safe over the boxed query, which Diesel boxes per backend.
+    let takes_query = opts.crud_repo.find_one_query
+        || opts.crud_repo.find_query
+        || opts.crud_repo.count
+        || opts.crud_repo.exists_query
+        || opts.crud_repo.lock
+        || opts.paging_repo.find_query
+        || !opts.repo.projections.is_empty();
+    let query_repository = match &opts.repo.backend {
+        Some(backend) => quote! {
+            impl #impl_generics diesel_repository::QueryRepository for #repo_name #ty_generics #where_clause {
+                type Query = #diesel_table::BoxedQuery<'static, #backend>;
+            }
+        },
+        None if takes_query => abort!(
+            struct_name,
+            "Missing 'backend' attribute in #[repository]";
+            help = "declare `#[diesel(check_for_backend(...))]` or `#[repository(backend = ...)]` to box queries for it"
+        ),
+        None => TokenStream::new(),
+    };
+
+    // Checked up front, so that a `new_type`/`update_type` Diesel cannot write gets an error
+    // naming `#[repo_type]` rather than Diesel's errors from inside the generated bodies.
+    let inserts = opts.crud_repo.save || opts.batch_repo.save || opts.repo.queue.is_some();
+    let updates = opts.crud_repo.update || opts.batch_repo.update;
+    let new_check = inserts.then(|| {
+        quote_spanned! {new_span=>
+            new_record::<#new_type, #diesel_table::table>();
+        }
+    });
+    let update_check = updates.then(|| {
+        quote_spanned! {update_span=>
+            update_record::<&#update_type, #diesel_table::table>();
+        }
+    });
+    let record_checks = if inserts || updates {
+        quote! {
+            const _: () = {
+                #[allow(dead_code)]
+                fn records #impl_generics () #where_clause {
+                    fn new_record<N: diesel_repository::NewRecord<T>, T>() {}
+                    fn update_record<U: diesel_repository::UpdateRecord<T>, T>() {}
+                    #new_check
+                    #update_check
+                }
+            };
+        }
+    } else {
+        TokenStream::new()
+    };
+
+    // With `#[repository(schema_check)]`, the columns are checked against the deployed schema.
+    let schema_check = if opts.repo.schema_check {
+        if opts.repo.columns.is_empty() {
+            abort!(
+                struct_name,
+                "`schema_check` needs a struct with named fields"
+            );
+        }
+        let table = diesel_table
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string())
+            .unwrap_or_default();
+        let names = opts.repo.columns.iter().map(|column| column.to_string());
+        let columns = opts.repo.columns.iter();
+        quote! {
+            impl #impl_generics diesel_repository::SchemaCheck for #repo_name #ty_generics #where_clause {
+                const SCHEMA: diesel_repository::TableSchema = diesel_repository::TableSchema {
+                    table: #table,
+                    columns: &[
+                        #(diesel_repository::ColumnSchema::of::<diesel::dsl::SqlTypeOf<#diesel_table::#columns>>(#names),)*
+                    ],
+                };
+            }
+        }
+    } else {
+        TokenStream::new()
+    };
+
+    // With `#[repository(hooks)]`, the repository must implement `RepoHooks` for its types.
+    let hooks = opts.repo.hooks.then(|| {
+        if opts.crud_repo.patch {
+            quote! { diesel_repository::RepoHooks::<#entity, #id_type, #new_type, #update_type, #patch_type> }
+        } else {
+            quote! { diesel_repository::RepoHooks::<#entity, #id_type, #new_type, #update_type> }
+        }
+    });
+    let mutability = hooks.as_ref().map(|_| quote! { mut });
+    let hooked = |tokens: TokenStream| match hooks {
+        Some(_) => tokens,
+        None => TokenStream::new(),
+    };
+
+    // With `#[repository(outbox = ...)]`, the entity must implement `DomainEvents`.
+    let outbox = opts.repo.outbox.as_ref().map(|table| table.to_string());
+    let write_events = match flavor {
+        Flavor::Sync => quote! { diesel_repository::write_events },
+        Flavor::Async => quote! { diesel_repository::write_events_async },
+    };
+    let outbox_events = |operation: TokenStream| match &outbox {
+        Some(table) => quote! {
+            let events = diesel_repository::DomainEvents::domain_events(
+                &record,
+                diesel_repository::Operation::#operation,
+            );
+            #write_events(conn, #table, &events)#awaited?;
+        },
+        None => TokenStream::new(),
+    };
+
+    // With `#[repository(history = ...)]`, the repository must implement `HistoryActor`. The
+    // history table repeats the entity's columns and adds `operation`, `actor` and `changed_at`.
+    let history = opts.repo.history.as_ref();
+    let history_key = match (history, infer::primary_key(&opts.repo.attrs).as_slice()) {
+        (Some(_), _) if opts.repo.columns.is_empty() => {
+            abort!(struct_name, "`history` needs a struct with named fields")
+        }
+        (Some(_), [id]) => Some(id.clone()),
+        (Some(_), _) => abort!(struct_name, "`history` needs a single-column primary key"),
+        (None, _) => None,
+    };
+    let columns = &opts.repo.columns;
+    // Copies the rows of `source` into the history table before `operation` changes them.
+    let history_record = |operation: TokenStream, source: TokenStream| match history {
+        Some(history_table) => quote! {
+            let actor = diesel_repository::HistoryActor::current_actor(#this);
+            let previous = #source.select((
+                #(#diesel_table::#columns,)*
+                diesel::IntoSql::into_sql::<diesel::sql_types::Text>(diesel_repository::Operation::#operation.as_str()),
+                diesel::IntoSql::into_sql::<diesel::sql_types::Nullable<diesel::sql_types::Text>>(actor),
+                diesel::dsl::now,
+            ));
+            let query = diesel::insert_into(#history_table::table).values(previous).into_columns((
+                #(#history_table::#columns,)*
+                #history_table::operation,
+                #history_table::actor,
+                #history_table::changed_at,
+            ));
+            #run_query_dsl::execute(query, conn)#awaited?;
+        },
+        None => TokenStream::new(),
+    };
+
+    let write = |before: TokenStream,
+                 ty: TokenStream,
+                 statement: TokenStream,
+                 after: TokenStream,
+                 output: TokenStream| {
+        let body = quote! {
+            #before
+            #[allow(unused_mut)]
+            let mut record: #ty = #statement #awaited?;
+            #after
+            #output
+        };
+        if hooks.is_some() || outbox.is_some() || history.is_some() {
+            transact(body)
+        } else {
+            run(body)
+        }
+    };
+
+    // Reads run `after_load` on every record they return.
+    let read = |ty: TokenStream, statement: TokenStream, after: TokenStream| {
+        run(quote! {
+            #[allow(unused_mut)]
+            let mut record: #ty = #statement #awaited?;
+            #after
+            Ok(record)
+        })
+    };
+    let load_one = hooked(quote! { #hooks::after_load(#this, &mut record)?; });
+    let load_all = hooked(quote! {
+        for item in record.iter_mut() {
+            #hooks::after_load(#this, item)?;
+        }
+    });
+
+    let mut crud_methods = TokenStream::new();
+    if opts.crud_repo.find_one {
+        let body = read(
+            quote! { #entity },
+            quote! { #run_query_dsl::get_result(#diesel_table::table.find(id), conn) },
+            load_one.clone(),
+        );
+        let m = quote! {
+            #async_trait
+            impl #trait_impl_generics #traits::FindById<#entity, #id_type> for #self_ty #where_clause {
+                #asyncness fn find_by_id(&self, id: #id_type) -> Result<#entity, diesel::result::Error> {
+                    #body
+                }
+            }
+        };
+        crud_methods.extend(m);
+    }
+    if opts.crud_repo.find_one_query {
+        let body = read(
+            quote! { #entity },
+            quote! { #run_query_dsl::first(query(#diesel_table::table.into_boxed()), conn) },
+            load_one.clone(),
+        );
+        let m = quote! {
+            #async_trait
+            impl #trait_impl_generics #traits::FindOneByQuery<#entity> for #self_ty #where_clause {
+                #asyncness fn find_one_by_query(&self, query: #query_fn) -> Result<#entity, diesel::result::Error> {
+                    #body
+                }
+            }
+        };
+        crud_methods.extend(m);
+    }
+    if opts.crud_repo.find_query {
+        let body = read(
+            quote! { Vec<#entity> },
+            quote! { #run_query_dsl::load(query(#diesel_table::table.into_boxed()), conn) },
+            load_all.clone(),
+        );
+        let m = quote! {
+            #async_trait
+            impl #trait_impl_generics #traits::FindByQuery<#entity> for #self_ty #where_clause {
+                #asyncness fn find_by_query(&self, query: #query_fn) -> Result<Vec<#entity>, diesel::result::Error> {
+                    #body
+                }
+            }
+        };
+        crud_methods.extend(m);
+    }
+    if opts.crud_repo.find_all {
+        let body = read(
+            quote! { Vec<#entity> },
+            quote! { #run_query_dsl::load(#diesel_table::table, conn) },
+            load_all.clone(),
+        );
+        let m = quote! {
+            #async_trait
+            impl #trait_impl_generics #traits::FindAll<#entity> for #self_ty #where_clause {
+                #asyncness fn find_all(&self) -> Result<Vec<#entity>, diesel::result::Error> {
+                    #body
+                }
+            }
+        };
+        crud_methods.extend(m);
+    }
+    if opts.crud_repo.save {
+        let body = write(
+            hooked(quote! { #hooks::before_save(#this, &mut new_record)?; }),
+            quote! { #entity },
+            quote! {
+                #run_query_dsl::get_result(
+                    diesel::insert_into(#diesel_table::table).values(&new_record),
+                    conn,
+                )
+            },
+            [
+                hooked(quote! { #hooks::after_save(#this, &record)?; }),
+                outbox_events(quote! { Insert }),
+            ]
+            .into_iter()
+            .collect(),
+            quote! { Ok(record) },
+        );
+        let m = quote! {
+            #async_trait
+            impl #trait_impl_generics #traits::Save<#entity, #new_type> for #self_ty #where_clause {
+                #asyncness fn save(&self, #mutability new_record: #new_type) -> Result<#entity, diesel::result::Error> {
+                    #body
+                }
+            }
+        };
+        crud_methods.extend(m);
+    }
+    if opts.crud_repo.update {
+        let body = write(
+            [
+                hooked(quote! { #hooks::before_update(#this, &mut update_record)?; }),
+                history_record(
+                    quote! { Update },
+                    quote! { #diesel_table::table.find(diesel::Identifiable::id(&update_record)) },
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            quote! { #entity },
+            quote! {
+                #run_query_dsl::get_result(
+                    diesel::update(&update_record).set(&update_record),
+                    conn,
+                )
+            },
+            [
+                hooked(quote! { #hooks::after_save(#this, &record)?; }),
+                outbox_events(quote! { Update }),
+            ]
+            .into_iter()
+            .collect(),
+            quote! { Ok(record) },
+        );
+        let m = quote! {
+            #async_trait
+            impl #trait_impl_generics #traits::Update<#entity, #update_type> for #self_ty #where_clause {
+                #asyncness fn update(&self, #mutability update_record: #update_type) -> Result<#entity, diesel::result::Error> {
+                    #body
+                }
+            }
+        };
+        crud_methods.extend(m);
+    }
+    if opts.crud_repo.patch {
+        let Some(backend) = &opts.repo.backend else {
+            abort!(
+                struct_name,
+                "Missing 'backend' attribute in #[repository]";
+                help = "declare `#[diesel(check_for_backend(...))]` or `#[repository(backend = ...)]` to tell empty patches"
+            )
+        };
+        // Diesel refuses to build an update without columns, so an empty patch only reads.
+        let unchanged = quote! {
+            let changes = diesel::AsChangeset::as_changeset(&patch_record);
+            if diesel::query_builder::QueryFragment::<#backend>::is_noop(&changes, &<#backend as Default>::default())? {
+                let mut record: #entity = #run_query_dsl::get_result(#diesel_table::table.find(id), conn)#awaited?;
+                #load_one
+                return Ok(record);
+            }
+        };
+        let body = write(
+            [
+                hooked(quote! { #hooks::before_patch(#this, &id, &mut patch_record)?; }),
+                unchanged,
+                history_record(quote! { Update }, quote! { #diesel_table::table.find(&id) }),
+            ]
+            .into_iter()
+            .collect(),
+            quote! { #entity },
+            quote! {
+                #run_query_dsl::get_result(
+                    diesel::update(#diesel_table::table.find(id)).set(&patch_record),
+                    conn,
+                )
+            },
+            [
+                hooked(quote! { #hooks::after_save(#this, &record)?; }),
+                outbox_events(quote! { Update }),
+            ]
+            .into_iter()
+            .collect(),
+            quote! { Ok(record) },
+        );
+        let m = quote! {
+            #async_trait
+            impl #trait_impl_generics #traits::Patch<#entity, #id_type, #patch_type> for #self_ty #where_clause {
+                #asyncness fn patch(&self, id: #id_type, #mutability patch_record: #patch_type) -> Result<#entity, diesel::result::Error> {
+                    #body
+                }
+            }
+        };
+        crud_methods.extend(m);
+    }
+    if opts.crud_repo.replace {
+        let body = write(
+            [
+                hooked(quote! { #hooks::before_replace(#this, &mut update_record)?; }),
+                history_record(
+                    quote! { Replace },
+                    quote! { #diesel_table::table.find(diesel::Identifiable::id(&update_record)) },
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            quote! { #entity },
+            quote! {
+                #run_query_dsl::get_result(
+                    diesel::insert_into(#diesel_table::table)
+                        .values(&update_record)
+                        .on_conflict(diesel::Table::primary_key(&#diesel_table::table))
+                        .do_update()
+                        .set(&update_record),
+                    conn,
+                )
+            },
+            [
+                hooked(quote! { #hooks::after_save(#this, &record)?; }),
+                outbox_events(quote! { Replace }),
+            ]
+            .into_iter()
+            .collect(),
+            quote! { Ok(record) },
+        );
+        let m = quote! {
+            #async_trait
+            impl #trait_impl_generics #traits::Replace<#entity, #entity> for #self_ty #where_clause {
+                #asyncness fn replace(&self, #mutability update_record: #entity) -> Result<#entity, diesel::result::Error> {
+                    #body
+                }
+            }
+        };
+        crud_methods.extend(m);
+    }
+    if opts.crud_repo.delete {
+        // The events of a deletion are those of the deleted row, which it returns.
+        let (ty, statement, output) = if outbox.is_some() {
+            (
+                quote! { #entity },
+                quote! { #run_query_dsl::get_result(diesel::delete(#diesel_table::table.find(id)), conn) },
+                quote! { Ok(()) },
+            )
+        } else {
+            (
+                quote! { usize },
+                quote! { #run_query_dsl::execute(diesel::delete(#diesel_table::table.find(id)), conn) },
+                quote! {
+                    if record == 0 {
+                        Err(diesel::result::Error::NotFound)
+                    } else {
+                        Ok(())
+                    }
+                },
+            )
+        };
+        let body = write(
+            [
+                hooked(quote! { #hooks::before_delete(#this, &id)?; }),
+                history_record(quote! { Delete }, quote! { #diesel_table::table.find(&id) }),
+            ]
+            .into_iter()
+            .collect(),
+            ty,
+            statement,
+            outbox_events(quote! { Delete }),
+            output,
+        );
+        let method_fn = quote! {
+            #async_trait
+            impl #trait_impl_generics #traits::Delete<#id_type> for #self_ty #where_clause {
+                #asyncness fn delete(&self, id: #id_type) -> Result<(), diesel::result::Error> {
+                    #body
+                }
+            }
+        };
+        crud_methods.extend(method_fn);
+    }
+    if opts.crud_repo.count {
+        let count_body = run(quote! {
+            #run_query_dsl::get_result(query(#diesel_table::table.into_boxed()).count(), conn)#awaited
+        });
+        let method_fn = quote! {
+            #async_trait
+            impl #trait_impl_generics #traits::Count for #self_ty #where_clause {
+                #asyncness fn count(&self, query: #query_fn) -> Result<i64, diesel::result::Error> {
+                    #count_body
+                }
+            }
+        };
+        crud_methods.extend(method_fn);
+    }
+    if opts.crud_repo.exists {
+        let exists_body = run(quote! {
+            let query = diesel::select(diesel::dsl::exists(#diesel_table::table.find(id)));
+            #run_query_dsl::get_result(query, conn)#awaited
+        });
+        let m = quote! {
+            #async_trait
+            impl #trait_impl_generics #traits::ExistsById<#id_type> for #self_ty #where_clause {
+                #asyncness fn exists_by_id(&self, id: #id_type) -> Result<bool, diesel::result::Error> {
+                    #exists_body
+                }
+            }
+        };
+        crud_methods.extend(m);
+    }
+    if opts.crud_repo.exists_query {
+        let exists_body = run(quote! {
+            let query = query(#diesel_table::table.into_boxed());
+            #run_query_dsl::get_result(diesel::select(diesel::dsl::exists(query)), conn)#awaited
+        });
+        let m = quote! {
+            #async_trait
+            impl #trait_impl_generics #traits::ExistsByQuery for #self_ty #where_clause {
+                #asyncness fn exists_by_query(&self, query: #query_fn) -> Result<bool, diesel::result::Error> {
+                    #exists_body
+                }
+            }
+        };
+        crud_methods.extend(m);
+    }
+    if opts.crud_repo.lock && !context {
+        // Locking selects are implemented on the transaction-bound view of the repository.
+        let mut lock_generics = generics.clone();
+        lock_generics.params.insert(0, syn::parse_quote!('conn));
+        lock_generics.params.insert(0, syn::parse_quote!('repo));
+        lock_generics.params.push(syn::parse_quote!(Conn));
+        let backend = &opts.repo.backend;
+        let predicate: syn::WherePredicate = match flavor {
+            Flavor::Sync => syn::parse_quote! {
+                Conn: diesel::Connection<Backend = #backend> + diesel::connection::LoadConnection
+            },
+            Flavor::Async => syn::parse_quote! {
+                Conn: diesel_repository::diesel_async::AsyncConnection<Backend = #backend> + Send
+            },
+        };
+        lock_generics.make_where_clause().predicates.push(predicate);
+        let (lock_impl_generics, _, lock_where_clause) = lock_generics.split_for_impl();
+        let view = quote! { diesel_repository::InTransaction<'repo, 'conn, #repo_name #ty_generics, Conn> };
+
+        // The lock is taken on the view's connection, in the caller's transaction.
+        let locked = |ty: TokenStream, statement: TokenStream, after: TokenStream| {
+            quote! {
+                let repo = self.repo();
+                let conn = self.conn();
+                #[allow(unused_mut)]
+                let mut record: #ty = #statement #awaited?;
+                #after
+                Ok(record)
+            }
+        };
+        let find_by_id_body = locked(
+            quote! { #entity },
+            quote! {
+                #run_query_dsl::get_result(
+                    diesel_repository::Locked::new(diesel::QueryDsl::find(#diesel_table::table, id), mode),
+                    conn,
+                )
+            },
+            hooked(quote! { #hooks::after_load(repo, &mut record)?; }),
+        );
+        let find_by_query_body = locked(
+            quote! { Vec<#entity> },
+            quote! {
+                #run_query_dsl::load(
+                    diesel_repository::Locked::new(query(diesel::QueryDsl::into_boxed(#diesel_table::table)), mode),
+                    conn,
+                )
+            },
+            hooked(quote! {
+                for item in record.iter_mut() {
+                    #hooks::after_load(repo, item)?;
+                }
+            }),
+        );
+        let m = quote! {
+            #async_trait
+            impl #lock_impl_generics #traits::Lock<#entity, #id_type> for #view #lock_where_clause {
+                #asyncness fn find_by_id_locked(&mut self, id: #id_type, mode: diesel_repository::LockMode) -> Result<#entity, diesel::result::Error> {
+                    #find_by_id_body
+                }
+
+                #asyncness fn find_by_query_locked(
+                        &mut self,
+                        query: #query_fn,
+                        mode: diesel_repository::LockMode) -> Result<Vec<#entity>, diesel::result::Error> {
+                    #find_by_query_body
+                }
+            }
+        };
+        crud_methods.extend(m);
+    }
+
+    if let (Some(history_table), Some(key)) = (history, &history_key) {
+        let history_of = quote! {
+            let query = #history_table::table
+                .filter(#history_table::#key.eq(&id))
+                .order(#history_table::changed_at.asc())
+                .select((
+                    (#(#history_table::#columns,)*),
+                    #history_table::operation,
+                    #history_table::actor,
+                    #history_table::changed_at,
+                ));
+            let rows: Vec<(#entity, String, Option<String>, std::time::SystemTime)> =
+                #run_query_dsl::load(query, conn)#awaited?;
+            rows.into_iter()
+                .map(diesel_repository::HistoryEntry::from_row)
+                .collect::<Result<Vec<_>, diesel::result::Error>>()
+        };
+        let history_of_body = run(history_of.clone());
+        // The history and the current row are read in one transaction, so they agree.
+        let as_of_body = transact(quote! {
+            let entries: Vec<diesel_repository::HistoryEntry<#entity>> = { #history_of }?;
+            let current = diesel::OptionalExtension::optional(
+                #run_query_dsl::get_result(#diesel_table::table.find(id), conn)#awaited,
+            )?;
+            Ok(diesel_repository::HistoryEntry::state_at(entries, current, timestamp))
+        });
+        let m = quote! {
+            #async_trait
+            impl #trait_impl_generics #traits::History<#entity, #id_type> for #self_ty #where_clause {
+                #asyncness fn history_of(&self, id: #id_type) -> Result<Vec<diesel_repository::HistoryEntry<#entity>>, diesel::result::Error> {
+                    #history_of_body
+                }
+
+                #asyncness fn as_of(&self, id: #id_type, timestamp: std::time::SystemTime) -> Result<Option<#entity>, diesel::result::Error> {
+                    #as_of_body
+                }
+            }
+        };
+        crud_methods.extend(m);
+    }
+
+    // With `#[repository(subscribe)]`, async repositories stream the changes of their table.
+    let mut subscribe_methods = TokenStream::new();
+    if opts.repo.subscribe {
+        if opts.repo.mode == Mode::Sync {
+            abort!(
+                struct_name,
+                "`subscribe` needs an async repository";
+                help = "add `mode = \"async\"` or `mode = \"both\"` to #[repository]"
+            );
+        }
+        let table = diesel_table
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string())
+            .unwrap_or_default();
+        let id = match infer::primary_key(&opts.repo.attrs).as_slice() {
+            [id] => id.to_string(),
+            _ => abort!(struct_name, "`subscribe` needs a single-column primary key"),
+        };
+        let channel = format!("{}_changes", table);
+        if flavor == Flavor::Async && !context {
+            subscribe_methods = quote! {
+                #async_trait
+                impl #trait_impl_generics #traits::Subscribe<#entity, #id_type> for #self_ty #where_clause {
+                    const CHANNEL: &'static str = #channel;
+
+                    #asyncness fn install_trigger(&self) -> Result<(), diesel::result::Error> {
+                        let mut connection = #checkout;
+                        diesel_repository::install_notify_trigger(&mut *connection, #table, #id, Self::CHANNEL).await
+                    }
+
+                    #asyncness fn changes(
+                        &self,
+                        database_url: &str,
+                    ) -> Result<diesel_repository::ChangeStream<#entity, #id_type>, diesel::result::Error> {
+                        let load: diesel_repository::LoadChanged<#entity, #id_type> = |conn, id| {
+                            Box::pin(async move {
+                                let query = diesel::QueryDsl::find(#diesel_table::table, id);
+                                diesel::OptionalExtension::optional(
+                                    #run_query_dsl::get_result(query, conn).await,
+                                )
+                            })
+                        };
+                        let listener = diesel_repository::ChangeListener::connect(database_url, Self::CHANNEL).await?;
+                        Ok(listener.changes(load))
+                    }
+                }
+            };
+        }
+    }
+
+    // Paged methods validate their arguments against `#[paging_repo(...)]` before querying.
+    let page_limit = |limit: &Option<syn::LitInt>| match limit {
+        Some(n) => quote! { Some(#n) },
+        None => quote! { None },
+    };
+    let default_per_page = page_limit(&opts.paging_repo.default_per_page);
+    let max_per_page = page_limit(&opts.paging_repo.max_per_page);
+    let page_request = quote! {
+        let diesel_repository::PageRequest { page, per_page } =
+            diesel_repository::PageRequest::try_with_limits(page, per_page, #default_per_page, #max_per_page)?;
+    };
+
+    // With `count_strategy = "none"`, pages are `Slice`s served by the `*Slice` traits.
+    let count_strategy = opts.paging_repo.count_strategy;
+    let (page_type, page_trait, paging) = match count_strategy {
+        CountStrategy::None => (quote! { diesel_repository::Slice }, "Slice", "slice"),
+        _ => (quote! { diesel_repository::Paged }, "Paged", "paged"),
+    };
+    // Estimated totals come from the table's statistics or the plan of the refined query.
+    let (estimate_count, estimate_query_count) = match flavor {
+        Flavor::Sync => (
+            quote! { diesel_repository::estimate_count },
+            quote! { diesel_repository::estimate_query_count },
+        ),
+        Flavor::Async => (
+            quote! { diesel_repository::estimate_count_async },
+            quote! { diesel_repository::estimate_query_count_async },
+        ),
+    };
+    let table_name = diesel_table
+        .segments
+        .last()
+        .map(|segment| segment.ident.to_string())
+        .unwrap_or_default();
+    // Loads one page of the table, or of the boxed query `refined` by the caller, into
+    // `record`. Pages of a projection select only its `selection` and skip `after_load`,
+    // which takes the entity.
+    let page_body = |strategy: CountStrategy,
+                     refined: bool,
+                     selection: Option<TokenStream>|
+     -> TokenStream {
+        let base = if refined {
+            quote! { query(#diesel_table::table.into_boxed()) }
+        } else {
+            quote! { #diesel_table::table }
+        };
+        let (columns, select) = match &selection {
+            Some(selection) => (selection.clone(), quote! { .select(#selection) }),
+            None => (quote! { #diesel_table::all_columns }, TokenStream::new()),
+        };
+        let load_page = match strategy {
+            CountStrategy::Separate => quote! {
+                let total_count: i64 = #run_query_dsl::get_result(#base.count(), conn)#awaited?;
+                let items = #run_query_dsl::load(#base #select .limit(per_page).offset(offset), conn)#awaited?;
+                #[allow(unused_mut)]
+                let mut record = diesel_repository::Paged { items, total_count, page, per_page };
+            },
+            CountStrategy::Window => quote! {
+                let total = diesel::dsl::sql::<diesel::sql_types::BigInt>("COUNT(*) OVER ()");
+                let page_query = #base
+                    .select((#columns, total))
+                    .limit(per_page)
+                    .offset(offset);
+                let rows = #run_query_dsl::load(page_query, conn)#awaited?;
+                let mut record = diesel_repository::Paged::from_window(rows, page, per_page);
+                // A page past the end has no row carrying the total, so count separately.
+                if record.items.is_empty() && page > 1 {
+                    record.total_count = #run_query_dsl::get_result(#base.count(), conn)#awaited?;
+                }
+            },
+            CountStrategy::Estimate => {
+                let total_count = if refined {
+                    quote! { #estimate_query_count(conn, #base)#awaited? }
+                } else {
+                    quote! { #estimate_count(conn, #table_name)#awaited? }
+                };
+                quote! {
+                    let total_count = #total_count;
+                    let items = #run_query_dsl::load(#base #select .limit(per_page).offset(offset), conn)#awaited?;
+                    #[allow(unused_mut)]
+                    let mut record = diesel_repository::Paged { items, total_count, page, per_page };
+                }
+            }
+            CountStrategy::None => quote! {
+                let page_query = #base #select .limit(per_page.saturating_add(1)).offset(offset);
+                let items = #run_query_dsl::load(page_query, conn)#awaited?;
+                #[allow(unused_mut)]
+                let mut record = diesel_repository::Slice::from_overfetch(items, page, per_page);
+            },
+        };
+        let after_load = match selection {
+            Some(_) => TokenStream::new(),
+            None => hooked(quote! {
+                for item in record.items.iter_mut() {
+                    #hooks::after_load(#this, item)?;
+                }
+            }),
+        };
+        run(quote! {
+            let offset = (page - 1) * per_page;
+            #load_page
+            #after_load
+            Ok(record)
+        })
+    };
+
+    // Repositories paging into `Slice`s keep the `*Paged` traits with a separate count, so
+    // they remain a `PagingAndSortingRepository`.
+    let mut paging_methods = TokenStream::new();
+    let mut strategies = vec![count_strategy];
+    if count_strategy == CountStrategy::None {
+        strategies.push(CountStrategy::Separate);
+    }
+    for strategy in strategies {
+        let (page_type, page_trait, paging) = match strategy {
+            CountStrategy::None => (quote! { diesel_repository::Slice }, "Slice", "slice"),
+            _ => (quote! { diesel_repository::Paged }, "Paged", "paged"),
+        };
+        if opts.paging_repo.find_query {
+            let find_query_trait = format_ident!("FindByQuery{}", page_trait);
+            let find_by_query_page = format_ident!("find_by_query_{}", paging);
+            let body = page_body(strategy, true, None);
+            let m = quote! {
+                #async_trait
+                impl #trait_impl_generics #traits::#find_query_trait<#entity> for #self_ty #where_clause {
+                    #asyncness fn #find_by_query_page(
+                            &self,
+                            query: #query_fn,
+                            page: i64,
+                            per_page: i64) -> Result<#page_type<#entity>, diesel::result::Error> {
+                        #page_request
+                        #body
+                    }
+                }
+            };
+            paging_methods.extend(m);
+        }
+        if opts.paging_repo.find_all {
+            let find_all_trait = format_ident!("FindAll{}", page_trait);
+            let find_all_page = format_ident!("find_all_{}", paging);
+            let body = page_body(strategy, false, None);
+            let m = quote! {
+                #async_trait
+                impl #trait_impl_generics #traits::#find_all_trait<#entity> for #self_ty #where_clause {
+                    #asyncness fn #find_all_page(&self, page: i64, per_page: i64) -> Result<#page_type<#entity>, diesel::result::Error> {
+                        #page_request
+                        #body
+                    }
+                }
+            };
+            paging_methods.extend(m);
+        }
+    }
+
+    // Batch inputs are copied for the `before_*` hooks, which take them mutably.
+    let before_save_batch = hooked(quote! {
+        let mut new_records = new_records.to_vec();
+        for new_record in new_records.iter_mut() {
+            #hooks::before_save(#this, new_record)?;
+        }
+    });
+    let before_update_batch = hooked(quote! {
+        let mut update_records = update_records.to_vec();
+        for update_record in update_records.iter_mut() {
+            #hooks::before_update(#this, update_record)?;
+        }
+    });
+    let before_delete_batch = hooked(quote! {
+        for id in ids {
+            #hooks::before_delete(#this, id)?;
+        }
+    });
+    let after_save_batch = hooked(quote! {
+        for item in record.iter() {
+            #hooks::after_save(#this, item)?;
+        }
+    });
+    let history_update = history_record(
+        quote! { Update },
+        quote! { #diesel_table::table.find(diesel::Identifiable::id(update_record)) },
+    );
+    let history_delete = history_record(
+        quote! { Delete },
+        quote! { #diesel_table::table.filter(diesel::Table::primary_key(&#diesel_table::table).eq_any(ids)) },
+    );
+
+    // Batch writes run record by record in one transaction, so a rejected or failing record
+    // rolls back the whole batch.
+    let mut batch_methods = TokenStream::new();
+    if opts.batch_repo.find {
+        let body = read(
+            quote! { Vec<#entity> },
+            quote! {
+                #run_query_dsl::load(
+                    #diesel_table::table.filter(diesel::Table::primary_key(&#diesel_table::table).eq_any(ids)),
+                    conn,
+                )
+            },
+            load_all.clone(),
+        );
+        let m = quote! {
+            #async_trait
+            impl #trait_impl_generics #traits::FindByIdBatch<#entity, #id_type> for #self_ty #where_clause {
+                #asyncness fn find_by_id_batch(&self, ids: &[#id_type]) -> Result<Vec<#entity>, diesel::result::Error> {
+                    if ids.is_empty() {
+                        return Ok(Vec::new());
+                    }
+                    #body
+                }
+            }
+        };
+        batch_methods.extend(m);
+    }
+    if opts.batch_repo.save {
+        let body = transact(quote! {
+            #before_save_batch
+            let mut record: Vec<#entity> = Vec::with_capacity(new_records.len());
+            for new_record in new_records.iter() {
+                let saved = #run_query_dsl::get_result(
+                    diesel::insert_into(#diesel_table::table).values(new_record),
+                    conn,
+                )#awaited?;
+                record.push(saved);
+            }
+            #after_save_batch
+            Ok(record)
+        });
+        let m = quote! {
+            #async_trait
+            impl #trait_impl_generics #traits::SaveBatch<#entity, #new_type> for #self_ty #where_clause {
+                #asyncness fn save_batch(&self, new_records: &[#new_type]) -> Result<Vec<#entity>, diesel::result::Error> {
+                    if new_records.is_empty() {
+                        return Ok(Vec::new());
+                    }
+                    #body
+                }
+            }
+        };
+        batch_methods.extend(m);
+    }
+    if opts.batch_repo.update {
+        let body = transact(quote! {
+            #before_update_batch
+            let mut record: Vec<#entity> = Vec::with_capacity(update_records.len());
+            for update_record in update_records.iter() {
+                #history_update
+                let updated = #run_query_dsl::get_result(
+                    diesel::update(update_record).set(update_record),
+                    conn,
+                )#awaited?;
+                record.push(updated);
+            }
+            #after_save_batch
+            Ok(record)
+        });
+        let m = quote! {
+            #async_trait
+            impl #trait_impl_generics #traits::UpdateBatch<#entity, #update_type> for #self_ty #where_clause {
+                #asyncness fn update_batch(&self, update_records: &[#update_type]) -> Result<Vec<#entity>, diesel::result::Error> {
+                    #body
+                }
+            }
+        };
+        batch_methods.extend(m);
+    }
+    if opts.batch_repo.delete {
+        let body = transact(quote! {
+            #before_delete_batch
+            #history_delete
+            let query = diesel::delete(
+                #diesel_table::table.filter(diesel::Table::primary_key(&#diesel_table::table).eq_any(ids)),
+            );
+            #run_query_dsl::execute(query, conn)#awaited?;
+            Ok(())
+        });
+        let m = quote! {
+            #async_trait
+            impl #trait_impl_generics #traits::DeleteBatch<#id_type> for #self_ty #where_clause {
+                #asyncness fn delete_batch(&self, ids: &[#id_type]) -> Result<(), diesel::result::Error> {
+                    if ids.is_empty() {
+                        return Ok(());
+                    }
+                    #body
+                }
+            }
+        };
+        batch_methods.extend(m);
+    }
+    if opts.batch_repo.exists {
+        let exists_body = run(quote! {
+            let key = diesel::Table::primary_key(&#diesel_table::table);
+            let query = #diesel_table::table.filter(key.eq_any(ids)).select(key);
+            #run_query_dsl::load(query, conn)#awaited
+        });
+        let m = quote! {
+            #async_trait
+            impl #trait_impl_generics #traits::ExistsByIdBatch<#id_type> for #self_ty #where_clause {
+                #asyncness fn exists_by_id_batch(&self, ids: &[#id_type]) -> Result<Vec<#id_type>, diesel::result::Error> {
+                    if ids.is_empty() {
+                        return Ok(Vec::new());
+                    }
+                    #exists_body
+                }
+            }
+        };
+        batch_methods.extend(m);
+    }
+
+    // `has_many` loaders go through the related entity's `BelongsTo` impl, as derived by
+    // `#[derive(Associations)]`, and match the loaded records to their parents with `grouped_by`.
+    let mut association_methods = TokenStream::new();
+    for association in &opts.repo.has_many.0 {
+        let name = &association.name;
+        let related_repo = &association.repo;
+        if let Some(foreign_key) = &association.foreign_key {
+            let help = format!(
+                "declare it with `#[diesel(belongs_to({}, foreign_key = {}))]` on the related entity",
+                struct_name, foreign_key
+            );
+            abort!(
+                foreign_key,
+                "`has_many` takes the foreign key from the related entity's `BelongsTo` impl";
+                help = help
+            );
+        }
+        let related = quote! { <#related_repo as diesel_repository::Repository>::Entity };
+        let find_with = format_ident!("find_with_{}{}", name, suffix);
+        let load_for = format_ident!("load_{}_for{}", name, suffix);
+        let find_with_doc = format!(
+            "Loads the record with the given ID together with its `{}`.",
+            name
+        );
+        let load_for_doc = format!(
+            "Loads the `{}` of every given record in one query, grouped in the same order as `parents`.",
+            name
+        );
+        let find_with_body = run(quote! {
+            #[allow(unused_mut)]
+            let mut record: #entity = #run_query_dsl::get_result(#diesel_table::table.find(id), conn)#awaited?;
+            #load_one
+            let query = <#related as diesel::BelongingToDsl<&#entity>>::belonging_to(&record);
+            let related: Vec<#related> = #run_query_dsl::load(query, conn)#awaited?;
+            Ok((record, related))
+        });
+        let load_for_body = run(quote! {
+            let query = <#related as diesel::BelongingToDsl<&[#entity]>>::belonging_to(parents);
+            let related: Vec<#related> = #run_query_dsl::load(query, conn)#awaited?;
+            Ok(diesel::GroupedBy::grouped_by(related, parents))
+        });
+        let m = quote! {
+            #[doc = #find_with_doc]
+            pub #asyncness fn #find_with(
+                &self,
+                id: #id_type,
+            ) -> Result<(#entity, Vec<#related>), diesel::result::Error> {
+                #find_with_body
+            }
+
+            #[doc = #load_for_doc]
+            pub #asyncness fn #load_for(
+                &self,
+                parents: &[#entity],
+            ) -> Result<Vec<Vec<#related>>, diesel::result::Error> {
+                if parents.is_empty() {
+                    return Ok(Vec::new());
+                }
+                #load_for_body
+            }
+        };
+        association_methods.extend(m);
+    }
+    for association in &opts.repo.belongs_to.0 {
+        let name = &association.name;
+        let related_repo = &association.repo;
+        let foreign_key = association
+            .foreign_key
+            .clone()
+            .unwrap_or_else(|| format_ident!("{}_id", name));
+        // Tuple structs have no column names to check against; Diesel reports those.
+        if !opts.repo.columns.is_empty() && !opts.repo.columns.contains(&foreign_key) {
+            let help =
+                match diagnostics::suggest_column(&foreign_key.to_string(), &opts.repo.columns) {
+                    Some(column) => format!("did you mean `{}`?", column),
+                    None => format!(
+                        "declare the column with `belongs_to({}(repo = ..., foreign_key = ...))`",
+                        name
+                    ),
+                };
+            let span = association.foreign_key.as_ref().unwrap_or(name);
+            abort!(
+                span,
+                "`{}` has no column `{}` referencing `{}`", struct_name, foreign_key, name;
+                help = help
+            );
+        }
+        let find_by = format_ident!("find_by_{}{}", foreign_key, suffix);
+        let find_by_doc = format!("Loads all records belonging to the given `{}`.", name);
+        let find_by_body = run(quote! {
+            let query = #diesel_table::table.filter(#diesel_table::#foreign_key.eq(#foreign_key));
+            #run_query_dsl::load(query, conn)#awaited
+        });
+        let m = quote! {
+            #[doc = #find_by_doc]
+            pub #asyncness fn #find_by(
+                &self,
+                #foreign_key: <#related_repo as diesel_repository::Repository>::Id,
+            ) -> Result<Vec<#entity>, diesel::result::Error> {
+                #find_by_body
+            }
+        };
+        association_methods.extend(m);
+    }
+
+    let mut sql_methods = TokenStream::new();
+    for custom in &opts.repo_sql {
+        let name = format_ident!("{}{}", custom.name, suffix);
+        let sql = &custom.sql;
+        let arg_names: Vec<_> = custom.args.0.iter().map(|arg| &arg.name).collect();
+        let arg_types: Vec<_> = custom.args.0.iter().map(|arg| &arg.ty).collect();
+        let sql_types: Vec<_> = custom.args.0.iter().map(|arg| &arg.sql_type).collect();
+        let row = match &custom.row {
+            Some(path) => quote! { #path },
+            None => entity.clone(),
+        };
+        let output = match custom.returns {
+            SqlReturns::Vec => quote! { Vec<#row> },
+            SqlReturns::Option => quote! { Option<#row> },
+            SqlReturns::One => quote! { #row },
+            SqlReturns::Execute => quote! { usize },
+        };
+        let statement = match custom.returns {
+            SqlReturns::Vec => quote! { #run_query_dsl::load(query, conn) },
+            SqlReturns::Option => quote! {
+                diesel::OptionalExtension::optional(#run_query_dsl::get_result(query, conn)#awaited)
+            },
+            SqlReturns::One => quote! { #run_query_dsl::get_result(query, conn) },
+            SqlReturns::Execute => quote! { #run_query_dsl::execute(query, conn) },
+        };
+        let statement = match custom.returns {
+            SqlReturns::Option => statement,
+            _ => quote! { #statement #awaited },
+        };
+        let body = run(quote! {
+            let query = diesel::sql_query(#sql)#(.bind::<#sql_types, _>(#arg_names))*;
+            #statement
+        });
+        let doc = format!("Runs `{}`.", sql.value());
+        let m = quote! {
+            #[doc = #doc]
+            pub #asyncness fn #name(&self, #(#arg_names: #arg_types),*) -> Result<#output, diesel::result::Error> {
+                #body
+            }
+        };
+        sql_methods.extend(m);
+    }
+
+    // With `#[repository(session_context)]`, the repository hands out views applying a context.
+    let mut with_context = TokenStream::new();
+    let mut context_view = TokenStream::new();
+    if opts.repo.session_context {
+        let context_doc = format!(
+            "A [`{}`] applying a [`SessionContext`](diesel_repository::SessionContext) in every transaction it runs.",
+            repo_name
+        );
+        with_context = quote! {
+            /// A view of the repository applying `context` in every transaction it runs.
+            pub fn with_context<'ctx>(&'ctx self, context: diesel_repository::SessionContext) -> #context_name #context_ty_generics {
+                #context_name { repo: self, context }
+            }
+        };
+        let context_query_repository = if query_repository.is_empty() {
+            TokenStream::new()
+        } else {
+            quote! {
+                impl #context_impl_generics diesel_repository::QueryRepository for #context_name #context_ty_generics #where_clause {
+                    type Query = <#repo_name #ty_generics as diesel_repository::QueryRepository>::Query;
+                }
+            }
+        };
+        context_view = quote! {
+            #[doc = #context_doc]
+            #repo_vis struct #context_name #context_generics #where_clause {
+                repo: &'ctx #repo_name #ty_generics,
+                context: diesel_repository::SessionContext,
+            }
+
+            impl #context_impl_generics #context_name #context_ty_generics #where_clause {
+                /// The repository this view was created from.
+                pub fn repo(&self) -> &'ctx #repo_name #ty_generics {
+                    self.repo
+                }
+
+                /// The settings applied in every transaction of this view.
+                pub fn context(&self) -> &diesel_repository::SessionContext {
+                    &self.context
+                }
+            }
+
+            impl #context_impl_generics diesel_repository::Repository for #context_name #context_ty_generics #where_clause {
+                type Entity = #entity;
+                type Id = #id_type;
+            }
+
+            #context_query_repository
+        };
+    }
+
+    // With `#[repository(queue(...))]`, the repository works through its table as a job queue.
+    let mut queue_const = TokenStream::new();
+    let mut queue_methods = TokenStream::new();
+    if let Some(queue) = &opts.repo.queue {
+        let table = diesel_table
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string())
+            .unwrap_or_default();
+        let id = queue
+            .id
+            .as_ref()
+            .map_or_else(|| "id".to_string(), |id| id.to_string());
+        let status = queue.status.to_string();
+        let run_at = queue.run_at.to_string();
+        let attempts = queue.attempts.to_string();
+        queue_const = quote! {
+            /// The queue table and columns the job-queue methods work on.
+            pub const QUEUE: diesel_repository::QueueTable = diesel_repository::QueueTable {
+                table: #table,
+                id: #id,
+                status: #status,
+                run_at: #run_at,
+                attempts: #attempts,
+            };
+        };
+
+        let enqueue = format_ident!("enqueue{}", suffix);
+        let dequeue = format_ident!("dequeue{}", suffix);
+        let complete = format_ident!("complete{}", suffix);
+        let fail_with_backoff = format_ident!("fail_with_backoff{}", suffix);
+        let reap_stuck = format_ident!("reap_stuck{}", suffix);
+        let id_column = queue.id.clone().unwrap_or_else(|| format_ident!("id"));
+        let id_sql_type = quote! { diesel::dsl::SqlTypeOf<#diesel_table::#id_column> };
+        let queue_table = quote! { <#repo_name #ty_generics>::QUEUE };
+        let enqueue_body = run(quote! {
+            #run_query_dsl::get_result(
+                diesel::insert_into(#diesel_table::table).values(&new_record),
+                conn,
+            )#awaited
+        });
+        let dequeue_body = run(quote! {
+            let query = diesel::sql_query(#queue_table.dequeue_sql())
+                .bind::<diesel::sql_types::BigInt, _>(n);
+            #run_query_dsl::load(query, conn)#awaited
+        });
+        let complete_body = run(quote! {
+            let query = diesel::sql_query(#queue_table.complete_sql()).bind::<#id_sql_type, _>(id);
+            match #run_query_dsl::execute(query, conn)#awaited? {
+                0 => Err(diesel::result::Error::NotFound),
+                _ => Ok(()),
+            }
+        });
+        let fail_with_backoff_body = run(quote! {
+            let query = diesel::sql_query(#queue_table.fail_sql())
+                .bind::<#id_sql_type, _>(id)
+                .bind::<diesel::sql_types::Integer, _>(backoff.max_attempts)
+                .bind::<diesel::sql_types::Double, _>(diesel_repository::QueueTable::millis(backoff.base))
+                .bind::<diesel::sql_types::Double, _>(diesel_repository::QueueTable::millis(backoff.max_delay));
+            #run_query_dsl::get_result(query, conn)#awaited
+        });
+        let reap_stuck_body = run(quote! {
+            let query = diesel::sql_query(#queue_table.reap_sql())
+                .bind::<diesel::sql_types::Double, _>(diesel_repository::QueueTable::millis(older_than))
+                .bind::<diesel::sql_types::Integer, _>(max_attempts);
+            #run_query_dsl::execute(query, conn)#awaited
+        });
+        let m = quote! {
+            /// Inserts a job; the table's defaults make it pending and due now.
+            pub #asyncness fn #enqueue(&self, new_record: #new_type) -> Result<#entity, diesel::result::Error> {
+                #enqueue_body
+            }
+
+            /// Claims up to `n` due jobs with `FOR UPDATE SKIP LOCKED`, so concurrent workers
+            /// never claim the same job. Claimed jobs are running and count one more attempt.
+            pub #asyncness fn #dequeue(&self, n: i64) -> Result<Vec<#entity>, diesel::result::Error> {
+                #dequeue_body
+            }
+
+            /// Marks a running job as done, failing with `NotFound` if it is not running.
+            pub #asyncness fn #complete(&self, id: #id_type) -> Result<(), diesel::result::Error> {
+                #complete_body
+            }
+
+            /// Reschedules a running job after a failed attempt, or marks it as failed once
+            /// `backoff` gives up on it.
+            pub #asyncness fn #fail_with_backoff(
+                &self,
+                id: #id_type,
+                backoff: &diesel_repository::Backoff,
+            ) -> Result<#entity, diesel::result::Error> {
+                #fail_with_backoff_body
+            }
+
+            /// Makes jobs running for longer than `older_than` due again, e.g. after their
+            /// worker crashed, and returns how many were reaped. A reaped job that already
+            /// used `max_attempts` attempts is marked as failed instead.
+            pub #asyncness fn #reap_stuck(
+                &self,
+                older_than: std::time::Duration,
+                max_attempts: i32,
+            ) -> Result<usize, diesel::result::Error> {
+                #reap_stuck_body
+            }
+        };
+        queue_methods.extend(m);
+    }
+
+    // Projections load any `Selectable` of the same table, selecting only its columns.
+    let mut projection_methods = TokenStream::new();
+    let mut projection_shortcuts = TokenStream::new();
+    let project_page = format_ident!("Project{}", page_trait);
+    let find_all_as_page = format_ident!("find_all_as_{}", paging);
+    let find_by_query_as_page = format_ident!("find_by_query_as_{}", paging);
+    let backend = &opts.repo.backend;
+    for projection in opts.repo.projections.iter() {
+        let selection = quote! { <#projection as diesel::SelectableHelper<#backend>>::as_select() };
+        let find_all_as_body = run(quote! {
+            #run_query_dsl::load(#diesel_table::table.select(#selection), conn)#awaited
+        });
+        let find_by_query_as_body = run(quote! {
+            let query = query(#diesel_table::table.into_boxed()).select(#selection);
+            #run_query_dsl::load(query, conn)#awaited
+        });
+        let find_all_as_page_body = page_body(count_strategy, false, Some(selection.clone()));
+        let find_by_query_as_page_body = page_body(count_strategy, true, Some(selection));
+        let m = quote! {
+            #async_trait
+            impl #trait_impl_generics #traits::Project<#projection> for #self_ty #where_clause {
+                #asyncness fn find_all_as(&self) -> Result<Vec<#projection>, diesel::result::Error> {
+                    #find_all_as_body
+                }
+
+                #asyncness fn find_by_query_as(&self, query: #query_fn) -> Result<Vec<#projection>, diesel::result::Error> {
+                    #find_by_query_as_body
+                }
+            }
+
+            #async_trait
+            impl #trait_impl_generics #traits::#project_page<#projection> for #self_ty #where_clause {
+                #asyncness fn #find_all_as_page(&self, page: i64, per_page: i64) -> Result<#page_type<#projection>, diesel::result::Error> {
+                    #page_request
+                    #find_all_as_page_body
+                }
+
+                #asyncness fn #find_by_query_as_page(
+                        &self,
+                        query: #query_fn,
+                        page: i64,
+                        per_page: i64) -> Result<#page_type<#projection>, diesel::result::Error> {
+                    #page_request
+                    #find_by_query_as_page_body
+                }
+            }
+        };
+        projection_methods.extend(m);
+    }
+    if !opts.repo.projections.is_empty() {
+        // Inherent generic shortcuts, so that `repo.find_all_as::<AccountSummary>()` works.
+        let find_all_as = format_ident!("find_all_as{}", suffix);
+        let find_by_query_as = format_ident!("find_by_query_as{}", suffix);
+        let find_all_as_paged = format_ident!("{}{}", find_all_as_page, suffix);
+        let find_by_query_as_paged = format_ident!("{}{}", find_by_query_as_page, suffix);
+        let m = quote! {
+            /// Returns all records projected into `P`.
+            pub #asyncness fn #find_all_as<P>(&self) -> Result<Vec<P>, diesel::result::Error>
+            where
+                Self: #traits::Project<P>,
+            {
+                #traits::Project::<P>::find_all_as(self)#awaited
+            }
+
+            /// Executes the query and returns the matching records projected into `P`.
+            pub #asyncness fn #find_by_query_as<P>(&self, query: #query_fn) -> Result<Vec<P>, diesel::result::Error>
+            where
+                Self: #traits::Project<P>,
+            {
+                #traits::Project::<P>::find_by_query_as(self, query)#awaited
+            }
+
+            /// Returns all records projected into `P`, paginated.
+            pub #asyncness fn #find_all_as_paged<P>(&self, page: i64, per_page: i64) -> Result<#page_type<P>, diesel::result::Error>
+            where
+                Self: #traits::#project_page<P>,
+            {
+                #traits::#project_page::<P>::#find_all_as_page(self, page, per_page)#awaited
+            }
+
+            /// Executes the query with pagination and returns the records projected into `P`.
+            pub #asyncness fn #find_by_query_as_paged<P>(
+                &self,
+                query: #query_fn,
+                page: i64,
+                per_page: i64,
+            ) -> Result<#page_type<P>, diesel::result::Error>
+            where
+                Self: #traits::#project_page<P>,
+            {
+                #traits::#project_page::<P>::#find_by_query_as_page(self, query, page, per_page)#awaited
+            }
+        };
+        projection_shortcuts.extend(m);
+    }
+
+    // Extra fields are passed to `new` in declaration order, or set through the builder.
+    let extra_fields = &opts.repo.fields.0;
+    let field_names: Vec<_> = extra_fields
+        .iter()
+        .filter_map(|f| f.ident.as_ref())
+        .collect();
+    let field_types: Vec<_> = extra_fields.iter().map(|f| &f.ty).collect();
+    let field_missing: Vec<_> = field_names.iter().map(|name| name.to_string()).collect();
+    let builder_doc = format!("Builds a [`{}`] field by field.", repo_name);
+
+    // Assemble the final repository implementation.
+    let declaration = quote! {
+        #(#[derive(#repo_derives)])*
+        #repo_vis struct #repo_name #generics #where_clause {
+            pool: std::sync::Arc<#pool_type>,
+            _entity: std::marker::PhantomData<fn() -> #entity>,
+            #(#extra_fields,)*
+        }
+
+        #[doc = #builder_doc]
+        #repo_vis struct #builder_name #generics #where_clause {
+            pool: Option<std::sync::Arc<#pool_type>>,
+            _entity: std::marker::PhantomData<fn() -> #entity>,
+            #(#field_names: Option<#field_types>,)*
+        }
+
+        impl #impl_generics Default for #builder_name #ty_generics #where_clause {
+            fn default() -> Self {
+                Self {
+                    pool: None,
+                    _entity: std::marker::PhantomData,
+                    #(#field_names: None,)*
+                }
+            }
+        }
+
+        impl #impl_generics #builder_name #ty_generics #where_clause {
+            pub fn pool(mut self, pool: std::sync::Arc<#pool_type>) -> Self {
+                self.pool = Some(pool);
+                self
+            }
+
+            #(
+                pub fn #field_names(mut self, #field_names: #field_types) -> Self {
+                    self.#field_names = Some(#field_names);
+                    self
+                }
+            )*
+
+            /// Creates the repository, failing on the first field that was not set.
+            pub fn build(self) -> Result<#repo_name #ty_generics, diesel_repository::BuildError> {
+                Ok(#repo_name {
+                    pool: self.pool.ok_or(diesel_repository::BuildError { field: "pool" })?,
+                    _entity: std::marker::PhantomData,
+                    #(#field_names: self.#field_names.ok_or(diesel_repository::BuildError { field: #field_missing })?,)*
+                })
+            }
+        }
+
+        impl #impl_generics #repo_name #ty_generics #where_clause {
+            pub fn new(pool: std::sync::Arc<#pool_type>, #(#field_names: #field_types),*) -> Self {
+                Self { pool, _entity: std::marker::PhantomData, #(#field_names),* }
+            }
+
+            pub fn builder() -> #builder_name #ty_generics {
+                #builder_name::default()
+            }
+
+            #queue_const
+
+            #with_context
+        }
+
+        impl #impl_generics diesel_repository::Repository for #repo_name #ty_generics #where_clause {
+            type Entity = #entity;
+            type Id = #id_type;
+        }
+
+        #query_repository
+
+        #schema_check
+
+        #record_checks
+
+        #context_view
+    };
+
+    let mut expanded = if declare {
+        declaration
+    } else {
+        TokenStream::new()
+    };
+    let inherent_methods = quote! {
+        #association_methods
+
+        #sql_methods
+
+        #queue_methods
+
+        #projection_shortcuts
+    };
+    if !inherent_methods.is_empty() {
+        expanded.extend(quote! {
+            impl #trait_impl_generics #self_ty #where_clause {
+                #inherent_methods
+            }
+        });
+    }
+    expanded.extend(quote! {
+        #crud_methods
+
+        #paging_methods
+
+        #batch_methods
+
+        #projection_methods
+
+        #subscribe_methods
+    });
+
+    Ok(expanded)
+}
@@ -0,0 +1,214 @@
+use darling::ast::NestedMeta;
+
+/// Flags and keys accepted by each attribute of `#[derive(Repo)]`.
+pub(crate) const REPOSITORY: &[&str] = &[
+    "pool",
+    "table_name",
+    "backend",
+    "has_many",
+    "belongs_to",
+    "projections",
+    "hooks",
+    "outbox",
+    "history",
+    "queue",
+    "schema_check",
+    "session_context",
+    "subscribe",
+    "mode",
+    "name",
+    "vis",
+    "derive",
+    "fields",
+];
+pub(crate) const REPO_TYPE: &[&str] = &["id_type", "new_type", "update_type", "patch_type"];
+pub(crate) const CRUD_REPO: &[&str] = &[
+    "find_one",
+    "find_one_query",
+    "find_query",
+    "find_all",
+    "save",
+    "update",
+    "patch",
+    "replace",
+    "delete",
+    "count",
+    "exists",
+    "exists_query",
+    "lock",
+];
+pub(crate) const PAGING_REPO: &[&str] = &[
+    "find_query",
+    "find_all",
+    "count_strategy",
+    "max_per_page",
+    "default_per_page",
+];
+pub(crate) const REPO_SQL: &[&str] = &["name", "sql", "args", "returns", "row"];
+pub(crate) const BATCH_REPO: &[&str] = &["find", "save", "update", "delete", "exists"];
+
+/// Names commonly used by other repository frameworks, mapped to the flag doing the same here.
+const ALIASES: &[(&str, &str)] = &[
+    ("insert", "save"),
+    ("create", "save"),
+    ("persist", "save"),
+    ("find_by_id", "find_one"),
+    ("get", "find_one"),
+    ("find", "find_one"),
+    ("find_by_query", "find_query"),
+    ("find_one_by_query", "find_one_query"),
+    ("remove", "delete"),
+    ("upsert", "replace"),
+    ("exists_by_id", "exists"),
+    ("exists_by_query", "exists_query"),
+    ("find_all_paged", "find_all"),
+    ("find_by_query_paged", "find_query"),
+    ("find_by_id_batch", "find"),
+    ("table", "table_name"),
+    ("id", "id_type"),
+];
+
+/// Attributes that look like ours but are not registered, with the supported spelling.
+const UNREGISTERED: &[(&str, &str)] = &[
+    ("repo_table_name", "#[repository(table_name = ...)]"),
+    ("table_name", "#[repository(table_name = ...)]"),
+    ("pool", "#[repository(pool = ...)]"),
+    ("id_type", "#[repo_type(id_type = ...)]"),
+    ("new_type", "#[repo_type(new_type = ...)]"),
+    ("update_type", "#[repo_type(update_type = ...)]"),
+    ("patch_type", "#[repo_type(patch_type = ...)]"),
+    ("crud", "#[crud_repo(...)]"),
+    ("paging", "#[paging_repo(...)]"),
+    ("batch", "#[batch_repo(...)]"),
+];
+
+/// Checks the `#[derive(Repo)]` attributes before they are parsed, reporting unknown flags
+/// on their own span with a suggestion, and misspelled attributes with the supported form.
+pub fn validate_attributes(attrs: &[syn::Attribute]) -> darling::Result<()> {
+    let mut errors = darling::Error::accumulator();
+
+    for attr in attrs {
+        let Some(name) = attr.path().get_ident().map(|ident| ident.to_string()) else {
+            continue;
+        };
+
+        let known: &'static [&'static str] = match name.as_str() {
+            "repository" => REPOSITORY,
+            "repo_type" => REPO_TYPE,
+            "crud_repo" => CRUD_REPO,
+            "paging_repo" => PAGING_REPO,
+            "batch_repo" => BATCH_REPO,
+            "repo_sql" => REPO_SQL,
+            _ => {
+                if let Some((_, replacement)) = UNREGISTERED.iter().find(|(n, _)| *n == name) {
+                    errors.push(
+                        darling::Error::custom(format!(
+                            "`#[{}]` is not an attribute of #[derive(Repo)]; use `{}` instead",
+                            name, replacement
+                        ))
+                        .with_span(&attr.path()),
+                    );
+                }
+                continue;
+            }
+        };
+
+        let Some(items) = errors.handle(list_items(attr)) else {
+            continue;
+        };
+        for item in items {
+            let path = match &item {
+                NestedMeta::Meta(meta) => meta.path(),
+                NestedMeta::Lit(lit) => {
+                    errors.push(
+                        darling::Error::custom(format!(
+                            "unexpected literal in #[{}]; expected one of {}",
+                            name,
+                            expected(known)
+                        ))
+                        .with_span(lit),
+                    );
+                    continue;
+                }
+            };
+            let flag = path
+                .get_ident()
+                .map(|ident| ident.to_string())
+                .unwrap_or_else(|| quote_path(path));
+            if known.contains(&flag.as_str()) {
+                continue;
+            }
+
+            let message = match suggest(&flag, known) {
+                Some(suggestion) => format!(
+                    "unknown flag `{}` in #[{}]; did you mean `{}`?",
+                    flag, name, suggestion
+                ),
+                None => format!(
+                    "unknown flag `{}` in #[{}]; expected one of {}",
+                    flag,
+                    name,
+                    expected(known)
+                ),
+            };
+            errors.push(darling::Error::custom(message).with_span(path));
+        }
+    }
+
+    errors.finish()
+}
+
+fn list_items(attr: &syn::Attribute) -> darling::Result<Vec<NestedMeta>> {
+    match &attr.meta {
+        syn::Meta::List(list) => Ok(NestedMeta::parse_meta_list(list.tokens.clone())?),
+        _ => Err(darling::Error::custom(format!(
+            "expected a list, e.g. `#[{}(...)]`",
+            quote_path(attr.path())
+        ))
+        .with_span(attr)),
+    }
+}
+
+/// Suggests the flag the user most likely meant: a known alias first, then the closest spelling.
+fn suggest(flag: &str, known: &'static [&'static str]) -> Option<&'static str> {
+    if let Some((_, target)) = ALIASES
+        .iter()
+        .find(|(alias, target)| *alias == flag && known.contains(target))
+    {
+        return Some(target);
+    }
+
+    known
+        .iter()
+        .map(|candidate| (strsim::jaro_winkler(flag, candidate), *candidate))
+        .filter(|(confidence, _)| *confidence > 0.8)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, candidate)| candidate)
+}
+
+/// Suggests the column of the entity closest in spelling to an unknown one.
+pub(crate) fn suggest_column(column: &str, columns: &[syn::Ident]) -> Option<String> {
+    columns
+        .iter()
+        .map(|candidate| candidate.to_string())
+        .map(|candidate| (strsim::jaro_winkler(column, &candidate), candidate))
+        .filter(|(confidence, _)| *confidence > 0.8)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, candidate)| candidate)
+}
+
+fn expected(known: &[&str]) -> String {
+    known
+        .iter()
+        .map(|flag| format!("`{}`", flag))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn quote_path(path: &syn::Path) -> String {
+    path.segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<_>>()
+        .join("::")
+}
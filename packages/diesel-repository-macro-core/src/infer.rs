@@ -0,0 +1,114 @@
+//! Defaults read from the `#[diesel(...)]` attributes the entity already carries.
+//!
+//! Malformed Diesel attributes are skipped here: Diesel's own derives report them.
+
+use darling::FromMeta;
+use syn::punctuated::Punctuated;
+use syn::{Attribute, Data, DeriveInput, Fields, Meta, Token};
+
+/// Items of every `#[diesel(...)]` attribute, in declaration order.
+fn diesel_items(attrs: &[Attribute]) -> Vec<Meta> {
+    attrs
+        .iter()
+        .filter(|a| a.path().is_ident("diesel"))
+        .filter_map(|a| {
+            a.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+                .ok()
+        })
+        .flatten()
+        .collect()
+}
+
+/// The table of `#[diesel(table_name = ...)]`, if declared.
+pub fn table_name(attrs: &[Attribute]) -> Option<syn::Path> {
+    diesel_items(attrs).into_iter().find_map(|item| match item {
+        Meta::NameValue(nv) if nv.path.is_ident("table_name") => {
+            syn::Path::from_expr(&nv.value).ok()
+        }
+        _ => None,
+    })
+}
+
+/// The first backend of `#[diesel(check_for_backend(...))]`, if declared.
+pub fn backend(attrs: &[Attribute]) -> Option<syn::Path> {
+    diesel_items(attrs).into_iter().find_map(|item| match item {
+        Meta::List(list) if list.path.is_ident("check_for_backend") => list
+            .parse_args_with(Punctuated::<syn::Path, Token![,]>::parse_terminated)
+            .ok()?
+            .into_iter()
+            .next(),
+        _ => None,
+    })
+}
+
+/// The primary-key columns of `#[diesel(primary_key(...))]`, defaulting to Diesel's `id`.
+pub fn primary_key(attrs: &[Attribute]) -> Vec<syn::Ident> {
+    diesel_items(attrs)
+        .into_iter()
+        .find_map(|item| match item {
+            Meta::List(list) if list.path.is_ident("primary_key") => list
+                .parse_args_with(Punctuated::<syn::Ident, Token![,]>::parse_terminated)
+                .ok(),
+            _ => None,
+        })
+        .map(|columns| columns.into_iter().collect())
+        .unwrap_or_else(|| vec![syn::parse_quote!(id)])
+}
+
+/// The column a field maps to: its `#[diesel(column_name = ...)]`, otherwise its name.
+pub fn column_name(ident: Option<&syn::Ident>, attrs: &[Attribute]) -> Option<syn::Ident> {
+    diesel_items(attrs)
+        .into_iter()
+        .find_map(|item| match item {
+            Meta::NameValue(nv) if nv.path.is_ident("column_name") => {
+                syn::Ident::from_expr(&nv.value).ok()
+            }
+            _ => None,
+        })
+        .or_else(|| ident.cloned())
+}
+
+/// The columns of the named fields, in declaration order; empty for tuple structs.
+pub fn columns(input: &DeriveInput) -> Vec<syn::Ident> {
+    let Data::Struct(data) = &input.data else {
+        return Vec::new();
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Vec::new();
+    };
+    fields
+        .named
+        .iter()
+        .filter_map(|field| column_name(field.ident.as_ref(), &field.attrs))
+        .collect()
+}
+
+/// The id type from the fields backing the primary key; composite keys become a tuple.
+///
+/// Returns `None` when a key column has no matching field.
+pub fn id_type(input: &DeriveInput) -> Option<syn::Type> {
+    let Data::Struct(data) = &input.data else {
+        return None;
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return None;
+    };
+
+    let mut types = primary_key(&input.attrs)
+        .into_iter()
+        .map(|column| {
+            fields
+                .named
+                .iter()
+                .find(|field| {
+                    column_name(field.ident.as_ref(), &field.attrs).as_ref() == Some(&column)
+                })
+                .map(|field| field.ty.clone())
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    match types.len() {
+        1 => types.pop(),
+        _ => Some(syn::parse_quote! { (#(#types),*) }),
+    }
+}
@@ -1,4 +1,11 @@
-pub use crate::opts::Options;
+pub use crate::derive::derive;
+pub use crate::diagnostics::validate_attributes;
+pub use crate::opts::{CountStrategy, Mode, Options, PatchFieldOpts, PatchOpts};
+pub use crate::sql::{RepoSqlOpts, SqlArg, SqlArgs, SqlReturns};
 pub use darling::FromDeriveInput;
 
+mod derive;
+mod diagnostics;
+pub mod infer;
 mod opts;
+mod sql;
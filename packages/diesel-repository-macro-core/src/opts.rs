@@ -1,19 +1,263 @@
-use darling::{FromAttributes, FromDeriveInput};
+use darling::ast::NestedMeta;
+use darling::{FromAttributes, FromDeriveInput, FromField, FromMeta};
+use quote::ToTokens;
 use syn::DeriveInput;
 
+use crate::diagnostics::validate_attributes;
+use crate::infer;
+use crate::sql::{self, RepoSqlOpts};
+
 #[derive(Debug, PartialEq, Eq, Clone, FromDeriveInput)]
-#[darling(attributes(repository), supports(struct_named))]
+#[darling(
+    attributes(repository),
+    forward_attrs,
+    supports(struct_named, struct_tuple)
+)]
 pub struct RepoOpts {
     pub ident: syn::Ident,
 
+    pub generics: syn::Generics,
+
     pub attrs: Vec<syn::Attribute>,
 
     /// Expecting something like: #[repository(pool = "db::DbPool")]
     #[darling(default)]
     pub pool: Option<syn::Path>,
 
+    /// Defaults to `#[diesel(table_name = ...)]`.
     #[darling(default)]
     pub table_name: Option<syn::Path>,
+
+    /// The Diesel backend queries are boxed for, defaults to the first
+    /// `#[diesel(check_for_backend(...))]`.
+    #[darling(default)]
+    pub backend: Option<syn::Path>,
+
+    /// Expecting something like: #[repository(has_many(posts = PostRepo))]
+    #[darling(default)]
+    pub has_many: Associations,
+
+    /// Expecting something like: #[repository(belongs_to(account = AccountRepo))]
+    /// or #[repository(belongs_to(account(repo = AccountRepo, foreign_key = owner_id)))]
+    #[darling(default)]
+    pub belongs_to: Associations,
+
+    /// Expecting something like: #[repository(projections(AccountSummary, AccountName))]
+    #[darling(default)]
+    pub projections: darling::util::PathList,
+
+    /// Expecting something like: #[repository(hooks)]
+    #[darling(default)]
+    pub hooks: bool,
+
+    /// Expecting something like: #[repository(outbox = outbox_events)]
+    #[darling(default)]
+    pub outbox: Option<syn::Ident>,
+
+    /// Expecting something like: #[repository(history = account_history)]
+    #[darling(default)]
+    pub history: Option<syn::Path>,
+
+    /// Expecting something like: #[repository(schema_check)]
+    #[darling(default)]
+    pub schema_check: bool,
+
+    /// Expecting something like: #[repository(session_context)]
+    #[darling(default)]
+    pub session_context: bool,
+
+    /// The columns of the entity's fields, read from the struct.
+    #[darling(skip)]
+    pub columns: Vec<syn::Ident>,
+
+    /// Expecting something like: #[repository(subscribe)]; needs `mode = "async"` or `"both"`.
+    #[darling(default)]
+    pub subscribe: bool,
+
+    /// Expecting something like:
+    /// #[repository(queue(status = status, run_at = run_at, attempts = attempts))]
+    #[darling(default)]
+    pub queue: Option<QueueOpts>,
+
+    /// Expecting something like: #[repository(mode = "async")], one of `sync` (the
+    /// default), `async` or `both`.
+    #[darling(default)]
+    pub mode: Mode,
+
+    /// Name of the generated repository, defaults to `{Name}Repo`.
+    #[darling(default)]
+    pub name: Option<syn::Ident>,
+
+    /// Expecting something like: #[repository(vis = "pub(crate)")], defaults to `pub`.
+    #[darling(default, rename = "vis")]
+    pub visibility: Option<syn::Visibility>,
+
+    /// Expecting something like: #[repository(derive(Clone, Debug))]
+    #[darling(default)]
+    pub derive: darling::util::PathList,
+
+    /// Expecting something like: #[repository(fields(clock: Arc<dyn Clock>))]
+    #[darling(default)]
+    pub fields: RepoFields,
+}
+
+/// Which trait family the generated repository implements.
+#[derive(Debug, PartialEq, Eq, Default, Clone, Copy, FromMeta)]
+pub enum Mode {
+    /// The blocking traits of `diesel_repository::sync`.
+    #[default]
+    #[darling(rename = "sync")]
+    Sync,
+    /// The `async` traits of `diesel_repository::asynchronous`.
+    #[darling(rename = "async")]
+    Async,
+    /// Both families on the same repository, with `_async` inherent methods.
+    #[darling(rename = "both")]
+    Both,
+}
+
+/// How generated paged methods learn the total number of records.
+#[derive(Debug, PartialEq, Eq, Default, Clone, Copy, FromMeta)]
+pub enum CountStrategy {
+    /// A second `SELECT COUNT(*)` query per page.
+    #[default]
+    #[darling(rename = "separate")]
+    Separate,
+    /// `COUNT(*) OVER ()` selected alongside the rows, in one round trip.
+    #[darling(rename = "window")]
+    Window,
+    /// The planner's estimate from `pg_class.reltuples` or `EXPLAIN`, for huge tables.
+    #[darling(rename = "estimate")]
+    Estimate,
+    /// No total: pages become a `Slice` with `has_next`, fetching `per_page + 1` rows.
+    #[darling(rename = "none")]
+    None,
+}
+
+/// The columns of a job-queue table, see `diesel_repository::QueueTable`.
+#[derive(Debug, PartialEq, Eq, Clone, FromMeta)]
+pub struct QueueOpts {
+    /// The text column holding the `JobStatus`.
+    pub status: syn::Ident,
+
+    /// When the job is due, or when it was claimed while running.
+    pub run_at: syn::Ident,
+
+    /// The number of times the job was claimed.
+    pub attempts: syn::Ident,
+
+    /// The primary-key column, defaults to `id`.
+    #[darling(default)]
+    pub id: Option<syn::Ident>,
+}
+
+/// Extra fields of the generated repository, set through its constructor or builder.
+#[derive(Debug, PartialEq, Eq, Default, Clone)]
+pub struct RepoFields(pub Vec<syn::Field>);
+
+impl FromMeta for RepoFields {
+    // `name: Type` pairs are not meta items, so the list is parsed as named fields instead.
+    fn from_meta(item: &syn::Meta) -> darling::Result<Self> {
+        let list = item.require_list()?;
+        let fields = list
+            .parse_args_with(|input: syn::parse::ParseStream| {
+                syn::punctuated::Punctuated::<syn::Field, syn::Token![,]>::parse_terminated_with(
+                    input,
+                    syn::Field::parse_named,
+                )
+            })
+            .map_err(darling::Error::from)?;
+
+        let mut errors = darling::Error::accumulator();
+        for field in &fields {
+            if field.ident.as_ref().is_some_and(|ident| ident == "pool") {
+                errors.push(
+                    darling::Error::custom("`pool` is always a field of the repository")
+                        .with_span(&field.ident),
+                );
+            }
+        }
+
+        errors.finish_with(Self(fields.into_iter().collect()))
+    }
+}
+
+/// An ordered list of relations to other repositories, each `name = RepoType` or
+/// `name(repo = RepoType, foreign_key = column)`.
+#[derive(Debug, PartialEq, Eq, Default, Clone)]
+pub struct Associations(pub Vec<Association>);
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Association {
+    pub name: syn::Ident,
+
+    pub repo: syn::Path,
+
+    /// The column of this table referencing the parent of a `belongs_to`, `{name}_id` by
+    /// default. `has_many` rejects it: the related entity's `BelongsTo` impl names its key.
+    pub foreign_key: Option<syn::Ident>,
+}
+
+#[derive(Debug, FromMeta)]
+struct AssociationOpts {
+    repo: syn::Path,
+
+    #[darling(default)]
+    foreign_key: Option<syn::Ident>,
+}
+
+impl FromMeta for Associations {
+    fn from_list(items: &[NestedMeta]) -> darling::Result<Self> {
+        let mut errors = darling::Error::accumulator();
+        let mut associations = Vec::with_capacity(items.len());
+
+        for item in items {
+            match item {
+                NestedMeta::Meta(syn::Meta::NameValue(nv)) => {
+                    let Some(name) = nv.path.get_ident() else {
+                        errors.push(
+                            darling::Error::custom("expected a relation name").with_span(&nv.path),
+                        );
+                        continue;
+                    };
+                    if let Some(repo) = errors.handle(syn::Path::from_expr(&nv.value)) {
+                        associations.push(Association {
+                            name: name.clone(),
+                            repo,
+                            foreign_key: None,
+                        });
+                    }
+                }
+                NestedMeta::Meta(syn::Meta::List(list)) => {
+                    let Some(name) = list.path.get_ident() else {
+                        errors.push(
+                            darling::Error::custom("expected a relation name").with_span(&list.path),
+                        );
+                        continue;
+                    };
+                    let opts = NestedMeta::parse_meta_list(list.tokens.clone())
+                        .map_err(darling::Error::from)
+                        .and_then(|items| AssociationOpts::from_list(&items))
+                        .map_err(|err| err.with_span(list));
+                    if let Some(opts) = errors.handle(opts) {
+                        associations.push(Association {
+                            name: name.clone(),
+                            repo: opts.repo,
+                            foreign_key: opts.foreign_key,
+                        });
+                    }
+                }
+                _ => errors.push(
+                    darling::Error::custom(
+                        "expected `name = RepoType` or `name(repo = RepoType, foreign_key = column)`",
+                    )
+                    .with_span(item),
+                ),
+            }
+        }
+
+        errors.finish_with(Self(associations))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -23,22 +267,54 @@ pub struct Options {
     pub crud_repo: CrudRepoOpts,
     pub paging_repo: PagingRepoOpts,
     pub batch_repo: BatchRepoOpts,
+    pub repo_sql: Vec<RepoSqlOpts>,
 }
 
 impl FromDeriveInput for Options {
     fn from_derive_input(input: &DeriveInput) -> darling::Result<Self> {
-        let opts = RepoOpts::from_derive_input(&input)?;
-        let repo_type = RepoTypeOpts::from_attributes(&input.attrs)?;
-        let crud_repo = CrudRepoOpts::from_attributes(&input.attrs)?;
-        let paging_repo = PagingRepoOpts::from_attributes(&input.attrs)?;
-        let batch_repo = BatchRepoOpts::from_attributes(&input.attrs)?;
+        // Report unknown flags with suggestions first, instead of darling's generic errors.
+        validate_attributes(&input.attrs)?;
+
+        let mut errors = darling::Error::accumulator();
+        let opts = errors.handle(RepoOpts::from_derive_input(input));
+        let repo_type = errors.handle(RepoTypeOpts::from_attributes(&input.attrs));
+        let crud_repo = errors.handle(CrudRepoOpts::from_attributes(&input.attrs));
+        let paging_repo = errors
+            .handle(PagingRepoOpts::from_attributes(&input.attrs))
+            .filter(|opts| errors.handle(opts.validate()).is_some());
+        let batch_repo = errors.handle(BatchRepoOpts::from_attributes(&input.attrs));
+        let mut repo_sql = Vec::new();
+        for attr in input.attrs.iter().filter(|a| a.path().is_ident("repo_sql")) {
+            if let Some(opts) = errors.handle(RepoSqlOpts::from_meta(&attr.meta)) {
+                if errors.handle(sql::check_arity(&opts)).is_some() {
+                    repo_sql.push(opts);
+                }
+            }
+        }
+        errors.finish()?;
+
+        let mut repo = opts.unwrap();
+        let mut repo_type = repo_type.unwrap();
+
+        // Fall back to what the entity already declares for Diesel.
+        if repo.table_name.is_none() {
+            repo.table_name = infer::table_name(&input.attrs);
+        }
+        if repo.backend.is_none() {
+            repo.backend = infer::backend(&input.attrs);
+        }
+        if repo_type.id_type.is_none() {
+            repo_type.id_type = infer::id_type(input);
+        }
+        repo.columns = infer::columns(input);
 
         Ok(Self {
-            repo: opts,
+            repo,
             repo_type,
-            crud_repo,
-            paging_repo,
-            batch_repo,
+            crud_repo: crud_repo.unwrap(),
+            paging_repo: paging_repo.unwrap(),
+            batch_repo: batch_repo.unwrap(),
+            repo_sql,
         })
     }
 }
@@ -46,11 +322,32 @@ impl FromDeriveInput for Options {
 #[derive(Debug, PartialEq, Eq, Default, Clone, FromAttributes)]
 #[darling(default, attributes(repo_type))]
 pub struct RepoTypeOpts {
-    pub id_type: Option<syn::Ident>,
+    /// Defaults to the type of the primary-key field(s), see `#[diesel(primary_key(...))]`.
+    #[darling(with = type_from_meta)]
+    pub id_type: Option<syn::Type>,
 
+    /// Defaults to the entity; checked to be `Insertable` into the table.
     pub new_type: Option<syn::Ident>,
 
+    /// Defaults to the entity; checked to be an `Identifiable` `AsChangeset` by reference.
     pub update_type: Option<syn::Ident>,
+
+    pub patch_type: Option<syn::Ident>,
+}
+
+/// Accepts a type written as a path (`id_type = uuid::Uuid`), a tuple
+/// (`id_type = (i32, i64)`) or a string for anything else (`id_type = "Vec<u8>"`).
+fn type_from_meta(meta: &syn::Meta) -> darling::Result<Option<syn::Type>> {
+    let expr = &meta.require_name_value()?.value;
+    let ty = match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(s),
+            ..
+        }) => s.parse(),
+        _ => syn::parse2(expr.to_token_stream()),
+    };
+    ty.map(Some)
+        .map_err(|_| darling::Error::custom("expected a type").with_span(expr))
 }
 
 #[derive(Debug, PartialEq, Eq, Default, Clone, FromAttributes)]
@@ -74,6 +371,9 @@ pub struct CrudRepoOpts {
     #[darling(default)]
     pub update: bool,
 
+    #[darling(default)]
+    pub patch: bool,
+
     #[darling(default)]
     pub replace: bool,
 
@@ -82,6 +382,16 @@ pub struct CrudRepoOpts {
 
     #[darling(default)]
     pub count: bool,
+
+    #[darling(default)]
+    pub exists: bool,
+
+    #[darling(default)]
+    pub exists_query: bool,
+
+    /// Implements `Lock` on the repository's `InTransaction` view.
+    #[darling(default)]
+    pub lock: bool,
 }
 
 #[derive(Debug, PartialEq, Eq, Default, Clone, FromAttributes)]
@@ -92,6 +402,45 @@ pub struct PagingRepoOpts {
 
     #[darling(default)]
     pub find_all: bool,
+
+    /// Expecting something like: #[paging_repo(count_strategy = "window")], one of `separate`
+    /// (the default), `window`, `estimate` or `none`.
+    #[darling(default)]
+    pub count_strategy: CountStrategy,
+
+    /// Expecting something like: #[paging_repo(max_per_page = 500)]; larger pages are rejected.
+    #[darling(default)]
+    pub max_per_page: Option<syn::LitInt>,
+
+    /// Expecting something like: #[paging_repo(default_per_page = 20)], used for `per_page = 0`.
+    #[darling(default)]
+    pub default_per_page: Option<syn::LitInt>,
+}
+
+impl PagingRepoOpts {
+    /// Checks that the page sizes are positive and the default does not exceed the maximum.
+    fn validate(&self) -> darling::Result<()> {
+        let positive = |lit: &syn::LitInt| -> darling::Result<i64> {
+            match lit.base10_parse::<i64>() {
+                Ok(n) if n >= 1 => Ok(n),
+                _ => {
+                    Err(darling::Error::custom("expected a page size of at least 1").with_span(lit))
+                }
+            }
+        };
+        let max = self.max_per_page.as_ref().map(positive).transpose()?;
+        let default = self.default_per_page.as_ref().map(positive).transpose()?;
+        if let (Some(max), Some(default), Some(lit)) = (max, default, &self.default_per_page) {
+            if default > max {
+                return Err(darling::Error::custom(format!(
+                    "`default_per_page` must not exceed `max_per_page` ({})",
+                    max
+                ))
+                .with_span(lit));
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Default, Clone, FromAttributes)]
@@ -108,6 +457,44 @@ pub struct BatchRepoOpts {
 
     #[darling(default)]
     pub delete: bool,
+
+    #[darling(default)]
+    pub exists: bool,
+}
+
+/// Options of `#[derive(Patch)]`, e.g. `#[patch(name = AccountPatch, table_name = crate::accounts)]`.
+#[derive(Debug, PartialEq, Eq, Clone, FromDeriveInput)]
+#[darling(attributes(patch), supports(struct_named))]
+pub struct PatchOpts {
+    pub ident: syn::Ident,
+
+    pub vis: syn::Visibility,
+
+    pub data: darling::ast::Data<(), PatchFieldOpts>,
+
+    /// Name of the generated patch struct, defaults to `{Name}Patch`.
+    #[darling(default)]
+    pub name: Option<syn::Ident>,
+
+    /// Falls back to `#[repository(table_name = ...)]` when absent.
+    #[darling(default)]
+    pub table_name: Option<syn::Path>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, FromField)]
+#[darling(attributes(patch), forward_attrs(diesel))]
+pub struct PatchFieldOpts {
+    pub ident: Option<syn::Ident>,
+
+    pub vis: syn::Visibility,
+
+    pub ty: syn::Type,
+
+    pub attrs: Vec<syn::Attribute>,
+
+    /// Leaves the field out of the patch struct, e.g. for immutable columns.
+    #[darling(default)]
+    pub skip: bool,
 }
 
 #[test]
@@ -159,18 +546,375 @@ fn test() -> Result<(), Box<dyn std::error::Error>> {
     match repo_type.id_type {
         None => panic!("id_type should not be empty"),
         Some(v) => {
-            let challenge: syn::Ident = syn::parse_quote! {
+            let challenge: syn::Type = syn::parse_quote! {
                 String
             };
             assert_eq!(v, challenge);
         }
     }
 
-    assert_eq!(crud_repo.find_one, true);
-    assert_eq!(crud_repo.find_one_query, true);
-    assert_eq!(crud_repo.find_all, true);
+    assert!(crud_repo.find_one);
+    assert!(crud_repo.find_one_query);
+    assert!(crud_repo.find_all);
+
+    assert!(paging_repo.find_all);
+
+    Ok(())
+}
+
+#[test]
+fn test_patch() -> Result<(), Box<dyn std::error::Error>> {
+    let input: syn::DeriveInput = syn::parse_quote! {
+        #[patch(table_name = crate::accounts)]
+        pub struct Account {
+            pub id: String,
+            #[patch(skip)]
+            pub sub: String,
+            #[diesel(column_name = display_name)]
+            pub name: Option<String>,
+        }
+    };
+
+    let opts = PatchOpts::from_derive_input(&input)?;
+    assert_eq!(opts.name, None);
+    assert_eq!(opts.table_name, Some(syn::parse_quote! { crate::accounts }));
+
+    let fields = opts.data.take_struct().expect("named struct").fields;
+    assert_eq!(fields.len(), 3);
+    assert!(!fields[0].skip);
+    assert!(fields[1].skip);
+    assert_eq!(fields[2].attrs.len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_associations() -> Result<(), Box<dyn std::error::Error>> {
+    let input: syn::DeriveInput = syn::parse_quote! {
+        #[repository(
+            pool = db::DbPool,
+            table_name = crate::accounts,
+            has_many(posts = PostRepo, comments(repo = crate::comments::CommentRepo, foreign_key = author_id)),
+            belongs_to(organization = OrganizationRepo, owner(repo = AccountRepo, foreign_key = owner_id)),
+            projections(AccountSummary, dto::AccountName)
+        )]
+        struct Account {
+            id: String,
+            organization_id: String,
+            owner_id: String,
+        }
+    };
+
+    let Options { repo, .. } = Options::from_derive_input(&input)?;
+
+    let has_many: Vec<String> = repo
+        .has_many
+        .0
+        .iter()
+        .map(|association| association.name.to_string())
+        .collect();
+    assert_eq!(has_many, vec!["posts", "comments"]);
+    assert_eq!(repo.has_many.0[0].foreign_key, None);
+    assert_eq!(
+        repo.has_many.0[1].repo,
+        syn::parse_quote! { crate::comments::CommentRepo }
+    );
+    assert_eq!(
+        repo.has_many.0[1].foreign_key,
+        Some(syn::parse_quote! { author_id })
+    );
+
+    let belongs_to: Vec<String> = repo
+        .belongs_to
+        .0
+        .iter()
+        .map(|association| association.name.to_string())
+        .collect();
+    assert_eq!(belongs_to, vec!["organization", "owner"]);
+    assert_eq!(
+        repo.belongs_to.0[1].foreign_key,
+        Some(syn::parse_quote! { owner_id })
+    );
 
-    assert_eq!(paging_repo.find_all, true);
+    assert_eq!(repo.projections.len(), 2);
+    assert_eq!(repo.projections[1], syn::parse_quote! { dto::AccountName });
+
+    Ok(())
+}
+
+#[test]
+fn test_infer_from_diesel() -> Result<(), Box<dyn std::error::Error>> {
+    let input: syn::DeriveInput = syn::parse_quote! {
+        #[diesel(table_name = crate::accounts, check_for_backend(diesel::pg::Pg))]
+        #[repository(pool = db::DbPool)]
+        struct Account {
+            id: uuid::Uuid,
+            #[diesel(sql_type = Nullable<Text>)]
+            name: Option<String>,
+        }
+    };
+
+    let Options {
+        repo, repo_type, ..
+    } = Options::from_derive_input(&input)?;
+    assert_eq!(repo.table_name, Some(syn::parse_quote! { crate::accounts }));
+    assert_eq!(repo.backend, Some(syn::parse_quote! { diesel::pg::Pg }));
+    assert_eq!(repo_type.id_type, Some(syn::parse_quote! { uuid::Uuid }));
+
+    let input: syn::DeriveInput = syn::parse_quote! {
+        #[diesel(table_name = memberships, primary_key(account, group_id))]
+        #[repository(pool = db::DbPool, table_name = crate::schema::memberships)]
+        struct Membership {
+            #[diesel(column_name = account)]
+            account_id: i32,
+            group_id: i64,
+        }
+    };
+
+    let Options {
+        repo, repo_type, ..
+    } = Options::from_derive_input(&input)?;
+    assert_eq!(
+        repo.table_name,
+        Some(syn::parse_quote! { crate::schema::memberships })
+    );
+    assert_eq!(repo_type.id_type, Some(syn::parse_quote! { (i32, i64) }));
+
+    Ok(())
+}
+
+#[test]
+fn test_repo_struct() -> Result<(), Box<dyn std::error::Error>> {
+    let input: syn::DeriveInput = syn::parse_quote! {
+        #[repository(
+            pool = db::DbPool,
+            table_name = crate::accounts,
+            name = AccountStore,
+            vis = "pub(crate)",
+            derive(Clone, Debug),
+            fields(clock: Arc<dyn Clock>, pub tenant: String)
+        )]
+        struct Account {
+            id: String,
+        }
+    };
+
+    let Options { repo, .. } = Options::from_derive_input(&input)?;
+    assert_eq!(repo.name, Some(syn::parse_quote! { AccountStore }));
+    assert_eq!(repo.visibility, Some(syn::parse_quote! { pub(crate) }));
+    assert_eq!(repo.derive.len(), 2);
+
+    let names: Vec<String> = repo
+        .fields
+        .0
+        .iter()
+        .map(|f| f.ident.as_ref().unwrap().to_string())
+        .collect();
+    assert_eq!(names, vec!["clock", "tenant"]);
+    assert_eq!(repo.fields.0[0].ty, syn::parse_quote! { Arc<dyn Clock> });
+
+    Ok(())
+}
+
+#[test]
+fn test_generics() -> Result<(), Box<dyn std::error::Error>> {
+    let input: syn::DeriveInput = syn::parse_quote! {
+        #[repository(pool = db::DbPool, table_name = crate::tags)]
+        struct Tagged<'a, T: Clone> where T: Debug {
+            id: i32,
+            label: Cow<'a, str>,
+            value: T,
+        }
+    };
+
+    let Options {
+        repo, repo_type, ..
+    } = Options::from_derive_input(&input)?;
+    assert_eq!(repo.generics.params.len(), 2);
+    assert!(repo.generics.where_clause.is_some());
+    assert_eq!(repo_type.id_type, Some(syn::parse_quote! { i32 }));
+
+    let input: syn::DeriveInput = syn::parse_quote! {
+        #[repository(pool = db::DbPool, table_name = crate::tags)]
+        #[repo_type(id_type = i32)]
+        struct TagRow(i32, String);
+    };
+    assert!(Options::from_derive_input(&input).is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn test_placeholder_count() {
+    use crate::sql::placeholder_count;
+
+    assert_eq!(placeholder_count("SELECT * FROM accounts"), 0);
+    assert_eq!(
+        placeholder_count("SELECT * FROM accounts WHERE sub = $1 AND name = $2 OR sub = $1"),
+        2
+    );
+    assert_eq!(
+        placeholder_count("SELECT * FROM a WHERE x = ? AND y = ?"),
+        2
+    );
+    assert_eq!(
+        placeholder_count("SELECT '$3?' FROM a WHERE \"weird?\" = $1"),
+        1
+    );
+}
+
+#[test]
+fn test_repo_sql() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::sql::SqlReturns;
+
+    let input: syn::DeriveInput = syn::parse_quote! {
+        #[repository(pool = db::DbPool, table_name = crate::accounts)]
+        #[repo_sql(
+            name = "find_active_by_sub",
+            sql = "SELECT * FROM accounts WHERE sub = $1 AND active",
+            args(sub: String),
+            returns = Vec
+        )]
+        #[repo_sql(
+            name = "rename",
+            sql = "UPDATE accounts SET name = $2 WHERE id = $1",
+            args(id: uuid::Uuid as diesel::sql_types::Uuid, name: String),
+            returns = Execute
+        )]
+        struct Account {
+            id: uuid::Uuid,
+        }
+    };
+
+    let Options { repo_sql, .. } = Options::from_derive_input(&input)?;
+    assert_eq!(repo_sql.len(), 2);
+    assert_eq!(repo_sql[0].name, "find_active_by_sub");
+    assert_eq!(repo_sql[0].returns, SqlReturns::Vec);
+    assert_eq!(
+        repo_sql[0].args.0[0].sql_type,
+        syn::parse_quote! { diesel::sql_types::Text }
+    );
+    assert_eq!(repo_sql[1].returns, SqlReturns::Execute);
+    assert_eq!(
+        repo_sql[1].args.0[0].sql_type,
+        syn::parse_quote! { diesel::sql_types::Uuid }
+    );
+
+    let input: syn::DeriveInput = syn::parse_quote! {
+        #[repository(pool = db::DbPool, table_name = crate::accounts)]
+        #[repo_sql(name = "by_sub", sql = "SELECT * FROM accounts WHERE sub = $1 AND name = $2", args(sub: String))]
+        struct Account {
+            id: String,
+        }
+    };
+    assert!(Options::from_derive_input(&input).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_mode() -> Result<(), Box<dyn std::error::Error>> {
+    let input: syn::DeriveInput = syn::parse_quote! {
+        #[repository(pool = db::DbPool, table_name = crate::accounts)]
+        struct Account {
+            id: String,
+        }
+    };
+    let Options { repo, .. } = Options::from_derive_input(&input)?;
+    assert_eq!(repo.mode, Mode::Sync);
+
+    let input: syn::DeriveInput = syn::parse_quote! {
+        #[repository(pool = db::DbPool, table_name = crate::accounts, mode = "both")]
+        struct Account {
+            id: String,
+        }
+    };
+    let Options { repo, .. } = Options::from_derive_input(&input)?;
+    assert_eq!(repo.mode, Mode::Both);
+
+    Ok(())
+}
+
+#[test]
+fn test_paging_limits() -> Result<(), Box<dyn std::error::Error>> {
+    let input: syn::DeriveInput = syn::parse_quote! {
+        #[repository(pool = db::DbPool, table_name = crate::accounts)]
+        #[paging_repo(find_all, max_per_page = 500, default_per_page = 20)]
+        struct Account {
+            id: String,
+        }
+    };
+    let Options { paging_repo, .. } = Options::from_derive_input(&input)?;
+    assert_eq!(paging_repo.max_per_page, Some(syn::parse_quote! { 500 }));
+    assert_eq!(paging_repo.default_per_page, Some(syn::parse_quote! { 20 }));
+
+    let input: syn::DeriveInput = syn::parse_quote! {
+        #[repository(pool = db::DbPool, table_name = crate::accounts)]
+        #[paging_repo(find_all, max_per_page = 50, default_per_page = 100)]
+        struct Account {
+            id: String,
+        }
+    };
+    assert!(Options::from_derive_input(&input).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_count_strategy() -> Result<(), Box<dyn std::error::Error>> {
+    let input: syn::DeriveInput = syn::parse_quote! {
+        #[repository(pool = db::DbPool, table_name = crate::accounts)]
+        #[paging_repo(find_all)]
+        struct Account {
+            id: String,
+        }
+    };
+    let Options { paging_repo, .. } = Options::from_derive_input(&input)?;
+    assert_eq!(paging_repo.count_strategy, CountStrategy::Separate);
+
+    let input: syn::DeriveInput = syn::parse_quote! {
+        #[repository(pool = db::DbPool, table_name = crate::accounts)]
+        #[paging_repo(find_all, count_strategy = "none")]
+        struct Account {
+            id: String,
+        }
+    };
+    let Options { paging_repo, .. } = Options::from_derive_input(&input)?;
+    assert_eq!(paging_repo.count_strategy, CountStrategy::None);
+
+    Ok(())
+}
+
+#[test]
+fn test_queue() -> Result<(), Box<dyn std::error::Error>> {
+    let input: syn::DeriveInput = syn::parse_quote! {
+        #[repository(
+            pool = db::DbPool,
+            table_name = crate::jobs,
+            queue(status = state, run_at = due_at, attempts = tries, id = job_id)
+        )]
+        struct Job {
+            job_id: i64,
+        }
+    };
+    let Options { repo, .. } = Options::from_derive_input(&input)?;
+    assert_eq!(
+        repo.queue,
+        Some(QueueOpts {
+            status: syn::parse_quote! { state },
+            run_at: syn::parse_quote! { due_at },
+            attempts: syn::parse_quote! { tries },
+            id: Some(syn::parse_quote! { job_id }),
+        })
+    );
+
+    let input: syn::DeriveInput = syn::parse_quote! {
+        #[repository(pool = db::DbPool, table_name = crate::jobs, queue(status = state))]
+        struct Job {
+            id: i64,
+        }
+    };
+    assert!(Options::from_derive_input(&input).is_err());
 
     Ok(())
 }
@@ -0,0 +1,180 @@
+use darling::FromMeta;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::Token;
+
+/// A hand-written query declared on the entity, e.g.
+/// `#[repo_sql(name = "find_active_by_sub", sql = "SELECT ...", args(sub: String), returns = Vec)]`.
+#[derive(Debug, PartialEq, Eq, Clone, FromMeta)]
+pub struct RepoSqlOpts {
+    /// Name of the generated method.
+    pub name: syn::Ident,
+
+    /// The statement, with `$1`-style or `?` placeholders bound to `args` in order.
+    pub sql: syn::LitStr,
+
+    #[darling(default)]
+    pub args: SqlArgs,
+
+    #[darling(default)]
+    pub returns: SqlReturns,
+
+    /// The `QueryableByName` type of each row, defaults to the entity.
+    #[darling(default)]
+    pub row: Option<syn::Path>,
+}
+
+/// Arguments of a custom query, written `name: Type` or `name: Type as SqlType`.
+#[derive(Debug, PartialEq, Eq, Default, Clone)]
+pub struct SqlArgs(pub Vec<SqlArg>);
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SqlArg {
+    pub name: syn::Ident,
+    pub ty: syn::Type,
+    /// The Diesel SQL type the argument is bound as.
+    pub sql_type: syn::Type,
+}
+
+impl Parse for SqlArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let ty: syn::Type = input.parse()?;
+        let sql_type = if input.parse::<Option<Token![as]>>()?.is_some() {
+            input.parse()?
+        } else {
+            default_sql_type(&ty).ok_or_else(|| {
+                syn::Error::new_spanned(
+                    &ty,
+                    "cannot infer the SQL type of this argument; write `name: Type as SqlType`",
+                )
+            })?
+        };
+        Ok(Self { name, ty, sql_type })
+    }
+}
+
+impl FromMeta for SqlArgs {
+    // `name: Type` pairs are not meta items, so the list is parsed directly.
+    fn from_meta(item: &syn::Meta) -> darling::Result<Self> {
+        let args = item
+            .require_list()?
+            .parse_args_with(Punctuated::<SqlArg, Token![,]>::parse_terminated)
+            .map_err(darling::Error::from)?;
+        Ok(Self(args.into_iter().collect()))
+    }
+}
+
+/// What the generated method returns.
+#[derive(Debug, PartialEq, Eq, Default, Clone, Copy)]
+pub enum SqlReturns {
+    /// All rows, `Result<Vec<Row>, _>`.
+    #[default]
+    Vec,
+    /// At most one row, `Result<Option<Row>, _>`.
+    Option,
+    /// Exactly one row, `Result<Row, _>`.
+    One,
+    /// No rows; the number of affected rows, `Result<usize, _>`.
+    Execute,
+}
+
+impl SqlReturns {
+    fn from_name(name: &str) -> darling::Result<Self> {
+        match name {
+            "Vec" => Ok(Self::Vec),
+            "Option" => Ok(Self::Option),
+            "One" => Ok(Self::One),
+            "Execute" => Ok(Self::Execute),
+            other => Err(darling::Error::unknown_value(other)),
+        }
+    }
+}
+
+impl FromMeta for SqlReturns {
+    fn from_string(value: &str) -> darling::Result<Self> {
+        Self::from_name(value)
+    }
+
+    fn from_expr(expr: &syn::Expr) -> darling::Result<Self> {
+        match expr {
+            syn::Expr::Path(path) => match path.path.get_ident() {
+                Some(ident) => Self::from_name(&ident.to_string()).map_err(|e| e.with_span(ident)),
+                None => Err(
+                    darling::Error::custom("expected Vec, Option, One or Execute").with_span(expr),
+                ),
+            },
+            syn::Expr::Lit(syn::ExprLit { lit, .. }) => Self::from_value(lit),
+            _ => Err(darling::Error::unexpected_expr_type(expr)),
+        }
+    }
+}
+
+/// The Diesel SQL type of common Rust argument types.
+fn default_sql_type(ty: &syn::Type) -> Option<syn::Type> {
+    let syn::Type::Path(path) = ty else {
+        return None;
+    };
+    let name = path.path.segments.last()?.ident.to_string();
+    let sql_type: syn::Type = match name.as_str() {
+        "String" | "str" => syn::parse_quote!(diesel::sql_types::Text),
+        "bool" => syn::parse_quote!(diesel::sql_types::Bool),
+        "i16" => syn::parse_quote!(diesel::sql_types::SmallInt),
+        "i32" => syn::parse_quote!(diesel::sql_types::Integer),
+        "i64" => syn::parse_quote!(diesel::sql_types::BigInt),
+        "f32" => syn::parse_quote!(diesel::sql_types::Float),
+        "f64" => syn::parse_quote!(diesel::sql_types::Double),
+        _ => return None,
+    };
+    Some(sql_type)
+}
+
+/// Counts the parameters a statement expects: the highest `$n` for Postgres-style
+/// placeholders, otherwise the number of `?`. Quoted strings and identifiers are skipped.
+pub fn placeholder_count(sql: &str) -> usize {
+    let mut numbered = 0;
+    let mut positional = 0;
+    let mut quote = None;
+    let mut chars = sql.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '?') => positional += 1,
+            (None, '$') => {
+                let mut digits = String::new();
+                while let Some(d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                    digits.push(*d);
+                    chars.next();
+                }
+                if let Ok(n) = digits.parse::<usize>() {
+                    numbered = numbered.max(n);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if numbered > 0 {
+        numbered
+    } else {
+        positional
+    }
+}
+
+/// Checks that the statement has as many placeholders as the method has arguments.
+pub(crate) fn check_arity(opts: &RepoSqlOpts) -> darling::Result<()> {
+    let expected = placeholder_count(&opts.sql.value());
+    let given = opts.args.0.len();
+    if expected == given {
+        return Ok(());
+    }
+    Err(darling::Error::custom(format!(
+        "`{}` binds {} argument(s) but the statement has {} placeholder(s)",
+        opts.name, given, expected
+    ))
+    .with_span(&opts.sql))
+}
@@ -0,0 +1,158 @@
+//! Expansion snapshots of `#[derive(Repo)]` in every mode.
+//!
+//! Run with `UPDATE_SNAPSHOTS=1` to rewrite the files under `tests/snapshots` after an
+//! intended change to the generated code, then review the diff.
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use diesel_repository_macro_core::{derive, FromDeriveInput, Mode, Options};
+
+fn account() -> syn::DeriveInput {
+    syn::parse_quote! {
+        #[diesel(table_name = crate::accounts, check_for_backend(diesel::pg::Pg))]
+        #[repository(
+            pool = db::DbPool,
+            has_many(posts = PostRepo),
+            belongs_to(organization = OrganizationRepo),
+            projections(AccountName),
+            hooks,
+            outbox = outbox_events,
+            history = account_history,
+            derive(Clone),
+            fields(clock: Arc<dyn Clock>)
+        )]
+        #[repo_type(new_type = NewAccount)]
+        #[crud_repo(
+            find_one, find_one_query, find_query, find_all, save, update, patch,
+            replace, delete, count, exists, exists_query, lock
+        )]
+        #[paging_repo(find_query, find_all, max_per_page = 500, default_per_page = 20)]
+        #[batch_repo(find, save, update, delete, exists)]
+        #[repo_sql(
+            name = "find_active_by_sub",
+            sql = "SELECT * FROM accounts WHERE sub = $1 AND active",
+            args(sub: String)
+        )]
+        pub struct Account {
+            pub id: String,
+            pub organization_id: String,
+            pub sub: String,
+        }
+    }
+}
+
+fn tagged() -> syn::DeriveInput {
+    syn::parse_quote! {
+        #[diesel(table_name = crate::tags)]
+        #[repository(pool = db::DbPool, name = TagStore, vis = "pub(crate)", session_context)]
+        #[crud_repo(find_one, find_all)]
+        #[paging_repo(find_all, count_strategy = "none")]
+        #[repo_sql(name = "relabel", sql = "UPDATE tags SET label = $2 WHERE id = $1", args(id: i32, label: String), returns = Execute)]
+        pub struct Tagged<'a, T: Clone>
+        where
+            T: Send,
+        {
+            pub id: i32,
+            pub label: Cow<'a, str>,
+            pub value: T,
+        }
+    }
+}
+
+fn job() -> syn::DeriveInput {
+    syn::parse_quote! {
+        #[diesel(table_name = crate::jobs)]
+        #[repository(
+            pool = db::DbPool,
+            queue(status = status, run_at = run_at, attempts = attempts),
+            subscribe,
+            schema_check
+        )]
+        #[repo_type(new_type = NewJob)]
+        pub struct Job {
+            pub id: i64,
+            pub payload: String,
+            pub status: String,
+            pub run_at: SystemTime,
+            pub attempts: i32,
+        }
+    }
+}
+
+fn expand(input: &syn::DeriveInput, mode: Mode) -> String {
+    let mut opts = Options::from_derive_input(input).expect("valid options");
+    opts.repo.mode = mode;
+    let tokens = derive(opts).expect("expansion");
+
+    let mut rustfmt = Command::new("rustfmt")
+        .args(["--edition", "2021", "--emit", "stdout"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("rustfmt is needed to compare expansions");
+    rustfmt
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(tokens.to_string().as_bytes())
+        .unwrap();
+    let output = rustfmt.wait_with_output().unwrap();
+    assert!(output.status.success(), "rustfmt rejected the expansion");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(format!("{}.rs", name));
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        expected == actual,
+        "expansion of `{}` changed; rerun with UPDATE_SNAPSHOTS=1 and review the diff of {}",
+        name,
+        path.display()
+    );
+}
+
+#[test]
+fn account_sync() {
+    assert_snapshot("account_sync", &expand(&account(), Mode::Sync));
+}
+
+#[test]
+fn account_async() {
+    assert_snapshot("account_async", &expand(&account(), Mode::Async));
+}
+
+#[test]
+fn tagged_sync() {
+    assert_snapshot("tagged_sync", &expand(&tagged(), Mode::Sync));
+}
+
+#[test]
+fn tagged_async() {
+    assert_snapshot("tagged_async", &expand(&tagged(), Mode::Async));
+}
+
+#[test]
+fn tagged_both() {
+    assert_snapshot("tagged_both", &expand(&tagged(), Mode::Both));
+}
+
+#[test]
+fn job_async() {
+    assert_snapshot("job_async", &expand(&job(), Mode::Async));
+}
+
+#[test]
+fn job_both() {
+    assert_snapshot("job_both", &expand(&job(), Mode::Both));
+}
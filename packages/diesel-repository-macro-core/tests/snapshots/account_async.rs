@@ -0,0 +1,702 @@
+#[derive(Clone)]
+pub struct AccountRepo {
+    pool: std::sync::Arc<db::DbPool>,
+    _entity: std::marker::PhantomData<fn() -> Account>,
+    clock: Arc<dyn Clock>,
+}
+#[doc = "Builds a [`AccountRepo`] field by field."]
+pub struct AccountRepoBuilder {
+    pool: Option<std::sync::Arc<db::DbPool>>,
+    _entity: std::marker::PhantomData<fn() -> Account>,
+    clock: Option<Arc<dyn Clock>>,
+}
+impl Default for AccountRepoBuilder {
+    fn default() -> Self {
+        Self {
+            pool: None,
+            _entity: std::marker::PhantomData,
+            clock: None,
+        }
+    }
+}
+impl AccountRepoBuilder {
+    pub fn pool(mut self, pool: std::sync::Arc<db::DbPool>) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+    #[doc = r" Creates the repository, failing on the first field that was not set."]
+    pub fn build(self) -> Result<AccountRepo, diesel_repository::BuildError> {
+        Ok(AccountRepo {
+            pool: self
+                .pool
+                .ok_or(diesel_repository::BuildError { field: "pool" })?,
+            _entity: std::marker::PhantomData,
+            clock: self
+                .clock
+                .ok_or(diesel_repository::BuildError { field: "clock" })?,
+        })
+    }
+}
+impl AccountRepo {
+    pub fn new(pool: std::sync::Arc<db::DbPool>, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            pool,
+            _entity: std::marker::PhantomData,
+            clock,
+        }
+    }
+    pub fn builder() -> AccountRepoBuilder {
+        AccountRepoBuilder::default()
+    }
+}
+impl diesel_repository::Repository for AccountRepo {
+    type Entity = Account;
+    type Id = String;
+}
+impl diesel_repository::QueryRepository for AccountRepo {
+    type Query = crate::accounts::BoxedQuery<'static, diesel::pg::Pg>;
+}
+const _: () = {
+    #[allow(dead_code)]
+    fn records() {
+        fn new_record<N: diesel_repository::NewRecord<T>, T>() {}
+        fn update_record<U: diesel_repository::UpdateRecord<T>, T>() {}
+        new_record::<NewAccount, crate::accounts::table>();
+        update_record::<&Account, crate::accounts::table>();
+    }
+};
+impl AccountRepo {
+    #[doc = "Loads the record with the given ID together with its `posts`."]
+    pub async fn find_with_posts(
+        &self,
+        id: String,
+    ) -> Result<
+        (
+            Account,
+            Vec<<PostRepo as diesel_repository::Repository>::Entity>,
+        ),
+        diesel::result::Error,
+    > {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        #[allow(unused_mut)]
+        let mut record: Account = diesel_repository::diesel_async::RunQueryDsl::get_result(
+            crate::accounts::table.find(id),
+            conn,
+        )
+        .await?;
+        diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: after_load (self , & mut record) ? ;
+        let query =
+            <<PostRepo as diesel_repository::Repository>::Entity as diesel::BelongingToDsl<
+                &Account,
+            >>::belonging_to(&record);
+        let related: Vec<<PostRepo as diesel_repository::Repository>::Entity> =
+            diesel_repository::diesel_async::RunQueryDsl::load(query, conn).await?;
+        Ok((record, related))
+    }
+    #[doc = "Loads the `posts` of every given record in one query, grouped in the same order as `parents`."]
+    pub async fn load_posts_for(
+        &self,
+        parents: &[Account],
+    ) -> Result<Vec<Vec<<PostRepo as diesel_repository::Repository>::Entity>>, diesel::result::Error>
+    {
+        if parents.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let query =
+            <<PostRepo as diesel_repository::Repository>::Entity as diesel::BelongingToDsl<
+                &[Account],
+            >>::belonging_to(parents);
+        let related: Vec<<PostRepo as diesel_repository::Repository>::Entity> =
+            diesel_repository::diesel_async::RunQueryDsl::load(query, conn).await?;
+        Ok(diesel::GroupedBy::grouped_by(related, parents))
+    }
+    #[doc = "Loads all records belonging to the given `organization`."]
+    pub async fn find_by_organization_id(
+        &self,
+        organization_id: <OrganizationRepo as diesel_repository::Repository>::Id,
+    ) -> Result<Vec<Account>, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let query =
+            crate::accounts::table.filter(crate::accounts::organization_id.eq(organization_id));
+        diesel_repository::diesel_async::RunQueryDsl::load(query, conn).await
+    }
+    #[doc = "Runs `SELECT * FROM accounts WHERE sub = $1 AND active`."]
+    pub async fn find_active_by_sub(
+        &self,
+        sub: String,
+    ) -> Result<Vec<Account>, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let query = diesel::sql_query("SELECT * FROM accounts WHERE sub = $1 AND active")
+            .bind::<diesel::sql_types::Text, _>(sub);
+        diesel_repository::diesel_async::RunQueryDsl::load(query, conn).await
+    }
+    #[doc = r" Returns all records projected into `P`."]
+    pub async fn find_all_as<P>(&self) -> Result<Vec<P>, diesel::result::Error>
+    where
+        Self: diesel_repository::asynchronous::Project<P>,
+    {
+        diesel_repository::asynchronous::Project::<P>::find_all_as(self).await
+    }
+    #[doc = r" Executes the query and returns the matching records projected into `P`."]
+    pub async fn find_by_query_as<P>(
+        &self,
+        query: &diesel_repository::QueryFn<'_, <Self as diesel_repository::QueryRepository>::Query>,
+    ) -> Result<Vec<P>, diesel::result::Error>
+    where
+        Self: diesel_repository::asynchronous::Project<P>,
+    {
+        diesel_repository::asynchronous::Project::<P>::find_by_query_as(self, query).await
+    }
+    #[doc = r" Returns all records projected into `P`, paginated."]
+    pub async fn find_all_as_paged<P>(
+        &self,
+        page: i64,
+        per_page: i64,
+    ) -> Result<diesel_repository::Paged<P>, diesel::result::Error>
+    where
+        Self: diesel_repository::asynchronous::ProjectPaged<P>,
+    {
+        diesel_repository::asynchronous::ProjectPaged::<P>::find_all_as_paged(self, page, per_page)
+            .await
+    }
+    #[doc = r" Executes the query with pagination and returns the records projected into `P`."]
+    pub async fn find_by_query_as_paged<P>(
+        &self,
+        query: &diesel_repository::QueryFn<'_, <Self as diesel_repository::QueryRepository>::Query>,
+        page: i64,
+        per_page: i64,
+    ) -> Result<diesel_repository::Paged<P>, diesel::result::Error>
+    where
+        Self: diesel_repository::asynchronous::ProjectPaged<P>,
+    {
+        diesel_repository::asynchronous::ProjectPaged::<P>::find_by_query_as_paged(
+            self, query, page, per_page,
+        )
+        .await
+    }
+}
+#[diesel_repository::async_trait]
+impl diesel_repository::asynchronous::FindById<Account, String> for AccountRepo {
+    async fn find_by_id(&self, id: String) -> Result<Account, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        #[allow(unused_mut)]
+        let mut record: Account = diesel_repository::diesel_async::RunQueryDsl::get_result(
+            crate::accounts::table.find(id),
+            conn,
+        )
+        .await?;
+        diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: after_load (self , & mut record) ? ;
+        Ok(record)
+    }
+}
+#[diesel_repository::async_trait]
+impl diesel_repository::asynchronous::FindOneByQuery<Account> for AccountRepo {
+    async fn find_one_by_query(
+        &self,
+        query: &diesel_repository::QueryFn<'_, <Self as diesel_repository::QueryRepository>::Query>,
+    ) -> Result<Account, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        #[allow(unused_mut)]
+        let mut record: Account = diesel_repository::diesel_async::RunQueryDsl::first(
+            query(crate::accounts::table.into_boxed()),
+            conn,
+        )
+        .await?;
+        diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: after_load (self , & mut record) ? ;
+        Ok(record)
+    }
+}
+#[diesel_repository::async_trait]
+impl diesel_repository::asynchronous::FindByQuery<Account> for AccountRepo {
+    async fn find_by_query(
+        &self,
+        query: &diesel_repository::QueryFn<'_, <Self as diesel_repository::QueryRepository>::Query>,
+    ) -> Result<Vec<Account>, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        #[allow(unused_mut)]
+        let mut record: Vec<Account> = diesel_repository::diesel_async::RunQueryDsl::load(
+            query(crate::accounts::table.into_boxed()),
+            conn,
+        )
+        .await?;
+        for item in record.iter_mut() {
+            diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: after_load (self , item) ? ;
+        }
+        Ok(record)
+    }
+}
+#[diesel_repository::async_trait]
+impl diesel_repository::asynchronous::FindAll<Account> for AccountRepo {
+    async fn find_all(&self) -> Result<Vec<Account>, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        #[allow(unused_mut)]
+        let mut record: Vec<Account> =
+            diesel_repository::diesel_async::RunQueryDsl::load(crate::accounts::table, conn)
+                .await?;
+        for item in record.iter_mut() {
+            diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: after_load (self , item) ? ;
+        }
+        Ok(record)
+    }
+}
+#[diesel_repository::async_trait]
+impl diesel_repository::asynchronous::Save<Account, NewAccount> for AccountRepo {
+    async fn save(&self, mut new_record: NewAccount) -> Result<Account, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        diesel_repository :: diesel_async :: AsyncConnection :: transaction :: < _ , diesel :: result :: Error , _ > (& mut * connection , | conn | { diesel_repository :: diesel_async :: scoped_futures :: ScopedFutureExt :: scope_boxed (async move { use diesel :: { ExpressionMethods , QueryDsl } ; diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: before_save (self , & mut new_record) ? ; # [allow (unused_mut)] let mut record : Account = diesel_repository :: diesel_async :: RunQueryDsl :: get_result (diesel :: insert_into (crate :: accounts :: table) . values (& new_record) , conn ,) . await ? ; diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: after_save (self , & record) ? ; let events = diesel_repository :: DomainEvents :: domain_events (& record , diesel_repository :: Operation :: Insert ,) ; diesel_repository :: write_events_async (conn , "outbox_events" , & events) . await ? ; Ok (record) }) }) . await
+    }
+}
+#[diesel_repository::async_trait]
+impl diesel_repository::asynchronous::Update<Account, Account> for AccountRepo {
+    async fn update(&self, mut update_record: Account) -> Result<Account, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        diesel_repository :: diesel_async :: AsyncConnection :: transaction :: < _ , diesel :: result :: Error , _ > (& mut * connection , | conn | { diesel_repository :: diesel_async :: scoped_futures :: ScopedFutureExt :: scope_boxed (async move { use diesel :: { ExpressionMethods , QueryDsl } ; diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: before_update (self , & mut update_record) ? ; let actor = diesel_repository :: HistoryActor :: current_actor (self) ; let previous = crate :: accounts :: table . find (diesel :: Identifiable :: id (& update_record)) . select ((crate :: accounts :: id , crate :: accounts :: organization_id , crate :: accounts :: sub , diesel :: IntoSql :: into_sql :: < diesel :: sql_types :: Text > (diesel_repository :: Operation :: Update . as_str ()) , diesel :: IntoSql :: into_sql :: < diesel :: sql_types :: Nullable < diesel :: sql_types :: Text >> (actor) , diesel :: dsl :: now ,)) ; let query = diesel :: insert_into (account_history :: table) . values (previous) . into_columns ((account_history :: id , account_history :: organization_id , account_history :: sub , account_history :: operation , account_history :: actor , account_history :: changed_at ,)) ; diesel_repository :: diesel_async :: RunQueryDsl :: execute (query , conn) . await ? ; # [allow (unused_mut)] let mut record : Account = diesel_repository :: diesel_async :: RunQueryDsl :: get_result (diesel :: update (& update_record) . set (& update_record) , conn ,) . await ? ; diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: after_save (self , & record) ? ; let events = diesel_repository :: DomainEvents :: domain_events (& record , diesel_repository :: Operation :: Update ,) ; diesel_repository :: write_events_async (conn , "outbox_events" , & events) . await ? ; Ok (record) }) }) . await
+    }
+}
+#[diesel_repository::async_trait]
+impl diesel_repository::asynchronous::Patch<Account, String, AccountPatch> for AccountRepo {
+    async fn patch(
+        &self,
+        id: String,
+        mut patch_record: AccountPatch,
+    ) -> Result<Account, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        diesel_repository :: diesel_async :: AsyncConnection :: transaction :: < _ , diesel :: result :: Error , _ > (& mut * connection , | conn | { diesel_repository :: diesel_async :: scoped_futures :: ScopedFutureExt :: scope_boxed (async move { use diesel :: { ExpressionMethods , QueryDsl } ; diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: before_patch (self , & id , & mut patch_record) ? ; let changes = diesel :: AsChangeset :: as_changeset (& patch_record) ; if diesel :: query_builder :: QueryFragment :: < diesel :: pg :: Pg > :: is_noop (& changes , & < diesel :: pg :: Pg as Default > :: default ()) ? { let mut record : Account = diesel_repository :: diesel_async :: RunQueryDsl :: get_result (crate :: accounts :: table . find (id) , conn) . await ? ; diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: after_load (self , & mut record) ? ; return Ok (record) ; } let actor = diesel_repository :: HistoryActor :: current_actor (self) ; let previous = crate :: accounts :: table . find (& id) . select ((crate :: accounts :: id , crate :: accounts :: organization_id , crate :: accounts :: sub , diesel :: IntoSql :: into_sql :: < diesel :: sql_types :: Text > (diesel_repository :: Operation :: Update . as_str ()) , diesel :: IntoSql :: into_sql :: < diesel :: sql_types :: Nullable < diesel :: sql_types :: Text >> (actor) , diesel :: dsl :: now ,)) ; let query = diesel :: insert_into (account_history :: table) . values (previous) . into_columns ((account_history :: id , account_history :: organization_id , account_history :: sub , account_history :: operation , account_history :: actor , account_history :: changed_at ,)) ; diesel_repository :: diesel_async :: RunQueryDsl :: execute (query , conn) . await ? ; # [allow (unused_mut)] let mut record : Account = diesel_repository :: diesel_async :: RunQueryDsl :: get_result (diesel :: update (crate :: accounts :: table . find (id)) . set (& patch_record) , conn ,) . await ? ; diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: after_save (self , & record) ? ; let events = diesel_repository :: DomainEvents :: domain_events (& record , diesel_repository :: Operation :: Update ,) ; diesel_repository :: write_events_async (conn , "outbox_events" , & events) . await ? ; Ok (record) }) }) . await
+    }
+}
+#[diesel_repository::async_trait]
+impl diesel_repository::asynchronous::Replace<Account, Account> for AccountRepo {
+    async fn replace(&self, mut update_record: Account) -> Result<Account, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        diesel_repository :: diesel_async :: AsyncConnection :: transaction :: < _ , diesel :: result :: Error , _ > (& mut * connection , | conn | { diesel_repository :: diesel_async :: scoped_futures :: ScopedFutureExt :: scope_boxed (async move { use diesel :: { ExpressionMethods , QueryDsl } ; diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: before_replace (self , & mut update_record) ? ; let actor = diesel_repository :: HistoryActor :: current_actor (self) ; let previous = crate :: accounts :: table . find (diesel :: Identifiable :: id (& update_record)) . select ((crate :: accounts :: id , crate :: accounts :: organization_id , crate :: accounts :: sub , diesel :: IntoSql :: into_sql :: < diesel :: sql_types :: Text > (diesel_repository :: Operation :: Replace . as_str ()) , diesel :: IntoSql :: into_sql :: < diesel :: sql_types :: Nullable < diesel :: sql_types :: Text >> (actor) , diesel :: dsl :: now ,)) ; let query = diesel :: insert_into (account_history :: table) . values (previous) . into_columns ((account_history :: id , account_history :: organization_id , account_history :: sub , account_history :: operation , account_history :: actor , account_history :: changed_at ,)) ; diesel_repository :: diesel_async :: RunQueryDsl :: execute (query , conn) . await ? ; # [allow (unused_mut)] let mut record : Account = diesel_repository :: diesel_async :: RunQueryDsl :: get_result (diesel :: insert_into (crate :: accounts :: table) . values (& update_record) . on_conflict (diesel :: Table :: primary_key (& crate :: accounts :: table)) . do_update () . set (& update_record) , conn ,) . await ? ; diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: after_save (self , & record) ? ; let events = diesel_repository :: DomainEvents :: domain_events (& record , diesel_repository :: Operation :: Replace ,) ; diesel_repository :: write_events_async (conn , "outbox_events" , & events) . await ? ; Ok (record) }) }) . await
+    }
+}
+#[diesel_repository::async_trait]
+impl diesel_repository::asynchronous::Delete<String> for AccountRepo {
+    async fn delete(&self, id: String) -> Result<(), diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        diesel_repository :: diesel_async :: AsyncConnection :: transaction :: < _ , diesel :: result :: Error , _ > (& mut * connection , | conn | { diesel_repository :: diesel_async :: scoped_futures :: ScopedFutureExt :: scope_boxed (async move { use diesel :: { ExpressionMethods , QueryDsl } ; diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: before_delete (self , & id) ? ; let actor = diesel_repository :: HistoryActor :: current_actor (self) ; let previous = crate :: accounts :: table . find (& id) . select ((crate :: accounts :: id , crate :: accounts :: organization_id , crate :: accounts :: sub , diesel :: IntoSql :: into_sql :: < diesel :: sql_types :: Text > (diesel_repository :: Operation :: Delete . as_str ()) , diesel :: IntoSql :: into_sql :: < diesel :: sql_types :: Nullable < diesel :: sql_types :: Text >> (actor) , diesel :: dsl :: now ,)) ; let query = diesel :: insert_into (account_history :: table) . values (previous) . into_columns ((account_history :: id , account_history :: organization_id , account_history :: sub , account_history :: operation , account_history :: actor , account_history :: changed_at ,)) ; diesel_repository :: diesel_async :: RunQueryDsl :: execute (query , conn) . await ? ; # [allow (unused_mut)] let mut record : Account = diesel_repository :: diesel_async :: RunQueryDsl :: get_result (diesel :: delete (crate :: accounts :: table . find (id)) , conn) . await ? ; let events = diesel_repository :: DomainEvents :: domain_events (& record , diesel_repository :: Operation :: Delete ,) ; diesel_repository :: write_events_async (conn , "outbox_events" , & events) . await ? ; Ok (()) }) }) . await
+    }
+}
+#[diesel_repository::async_trait]
+impl diesel_repository::asynchronous::Count for AccountRepo {
+    async fn count(
+        &self,
+        query: &diesel_repository::QueryFn<'_, <Self as diesel_repository::QueryRepository>::Query>,
+    ) -> Result<i64, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        diesel_repository::diesel_async::RunQueryDsl::get_result(
+            query(crate::accounts::table.into_boxed()).count(),
+            conn,
+        )
+        .await
+    }
+}
+#[diesel_repository::async_trait]
+impl diesel_repository::asynchronous::ExistsById<String> for AccountRepo {
+    async fn exists_by_id(&self, id: String) -> Result<bool, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let query = diesel::select(diesel::dsl::exists(crate::accounts::table.find(id)));
+        diesel_repository::diesel_async::RunQueryDsl::get_result(query, conn).await
+    }
+}
+#[diesel_repository::async_trait]
+impl diesel_repository::asynchronous::ExistsByQuery for AccountRepo {
+    async fn exists_by_query(
+        &self,
+        query: &diesel_repository::QueryFn<'_, <Self as diesel_repository::QueryRepository>::Query>,
+    ) -> Result<bool, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let query = query(crate::accounts::table.into_boxed());
+        diesel_repository::diesel_async::RunQueryDsl::get_result(
+            diesel::select(diesel::dsl::exists(query)),
+            conn,
+        )
+        .await
+    }
+}
+#[diesel_repository::async_trait]
+impl<'repo, 'conn, Conn> diesel_repository::asynchronous::Lock<Account, String>
+    for diesel_repository::InTransaction<'repo, 'conn, AccountRepo, Conn>
+where
+    Conn: diesel_repository::diesel_async::AsyncConnection<Backend = diesel::pg::Pg> + Send,
+{
+    async fn find_by_id_locked(
+        &mut self,
+        id: String,
+        mode: diesel_repository::LockMode,
+    ) -> Result<Account, diesel::result::Error> {
+        let repo = self.repo();
+        let conn = self.conn();
+        #[allow(unused_mut)]
+        let mut record: Account = diesel_repository::diesel_async::RunQueryDsl::get_result(
+            diesel_repository::Locked::new(
+                diesel::QueryDsl::find(crate::accounts::table, id),
+                mode,
+            ),
+            conn,
+        )
+        .await?;
+        diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: after_load (repo , & mut record) ? ;
+        Ok(record)
+    }
+    async fn find_by_query_locked(
+        &mut self,
+        query: &diesel_repository::QueryFn<'_, <Self as diesel_repository::QueryRepository>::Query>,
+        mode: diesel_repository::LockMode,
+    ) -> Result<Vec<Account>, diesel::result::Error> {
+        let repo = self.repo();
+        let conn = self.conn();
+        #[allow(unused_mut)]
+        let mut record: Vec<Account> = diesel_repository::diesel_async::RunQueryDsl::load(
+            diesel_repository::Locked::new(
+                query(diesel::QueryDsl::into_boxed(crate::accounts::table)),
+                mode,
+            ),
+            conn,
+        )
+        .await?;
+        for item in record.iter_mut() {
+            diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: after_load (repo , item) ? ;
+        }
+        Ok(record)
+    }
+}
+#[diesel_repository::async_trait]
+impl diesel_repository::asynchronous::History<Account, String> for AccountRepo {
+    async fn history_of(
+        &self,
+        id: String,
+    ) -> Result<Vec<diesel_repository::HistoryEntry<Account>>, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let query = account_history::table
+            .filter(account_history::id.eq(&id))
+            .order(account_history::changed_at.asc())
+            .select((
+                (
+                    account_history::id,
+                    account_history::organization_id,
+                    account_history::sub,
+                ),
+                account_history::operation,
+                account_history::actor,
+                account_history::changed_at,
+            ));
+        let rows: Vec<(Account, String, Option<String>, std::time::SystemTime)> =
+            diesel_repository::diesel_async::RunQueryDsl::load(query, conn).await?;
+        rows.into_iter()
+            .map(diesel_repository::HistoryEntry::from_row)
+            .collect::<Result<Vec<_>, diesel::result::Error>>()
+    }
+    async fn as_of(
+        &self,
+        id: String,
+        timestamp: std::time::SystemTime,
+    ) -> Result<Option<Account>, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        diesel_repository :: diesel_async :: AsyncConnection :: transaction :: < _ , diesel :: result :: Error , _ > (& mut * connection , | conn | { diesel_repository :: diesel_async :: scoped_futures :: ScopedFutureExt :: scope_boxed (async move { use diesel :: { ExpressionMethods , QueryDsl } ; let entries : Vec < diesel_repository :: HistoryEntry < Account >> = { let query = account_history :: table . filter (account_history :: id . eq (& id)) . order (account_history :: changed_at . asc ()) . select (((account_history :: id , account_history :: organization_id , account_history :: sub ,) , account_history :: operation , account_history :: actor , account_history :: changed_at ,)) ; let rows : Vec < (Account , String , Option < String > , std :: time :: SystemTime) > = diesel_repository :: diesel_async :: RunQueryDsl :: load (query , conn) . await ? ; rows . into_iter () . map (diesel_repository :: HistoryEntry :: from_row) . collect :: < Result < Vec < _ > , diesel :: result :: Error >> () } ? ; let current = diesel :: OptionalExtension :: optional (diesel_repository :: diesel_async :: RunQueryDsl :: get_result (crate :: accounts :: table . find (id) , conn) . await ,) ? ; Ok (diesel_repository :: HistoryEntry :: state_at (entries , current , timestamp)) }) }) . await
+    }
+}
+#[diesel_repository::async_trait]
+impl diesel_repository::asynchronous::FindByQueryPaged<Account> for AccountRepo {
+    async fn find_by_query_paged(
+        &self,
+        query: &diesel_repository::QueryFn<'_, <Self as diesel_repository::QueryRepository>::Query>,
+        page: i64,
+        per_page: i64,
+    ) -> Result<diesel_repository::Paged<Account>, diesel::result::Error> {
+        let diesel_repository::PageRequest { page, per_page } =
+            diesel_repository::PageRequest::try_with_limits(page, per_page, Some(20), Some(500))?;
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let offset = (page - 1) * per_page;
+        let total_count: i64 = diesel_repository::diesel_async::RunQueryDsl::get_result(
+            query(crate::accounts::table.into_boxed()).count(),
+            conn,
+        )
+        .await?;
+        let items = diesel_repository::diesel_async::RunQueryDsl::load(
+            query(crate::accounts::table.into_boxed())
+                .limit(per_page)
+                .offset(offset),
+            conn,
+        )
+        .await?;
+        #[allow(unused_mut)]
+        let mut record = diesel_repository::Paged {
+            items,
+            total_count,
+            page,
+            per_page,
+        };
+        for item in record.items.iter_mut() {
+            diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: after_load (self , item) ? ;
+        }
+        Ok(record)
+    }
+}
+#[diesel_repository::async_trait]
+impl diesel_repository::asynchronous::FindAllPaged<Account> for AccountRepo {
+    async fn find_all_paged(
+        &self,
+        page: i64,
+        per_page: i64,
+    ) -> Result<diesel_repository::Paged<Account>, diesel::result::Error> {
+        let diesel_repository::PageRequest { page, per_page } =
+            diesel_repository::PageRequest::try_with_limits(page, per_page, Some(20), Some(500))?;
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let offset = (page - 1) * per_page;
+        let total_count: i64 = diesel_repository::diesel_async::RunQueryDsl::get_result(
+            crate::accounts::table.count(),
+            conn,
+        )
+        .await?;
+        let items = diesel_repository::diesel_async::RunQueryDsl::load(
+            crate::accounts::table.limit(per_page).offset(offset),
+            conn,
+        )
+        .await?;
+        #[allow(unused_mut)]
+        let mut record = diesel_repository::Paged {
+            items,
+            total_count,
+            page,
+            per_page,
+        };
+        for item in record.items.iter_mut() {
+            diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: after_load (self , item) ? ;
+        }
+        Ok(record)
+    }
+}
+#[diesel_repository::async_trait]
+impl diesel_repository::asynchronous::FindByIdBatch<Account, String> for AccountRepo {
+    async fn find_by_id_batch(
+        &self,
+        ids: &[String],
+    ) -> Result<Vec<Account>, diesel::result::Error> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        #[allow(unused_mut)]
+        let mut record: Vec<Account> = diesel_repository::diesel_async::RunQueryDsl::load(
+            crate::accounts::table
+                .filter(diesel::Table::primary_key(&crate::accounts::table).eq_any(ids)),
+            conn,
+        )
+        .await?;
+        for item in record.iter_mut() {
+            diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: after_load (self , item) ? ;
+        }
+        Ok(record)
+    }
+}
+#[diesel_repository::async_trait]
+impl diesel_repository::asynchronous::SaveBatch<Account, NewAccount> for AccountRepo {
+    async fn save_batch(
+        &self,
+        new_records: &[NewAccount],
+    ) -> Result<Vec<Account>, diesel::result::Error> {
+        if new_records.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        diesel_repository :: diesel_async :: AsyncConnection :: transaction :: < _ , diesel :: result :: Error , _ > (& mut * connection , | conn | { diesel_repository :: diesel_async :: scoped_futures :: ScopedFutureExt :: scope_boxed (async move { use diesel :: { ExpressionMethods , QueryDsl } ; let mut new_records = new_records . to_vec () ; for new_record in new_records . iter_mut () { diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: before_save (self , new_record) ? ; } let mut record : Vec < Account > = Vec :: with_capacity (new_records . len ()) ; for new_record in new_records . iter () { let saved = diesel_repository :: diesel_async :: RunQueryDsl :: get_result (diesel :: insert_into (crate :: accounts :: table) . values (new_record) , conn ,) . await ? ; record . push (saved) ; } for item in record . iter () { diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: after_save (self , item) ? ; } Ok (record) }) }) . await
+    }
+}
+#[diesel_repository::async_trait]
+impl diesel_repository::asynchronous::UpdateBatch<Account, Account> for AccountRepo {
+    async fn update_batch(
+        &self,
+        update_records: &[Account],
+    ) -> Result<Vec<Account>, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        diesel_repository :: diesel_async :: AsyncConnection :: transaction :: < _ , diesel :: result :: Error , _ > (& mut * connection , | conn | { diesel_repository :: diesel_async :: scoped_futures :: ScopedFutureExt :: scope_boxed (async move { use diesel :: { ExpressionMethods , QueryDsl } ; let mut update_records = update_records . to_vec () ; for update_record in update_records . iter_mut () { diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: before_update (self , update_record) ? ; } let mut record : Vec < Account > = Vec :: with_capacity (update_records . len ()) ; for update_record in update_records . iter () { let actor = diesel_repository :: HistoryActor :: current_actor (self) ; let previous = crate :: accounts :: table . find (diesel :: Identifiable :: id (update_record)) . select ((crate :: accounts :: id , crate :: accounts :: organization_id , crate :: accounts :: sub , diesel :: IntoSql :: into_sql :: < diesel :: sql_types :: Text > (diesel_repository :: Operation :: Update . as_str ()) , diesel :: IntoSql :: into_sql :: < diesel :: sql_types :: Nullable < diesel :: sql_types :: Text >> (actor) , diesel :: dsl :: now ,)) ; let query = diesel :: insert_into (account_history :: table) . values (previous) . into_columns ((account_history :: id , account_history :: organization_id , account_history :: sub , account_history :: operation , account_history :: actor , account_history :: changed_at ,)) ; diesel_repository :: diesel_async :: RunQueryDsl :: execute (query , conn) . await ? ; let updated = diesel_repository :: diesel_async :: RunQueryDsl :: get_result (diesel :: update (update_record) . set (update_record) , conn ,) . await ? ; record . push (updated) ; } for item in record . iter () { diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: after_save (self , item) ? ; } Ok (record) }) }) . await
+    }
+}
+#[diesel_repository::async_trait]
+impl diesel_repository::asynchronous::DeleteBatch<String> for AccountRepo {
+    async fn delete_batch(&self, ids: &[String]) -> Result<(), diesel::result::Error> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        diesel_repository :: diesel_async :: AsyncConnection :: transaction :: < _ , diesel :: result :: Error , _ > (& mut * connection , | conn | { diesel_repository :: diesel_async :: scoped_futures :: ScopedFutureExt :: scope_boxed (async move { use diesel :: { ExpressionMethods , QueryDsl } ; for id in ids { diesel_repository :: RepoHooks :: < Account , String , NewAccount , Account , AccountPatch > :: before_delete (self , id) ? ; } let actor = diesel_repository :: HistoryActor :: current_actor (self) ; let previous = crate :: accounts :: table . filter (diesel :: Table :: primary_key (& crate :: accounts :: table) . eq_any (ids)) . select ((crate :: accounts :: id , crate :: accounts :: organization_id , crate :: accounts :: sub , diesel :: IntoSql :: into_sql :: < diesel :: sql_types :: Text > (diesel_repository :: Operation :: Delete . as_str ()) , diesel :: IntoSql :: into_sql :: < diesel :: sql_types :: Nullable < diesel :: sql_types :: Text >> (actor) , diesel :: dsl :: now ,)) ; let query = diesel :: insert_into (account_history :: table) . values (previous) . into_columns ((account_history :: id , account_history :: organization_id , account_history :: sub , account_history :: operation , account_history :: actor , account_history :: changed_at ,)) ; diesel_repository :: diesel_async :: RunQueryDsl :: execute (query , conn) . await ? ; let query = diesel :: delete (crate :: accounts :: table . filter (diesel :: Table :: primary_key (& crate :: accounts :: table) . eq_any (ids)) ,) ; diesel_repository :: diesel_async :: RunQueryDsl :: execute (query , conn) . await ? ; Ok (()) }) }) . await
+    }
+}
+#[diesel_repository::async_trait]
+impl diesel_repository::asynchronous::ExistsByIdBatch<String> for AccountRepo {
+    async fn exists_by_id_batch(
+        &self,
+        ids: &[String],
+    ) -> Result<Vec<String>, diesel::result::Error> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let key = diesel::Table::primary_key(&crate::accounts::table);
+        let query = crate::accounts::table.filter(key.eq_any(ids)).select(key);
+        diesel_repository::diesel_async::RunQueryDsl::load(query, conn).await
+    }
+}
+#[diesel_repository::async_trait]
+impl diesel_repository::asynchronous::Project<AccountName> for AccountRepo {
+    async fn find_all_as(&self) -> Result<Vec<AccountName>, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        diesel_repository::diesel_async::RunQueryDsl::load(
+            crate::accounts::table
+                .select(<AccountName as diesel::SelectableHelper<diesel::pg::Pg>>::as_select()),
+            conn,
+        )
+        .await
+    }
+    async fn find_by_query_as(
+        &self,
+        query: &diesel_repository::QueryFn<'_, <Self as diesel_repository::QueryRepository>::Query>,
+    ) -> Result<Vec<AccountName>, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let query = query(crate::accounts::table.into_boxed())
+            .select(<AccountName as diesel::SelectableHelper<diesel::pg::Pg>>::as_select());
+        diesel_repository::diesel_async::RunQueryDsl::load(query, conn).await
+    }
+}
+#[diesel_repository::async_trait]
+impl diesel_repository::asynchronous::ProjectPaged<AccountName> for AccountRepo {
+    async fn find_all_as_paged(
+        &self,
+        page: i64,
+        per_page: i64,
+    ) -> Result<diesel_repository::Paged<AccountName>, diesel::result::Error> {
+        let diesel_repository::PageRequest { page, per_page } =
+            diesel_repository::PageRequest::try_with_limits(page, per_page, Some(20), Some(500))?;
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let offset = (page - 1) * per_page;
+        let total_count: i64 = diesel_repository::diesel_async::RunQueryDsl::get_result(
+            crate::accounts::table.count(),
+            conn,
+        )
+        .await?;
+        let items = diesel_repository::diesel_async::RunQueryDsl::load(
+            crate::accounts::table
+                .select(<AccountName as diesel::SelectableHelper<diesel::pg::Pg>>::as_select())
+                .limit(per_page)
+                .offset(offset),
+            conn,
+        )
+        .await?;
+        #[allow(unused_mut)]
+        let mut record = diesel_repository::Paged {
+            items,
+            total_count,
+            page,
+            per_page,
+        };
+        Ok(record)
+    }
+    async fn find_by_query_as_paged(
+        &self,
+        query: &diesel_repository::QueryFn<'_, <Self as diesel_repository::QueryRepository>::Query>,
+        page: i64,
+        per_page: i64,
+    ) -> Result<diesel_repository::Paged<AccountName>, diesel::result::Error> {
+        let diesel_repository::PageRequest { page, per_page } =
+            diesel_repository::PageRequest::try_with_limits(page, per_page, Some(20), Some(500))?;
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let offset = (page - 1) * per_page;
+        let total_count: i64 = diesel_repository::diesel_async::RunQueryDsl::get_result(
+            query(crate::accounts::table.into_boxed()).count(),
+            conn,
+        )
+        .await?;
+        let items = diesel_repository::diesel_async::RunQueryDsl::load(
+            query(crate::accounts::table.into_boxed())
+                .select(<AccountName as diesel::SelectableHelper<diesel::pg::Pg>>::as_select())
+                .limit(per_page)
+                .offset(offset),
+            conn,
+        )
+        .await?;
+        #[allow(unused_mut)]
+        let mut record = diesel_repository::Paged {
+            items,
+            total_count,
+            page,
+            per_page,
+        };
+        Ok(record)
+    }
+}
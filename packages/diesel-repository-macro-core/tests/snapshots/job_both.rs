@@ -0,0 +1,257 @@
+pub struct JobRepo {
+    pool: std::sync::Arc<db::DbPool>,
+    _entity: std::marker::PhantomData<fn() -> Job>,
+}
+#[doc = "Builds a [`JobRepo`] field by field."]
+pub struct JobRepoBuilder {
+    pool: Option<std::sync::Arc<db::DbPool>>,
+    _entity: std::marker::PhantomData<fn() -> Job>,
+}
+impl Default for JobRepoBuilder {
+    fn default() -> Self {
+        Self {
+            pool: None,
+            _entity: std::marker::PhantomData,
+        }
+    }
+}
+impl JobRepoBuilder {
+    pub fn pool(mut self, pool: std::sync::Arc<db::DbPool>) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+    #[doc = r" Creates the repository, failing on the first field that was not set."]
+    pub fn build(self) -> Result<JobRepo, diesel_repository::BuildError> {
+        Ok(JobRepo {
+            pool: self
+                .pool
+                .ok_or(diesel_repository::BuildError { field: "pool" })?,
+            _entity: std::marker::PhantomData,
+        })
+    }
+}
+impl JobRepo {
+    pub fn new(pool: std::sync::Arc<db::DbPool>) -> Self {
+        Self {
+            pool,
+            _entity: std::marker::PhantomData,
+        }
+    }
+    pub fn builder() -> JobRepoBuilder {
+        JobRepoBuilder::default()
+    }
+    #[doc = r" The queue table and columns the job-queue methods work on."]
+    pub const QUEUE: diesel_repository::QueueTable = diesel_repository::QueueTable {
+        table: "jobs",
+        id: "id",
+        status: "status",
+        run_at: "run_at",
+        attempts: "attempts",
+    };
+}
+impl diesel_repository::Repository for JobRepo {
+    type Entity = Job;
+    type Id = i64;
+}
+impl diesel_repository::SchemaCheck for JobRepo {
+    const SCHEMA: diesel_repository::TableSchema = diesel_repository::TableSchema {
+        table: "jobs",
+        columns: &[
+            diesel_repository::ColumnSchema::of::<diesel::dsl::SqlTypeOf<crate::jobs::id>>("id"),
+            diesel_repository::ColumnSchema::of::<diesel::dsl::SqlTypeOf<crate::jobs::payload>>(
+                "payload",
+            ),
+            diesel_repository::ColumnSchema::of::<diesel::dsl::SqlTypeOf<crate::jobs::status>>(
+                "status",
+            ),
+            diesel_repository::ColumnSchema::of::<diesel::dsl::SqlTypeOf<crate::jobs::run_at>>(
+                "run_at",
+            ),
+            diesel_repository::ColumnSchema::of::<diesel::dsl::SqlTypeOf<crate::jobs::attempts>>(
+                "attempts",
+            ),
+        ],
+    };
+}
+const _: () = {
+    #[allow(dead_code)]
+    fn records() {
+        fn new_record<N: diesel_repository::NewRecord<T>, T>() {}
+        fn update_record<U: diesel_repository::UpdateRecord<T>, T>() {}
+        new_record::<NewJob, crate::jobs::table>();
+    }
+};
+impl JobRepo {
+    #[doc = r" Inserts a job; the table's defaults make it pending and due now."]
+    pub fn enqueue(&self, new_record: NewJob) -> Result<Job, diesel::result::Error> {
+        let mut connection = diesel_repository::ConnectionSource::connection(&*self.pool)?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        diesel::RunQueryDsl::get_result(
+            diesel::insert_into(crate::jobs::table).values(&new_record),
+            conn,
+        )
+    }
+    #[doc = r" Claims up to `n` due jobs with `FOR UPDATE SKIP LOCKED`, so concurrent workers"]
+    #[doc = r" never claim the same job. Claimed jobs are running and count one more attempt."]
+    pub fn dequeue(&self, n: i64) -> Result<Vec<Job>, diesel::result::Error> {
+        let mut connection = diesel_repository::ConnectionSource::connection(&*self.pool)?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let query = diesel::sql_query(<JobRepo>::QUEUE.dequeue_sql())
+            .bind::<diesel::sql_types::BigInt, _>(n);
+        diesel::RunQueryDsl::load(query, conn)
+    }
+    #[doc = r" Marks a running job as done, failing with `NotFound` if it is not running."]
+    pub fn complete(&self, id: i64) -> Result<(), diesel::result::Error> {
+        let mut connection = diesel_repository::ConnectionSource::connection(&*self.pool)?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let query = diesel::sql_query(<JobRepo>::QUEUE.complete_sql())
+            .bind::<diesel::dsl::SqlTypeOf<crate::jobs::id>, _>(id);
+        match diesel::RunQueryDsl::execute(query, conn)? {
+            0 => Err(diesel::result::Error::NotFound),
+            _ => Ok(()),
+        }
+    }
+    #[doc = r" Reschedules a running job after a failed attempt, or marks it as failed once"]
+    #[doc = r" `backoff` gives up on it."]
+    pub fn fail_with_backoff(
+        &self,
+        id: i64,
+        backoff: &diesel_repository::Backoff,
+    ) -> Result<Job, diesel::result::Error> {
+        let mut connection = diesel_repository::ConnectionSource::connection(&*self.pool)?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let query = diesel::sql_query(<JobRepo>::QUEUE.fail_sql())
+            .bind::<diesel::dsl::SqlTypeOf<crate::jobs::id>, _>(id)
+            .bind::<diesel::sql_types::Integer, _>(backoff.max_attempts)
+            .bind::<diesel::sql_types::Double, _>(diesel_repository::QueueTable::millis(
+                backoff.base,
+            ))
+            .bind::<diesel::sql_types::Double, _>(diesel_repository::QueueTable::millis(
+                backoff.max_delay,
+            ));
+        diesel::RunQueryDsl::get_result(query, conn)
+    }
+    #[doc = r" Makes jobs running for longer than `older_than` due again, e.g. after their"]
+    #[doc = r" worker crashed, and returns how many were reaped. A reaped job that already"]
+    #[doc = r" used `max_attempts` attempts is marked as failed instead."]
+    pub fn reap_stuck(
+        &self,
+        older_than: std::time::Duration,
+        max_attempts: i32,
+    ) -> Result<usize, diesel::result::Error> {
+        let mut connection = diesel_repository::ConnectionSource::connection(&*self.pool)?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let query = diesel::sql_query(<JobRepo>::QUEUE.reap_sql())
+            .bind::<diesel::sql_types::Double, _>(diesel_repository::QueueTable::millis(older_than))
+            .bind::<diesel::sql_types::Integer, _>(max_attempts);
+        diesel::RunQueryDsl::execute(query, conn)
+    }
+}
+impl JobRepo {
+    #[doc = r" Inserts a job; the table's defaults make it pending and due now."]
+    pub async fn enqueue_async(&self, new_record: NewJob) -> Result<Job, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        diesel_repository::diesel_async::RunQueryDsl::get_result(
+            diesel::insert_into(crate::jobs::table).values(&new_record),
+            conn,
+        )
+        .await
+    }
+    #[doc = r" Claims up to `n` due jobs with `FOR UPDATE SKIP LOCKED`, so concurrent workers"]
+    #[doc = r" never claim the same job. Claimed jobs are running and count one more attempt."]
+    pub async fn dequeue_async(&self, n: i64) -> Result<Vec<Job>, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let query = diesel::sql_query(<JobRepo>::QUEUE.dequeue_sql())
+            .bind::<diesel::sql_types::BigInt, _>(n);
+        diesel_repository::diesel_async::RunQueryDsl::load(query, conn).await
+    }
+    #[doc = r" Marks a running job as done, failing with `NotFound` if it is not running."]
+    pub async fn complete_async(&self, id: i64) -> Result<(), diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let query = diesel::sql_query(<JobRepo>::QUEUE.complete_sql())
+            .bind::<diesel::dsl::SqlTypeOf<crate::jobs::id>, _>(id);
+        match diesel_repository::diesel_async::RunQueryDsl::execute(query, conn).await? {
+            0 => Err(diesel::result::Error::NotFound),
+            _ => Ok(()),
+        }
+    }
+    #[doc = r" Reschedules a running job after a failed attempt, or marks it as failed once"]
+    #[doc = r" `backoff` gives up on it."]
+    pub async fn fail_with_backoff_async(
+        &self,
+        id: i64,
+        backoff: &diesel_repository::Backoff,
+    ) -> Result<Job, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let query = diesel::sql_query(<JobRepo>::QUEUE.fail_sql())
+            .bind::<diesel::dsl::SqlTypeOf<crate::jobs::id>, _>(id)
+            .bind::<diesel::sql_types::Integer, _>(backoff.max_attempts)
+            .bind::<diesel::sql_types::Double, _>(diesel_repository::QueueTable::millis(
+                backoff.base,
+            ))
+            .bind::<diesel::sql_types::Double, _>(diesel_repository::QueueTable::millis(
+                backoff.max_delay,
+            ));
+        diesel_repository::diesel_async::RunQueryDsl::get_result(query, conn).await
+    }
+    #[doc = r" Makes jobs running for longer than `older_than` due again, e.g. after their"]
+    #[doc = r" worker crashed, and returns how many were reaped. A reaped job that already"]
+    #[doc = r" used `max_attempts` attempts is marked as failed instead."]
+    pub async fn reap_stuck_async(
+        &self,
+        older_than: std::time::Duration,
+        max_attempts: i32,
+    ) -> Result<usize, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let query = diesel::sql_query(<JobRepo>::QUEUE.reap_sql())
+            .bind::<diesel::sql_types::Double, _>(diesel_repository::QueueTable::millis(older_than))
+            .bind::<diesel::sql_types::Integer, _>(max_attempts);
+        diesel_repository::diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+}
+#[diesel_repository::async_trait]
+impl diesel_repository::asynchronous::Subscribe<Job, i64> for JobRepo {
+    const CHANNEL: &'static str = "jobs_changes";
+    async fn install_trigger(&self) -> Result<(), diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        diesel_repository::install_notify_trigger(&mut *connection, "jobs", "id", Self::CHANNEL)
+            .await
+    }
+    async fn changes(
+        &self,
+        database_url: &str,
+    ) -> Result<diesel_repository::ChangeStream<Job, i64>, diesel::result::Error> {
+        let load: diesel_repository::LoadChanged<Job, i64> = |conn, id| {
+            Box::pin(async move {
+                let query = diesel::QueryDsl::find(crate::jobs::table, id);
+                diesel::OptionalExtension::optional(
+                    diesel_repository::diesel_async::RunQueryDsl::get_result(query, conn).await,
+                )
+            })
+        };
+        let listener =
+            diesel_repository::ChangeListener::connect(database_url, Self::CHANNEL).await?;
+        Ok(listener.changes(load))
+    }
+}
@@ -0,0 +1,500 @@
+pub(crate) struct TagStore<'a, T: Clone>
+where
+    T: Send,
+{
+    pool: std::sync::Arc<db::DbPool>,
+    _entity: std::marker::PhantomData<fn() -> Tagged<'a, T>>,
+}
+#[doc = "Builds a [`TagStore`] field by field."]
+pub(crate) struct TagStoreBuilder<'a, T: Clone>
+where
+    T: Send,
+{
+    pool: Option<std::sync::Arc<db::DbPool>>,
+    _entity: std::marker::PhantomData<fn() -> Tagged<'a, T>>,
+}
+impl<'a, T: Clone> Default for TagStoreBuilder<'a, T>
+where
+    T: Send,
+{
+    fn default() -> Self {
+        Self {
+            pool: None,
+            _entity: std::marker::PhantomData,
+        }
+    }
+}
+impl<'a, T: Clone> TagStoreBuilder<'a, T>
+where
+    T: Send,
+{
+    pub fn pool(mut self, pool: std::sync::Arc<db::DbPool>) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+    #[doc = r" Creates the repository, failing on the first field that was not set."]
+    pub fn build(self) -> Result<TagStore<'a, T>, diesel_repository::BuildError> {
+        Ok(TagStore {
+            pool: self
+                .pool
+                .ok_or(diesel_repository::BuildError { field: "pool" })?,
+            _entity: std::marker::PhantomData,
+        })
+    }
+}
+impl<'a, T: Clone> TagStore<'a, T>
+where
+    T: Send,
+{
+    pub fn new(pool: std::sync::Arc<db::DbPool>) -> Self {
+        Self {
+            pool,
+            _entity: std::marker::PhantomData,
+        }
+    }
+    pub fn builder() -> TagStoreBuilder<'a, T> {
+        TagStoreBuilder::default()
+    }
+    #[doc = r" A view of the repository applying `context` in every transaction it runs."]
+    pub fn with_context<'ctx>(
+        &'ctx self,
+        context: diesel_repository::SessionContext,
+    ) -> TagStoreWithContext<'ctx, 'a, T> {
+        TagStoreWithContext {
+            repo: self,
+            context,
+        }
+    }
+}
+impl<'a, T: Clone> diesel_repository::Repository for TagStore<'a, T>
+where
+    T: Send,
+{
+    type Entity = Tagged<'a, T>;
+    type Id = i32;
+}
+#[doc = "A [`TagStore`] applying a [`SessionContext`](diesel_repository::SessionContext) in every transaction it runs."]
+pub(crate) struct TagStoreWithContext<'ctx, 'a, T: Clone>
+where
+    T: Send,
+{
+    repo: &'ctx TagStore<'a, T>,
+    context: diesel_repository::SessionContext,
+}
+impl<'ctx, 'a, T: Clone> TagStoreWithContext<'ctx, 'a, T>
+where
+    T: Send,
+{
+    #[doc = r" The repository this view was created from."]
+    pub fn repo(&self) -> &'ctx TagStore<'a, T> {
+        self.repo
+    }
+    #[doc = r" The settings applied in every transaction of this view."]
+    pub fn context(&self) -> &diesel_repository::SessionContext {
+        &self.context
+    }
+}
+impl<'ctx, 'a, T: Clone> diesel_repository::Repository for TagStoreWithContext<'ctx, 'a, T>
+where
+    T: Send,
+{
+    type Entity = Tagged<'a, T>;
+    type Id = i32;
+}
+impl<'a, T: Clone> TagStore<'a, T>
+where
+    T: Send,
+{
+    #[doc = "Runs `UPDATE tags SET label = $2 WHERE id = $1`."]
+    pub fn relabel(&self, id: i32, label: String) -> Result<usize, diesel::result::Error> {
+        let mut connection = diesel_repository::ConnectionSource::connection(&*self.pool)?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let query = diesel::sql_query("UPDATE tags SET label = $2 WHERE id = $1")
+            .bind::<diesel::sql_types::Integer, _>(id)
+            .bind::<diesel::sql_types::Text, _>(label);
+        diesel::RunQueryDsl::execute(query, conn)
+    }
+}
+impl<'a, T: Clone> diesel_repository::sync::FindById<Tagged<'a, T>, i32> for TagStore<'a, T>
+where
+    T: Send,
+{
+    fn find_by_id(&self, id: i32) -> Result<Tagged<'a, T>, diesel::result::Error> {
+        let mut connection = diesel_repository::ConnectionSource::connection(&*self.pool)?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        #[allow(unused_mut)]
+        let mut record: Tagged<'a, T> =
+            diesel::RunQueryDsl::get_result(crate::tags::table.find(id), conn)?;
+        Ok(record)
+    }
+}
+impl<'a, T: Clone> diesel_repository::sync::FindAll<Tagged<'a, T>> for TagStore<'a, T>
+where
+    T: Send,
+{
+    fn find_all(&self) -> Result<Vec<Tagged<'a, T>>, diesel::result::Error> {
+        let mut connection = diesel_repository::ConnectionSource::connection(&*self.pool)?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        #[allow(unused_mut)]
+        let mut record: Vec<Tagged<'a, T>> = diesel::RunQueryDsl::load(crate::tags::table, conn)?;
+        Ok(record)
+    }
+}
+impl<'a, T: Clone> diesel_repository::sync::FindAllSlice<Tagged<'a, T>> for TagStore<'a, T>
+where
+    T: Send,
+{
+    fn find_all_slice(
+        &self,
+        page: i64,
+        per_page: i64,
+    ) -> Result<diesel_repository::Slice<Tagged<'a, T>>, diesel::result::Error> {
+        let diesel_repository::PageRequest { page, per_page } =
+            diesel_repository::PageRequest::try_with_limits(page, per_page, None, None)?;
+        let mut connection = diesel_repository::ConnectionSource::connection(&*self.pool)?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let offset = (page - 1) * per_page;
+        let page_query = crate::tags::table
+            .limit(per_page.saturating_add(1))
+            .offset(offset);
+        let items = diesel::RunQueryDsl::load(page_query, conn)?;
+        #[allow(unused_mut)]
+        let mut record = diesel_repository::Slice::from_overfetch(items, page, per_page);
+        Ok(record)
+    }
+}
+impl<'a, T: Clone> diesel_repository::sync::FindAllPaged<Tagged<'a, T>> for TagStore<'a, T>
+where
+    T: Send,
+{
+    fn find_all_paged(
+        &self,
+        page: i64,
+        per_page: i64,
+    ) -> Result<diesel_repository::Paged<Tagged<'a, T>>, diesel::result::Error> {
+        let diesel_repository::PageRequest { page, per_page } =
+            diesel_repository::PageRequest::try_with_limits(page, per_page, None, None)?;
+        let mut connection = diesel_repository::ConnectionSource::connection(&*self.pool)?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let offset = (page - 1) * per_page;
+        let total_count: i64 = diesel::RunQueryDsl::get_result(crate::tags::table.count(), conn)?;
+        let items =
+            diesel::RunQueryDsl::load(crate::tags::table.limit(per_page).offset(offset), conn)?;
+        #[allow(unused_mut)]
+        let mut record = diesel_repository::Paged {
+            items,
+            total_count,
+            page,
+            per_page,
+        };
+        Ok(record)
+    }
+}
+impl<'a, T: Clone> TagStore<'a, T>
+where
+    T: Send,
+{
+    #[doc = "Runs `UPDATE tags SET label = $2 WHERE id = $1`."]
+    pub async fn relabel_async(
+        &self,
+        id: i32,
+        label: String,
+    ) -> Result<usize, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let query = diesel::sql_query("UPDATE tags SET label = $2 WHERE id = $1")
+            .bind::<diesel::sql_types::Integer, _>(id)
+            .bind::<diesel::sql_types::Text, _>(label);
+        diesel_repository::diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+}
+#[diesel_repository::async_trait]
+impl<'a, T: Clone> diesel_repository::asynchronous::FindById<Tagged<'a, T>, i32> for TagStore<'a, T>
+where
+    T: Send,
+{
+    async fn find_by_id(&self, id: i32) -> Result<Tagged<'a, T>, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        #[allow(unused_mut)]
+        let mut record: Tagged<'a, T> = diesel_repository::diesel_async::RunQueryDsl::get_result(
+            crate::tags::table.find(id),
+            conn,
+        )
+        .await?;
+        Ok(record)
+    }
+}
+#[diesel_repository::async_trait]
+impl<'a, T: Clone> diesel_repository::asynchronous::FindAll<Tagged<'a, T>> for TagStore<'a, T>
+where
+    T: Send,
+{
+    async fn find_all(&self) -> Result<Vec<Tagged<'a, T>>, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        #[allow(unused_mut)]
+        let mut record: Vec<Tagged<'a, T>> =
+            diesel_repository::diesel_async::RunQueryDsl::load(crate::tags::table, conn).await?;
+        Ok(record)
+    }
+}
+#[diesel_repository::async_trait]
+impl<'a, T: Clone> diesel_repository::asynchronous::FindAllSlice<Tagged<'a, T>> for TagStore<'a, T>
+where
+    T: Send,
+{
+    async fn find_all_slice(
+        &self,
+        page: i64,
+        per_page: i64,
+    ) -> Result<diesel_repository::Slice<Tagged<'a, T>>, diesel::result::Error> {
+        let diesel_repository::PageRequest { page, per_page } =
+            diesel_repository::PageRequest::try_with_limits(page, per_page, None, None)?;
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let offset = (page - 1) * per_page;
+        let page_query = crate::tags::table
+            .limit(per_page.saturating_add(1))
+            .offset(offset);
+        let items = diesel_repository::diesel_async::RunQueryDsl::load(page_query, conn).await?;
+        #[allow(unused_mut)]
+        let mut record = diesel_repository::Slice::from_overfetch(items, page, per_page);
+        Ok(record)
+    }
+}
+#[diesel_repository::async_trait]
+impl<'a, T: Clone> diesel_repository::asynchronous::FindAllPaged<Tagged<'a, T>> for TagStore<'a, T>
+where
+    T: Send,
+{
+    async fn find_all_paged(
+        &self,
+        page: i64,
+        per_page: i64,
+    ) -> Result<diesel_repository::Paged<Tagged<'a, T>>, diesel::result::Error> {
+        let diesel_repository::PageRequest { page, per_page } =
+            diesel_repository::PageRequest::try_with_limits(page, per_page, None, None)?;
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.pool).await?;
+        let conn = &mut *connection;
+        use diesel::{ExpressionMethods, QueryDsl};
+        let offset = (page - 1) * per_page;
+        let total_count: i64 = diesel_repository::diesel_async::RunQueryDsl::get_result(
+            crate::tags::table.count(),
+            conn,
+        )
+        .await?;
+        let items = diesel_repository::diesel_async::RunQueryDsl::load(
+            crate::tags::table.limit(per_page).offset(offset),
+            conn,
+        )
+        .await?;
+        #[allow(unused_mut)]
+        let mut record = diesel_repository::Paged {
+            items,
+            total_count,
+            page,
+            per_page,
+        };
+        Ok(record)
+    }
+}
+impl<'ctx, 'a, T: Clone> TagStoreWithContext<'ctx, 'a, T>
+where
+    T: Send,
+{
+    #[doc = "Runs `UPDATE tags SET label = $2 WHERE id = $1`."]
+    pub fn relabel(&self, id: i32, label: String) -> Result<usize, diesel::result::Error> {
+        let mut connection = diesel_repository::ConnectionSource::connection(&*self.repo().pool)?;
+        diesel::Connection::transaction::<_, diesel::result::Error, _>(&mut *connection, |conn| {
+            use diesel::{ExpressionMethods, QueryDsl};
+            diesel_repository::SessionContext::apply(self.context(), conn)?;
+            let query = diesel::sql_query("UPDATE tags SET label = $2 WHERE id = $1")
+                .bind::<diesel::sql_types::Integer, _>(id)
+                .bind::<diesel::sql_types::Text, _>(label);
+            diesel::RunQueryDsl::execute(query, conn)
+        })
+    }
+}
+impl<'ctx, 'a, T: Clone> diesel_repository::sync::FindById<Tagged<'a, T>, i32>
+    for TagStoreWithContext<'ctx, 'a, T>
+where
+    T: Send,
+{
+    fn find_by_id(&self, id: i32) -> Result<Tagged<'a, T>, diesel::result::Error> {
+        let mut connection = diesel_repository::ConnectionSource::connection(&*self.repo().pool)?;
+        diesel::Connection::transaction::<_, diesel::result::Error, _>(&mut *connection, |conn| {
+            use diesel::{ExpressionMethods, QueryDsl};
+            diesel_repository::SessionContext::apply(self.context(), conn)?;
+            #[allow(unused_mut)]
+            let mut record: Tagged<'a, T> =
+                diesel::RunQueryDsl::get_result(crate::tags::table.find(id), conn)?;
+            Ok(record)
+        })
+    }
+}
+impl<'ctx, 'a, T: Clone> diesel_repository::sync::FindAll<Tagged<'a, T>>
+    for TagStoreWithContext<'ctx, 'a, T>
+where
+    T: Send,
+{
+    fn find_all(&self) -> Result<Vec<Tagged<'a, T>>, diesel::result::Error> {
+        let mut connection = diesel_repository::ConnectionSource::connection(&*self.repo().pool)?;
+        diesel::Connection::transaction::<_, diesel::result::Error, _>(&mut *connection, |conn| {
+            use diesel::{ExpressionMethods, QueryDsl};
+            diesel_repository::SessionContext::apply(self.context(), conn)?;
+            #[allow(unused_mut)]
+            let mut record: Vec<Tagged<'a, T>> =
+                diesel::RunQueryDsl::load(crate::tags::table, conn)?;
+            Ok(record)
+        })
+    }
+}
+impl<'ctx, 'a, T: Clone> diesel_repository::sync::FindAllSlice<Tagged<'a, T>>
+    for TagStoreWithContext<'ctx, 'a, T>
+where
+    T: Send,
+{
+    fn find_all_slice(
+        &self,
+        page: i64,
+        per_page: i64,
+    ) -> Result<diesel_repository::Slice<Tagged<'a, T>>, diesel::result::Error> {
+        let diesel_repository::PageRequest { page, per_page } =
+            diesel_repository::PageRequest::try_with_limits(page, per_page, None, None)?;
+        let mut connection = diesel_repository::ConnectionSource::connection(&*self.repo().pool)?;
+        diesel::Connection::transaction::<_, diesel::result::Error, _>(&mut *connection, |conn| {
+            use diesel::{ExpressionMethods, QueryDsl};
+            diesel_repository::SessionContext::apply(self.context(), conn)?;
+            let offset = (page - 1) * per_page;
+            let page_query = crate::tags::table
+                .limit(per_page.saturating_add(1))
+                .offset(offset);
+            let items = diesel::RunQueryDsl::load(page_query, conn)?;
+            #[allow(unused_mut)]
+            let mut record = diesel_repository::Slice::from_overfetch(items, page, per_page);
+            Ok(record)
+        })
+    }
+}
+impl<'ctx, 'a, T: Clone> diesel_repository::sync::FindAllPaged<Tagged<'a, T>>
+    for TagStoreWithContext<'ctx, 'a, T>
+where
+    T: Send,
+{
+    fn find_all_paged(
+        &self,
+        page: i64,
+        per_page: i64,
+    ) -> Result<diesel_repository::Paged<Tagged<'a, T>>, diesel::result::Error> {
+        let diesel_repository::PageRequest { page, per_page } =
+            diesel_repository::PageRequest::try_with_limits(page, per_page, None, None)?;
+        let mut connection = diesel_repository::ConnectionSource::connection(&*self.repo().pool)?;
+        diesel::Connection::transaction::<_, diesel::result::Error, _>(&mut *connection, |conn| {
+            use diesel::{ExpressionMethods, QueryDsl};
+            diesel_repository::SessionContext::apply(self.context(), conn)?;
+            let offset = (page - 1) * per_page;
+            let total_count: i64 =
+                diesel::RunQueryDsl::get_result(crate::tags::table.count(), conn)?;
+            let items =
+                diesel::RunQueryDsl::load(crate::tags::table.limit(per_page).offset(offset), conn)?;
+            #[allow(unused_mut)]
+            let mut record = diesel_repository::Paged {
+                items,
+                total_count,
+                page,
+                per_page,
+            };
+            Ok(record)
+        })
+    }
+}
+impl<'ctx, 'a, T: Clone> TagStoreWithContext<'ctx, 'a, T>
+where
+    T: Send,
+{
+    #[doc = "Runs `UPDATE tags SET label = $2 WHERE id = $1`."]
+    pub async fn relabel_async(
+        &self,
+        id: i32,
+        label: String,
+    ) -> Result<usize, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.repo().pool).await?;
+        diesel_repository :: diesel_async :: AsyncConnection :: transaction :: < _ , diesel :: result :: Error , _ > (& mut * connection , | conn | { diesel_repository :: diesel_async :: scoped_futures :: ScopedFutureExt :: scope_boxed (async move { use diesel :: { ExpressionMethods , QueryDsl } ; diesel_repository :: SessionContext :: apply_async (self . context () , conn) . await ? ; let query = diesel :: sql_query ("UPDATE tags SET label = $2 WHERE id = $1") . bind :: < diesel :: sql_types :: Integer , _ > (id) . bind :: < diesel :: sql_types :: Text , _ > (label) ; diesel_repository :: diesel_async :: RunQueryDsl :: execute (query , conn) . await }) }) . await
+    }
+}
+#[diesel_repository::async_trait]
+impl<'ctx, 'a, T: Clone> diesel_repository::asynchronous::FindById<Tagged<'a, T>, i32>
+    for TagStoreWithContext<'ctx, 'a, T>
+where
+    T: Send,
+{
+    async fn find_by_id(&self, id: i32) -> Result<Tagged<'a, T>, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.repo().pool).await?;
+        diesel_repository :: diesel_async :: AsyncConnection :: transaction :: < _ , diesel :: result :: Error , _ > (& mut * connection , | conn | { diesel_repository :: diesel_async :: scoped_futures :: ScopedFutureExt :: scope_boxed (async move { use diesel :: { ExpressionMethods , QueryDsl } ; diesel_repository :: SessionContext :: apply_async (self . context () , conn) . await ? ; # [allow (unused_mut)] let mut record : Tagged < 'a , T > = diesel_repository :: diesel_async :: RunQueryDsl :: get_result (crate :: tags :: table . find (id) , conn) . await ? ; Ok (record) }) }) . await
+    }
+}
+#[diesel_repository::async_trait]
+impl<'ctx, 'a, T: Clone> diesel_repository::asynchronous::FindAll<Tagged<'a, T>>
+    for TagStoreWithContext<'ctx, 'a, T>
+where
+    T: Send,
+{
+    async fn find_all(&self) -> Result<Vec<Tagged<'a, T>>, diesel::result::Error> {
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.repo().pool).await?;
+        diesel_repository :: diesel_async :: AsyncConnection :: transaction :: < _ , diesel :: result :: Error , _ > (& mut * connection , | conn | { diesel_repository :: diesel_async :: scoped_futures :: ScopedFutureExt :: scope_boxed (async move { use diesel :: { ExpressionMethods , QueryDsl } ; diesel_repository :: SessionContext :: apply_async (self . context () , conn) . await ? ; # [allow (unused_mut)] let mut record : Vec < Tagged < 'a , T > > = diesel_repository :: diesel_async :: RunQueryDsl :: load (crate :: tags :: table , conn) . await ? ; Ok (record) }) }) . await
+    }
+}
+#[diesel_repository::async_trait]
+impl<'ctx, 'a, T: Clone> diesel_repository::asynchronous::FindAllSlice<Tagged<'a, T>>
+    for TagStoreWithContext<'ctx, 'a, T>
+where
+    T: Send,
+{
+    async fn find_all_slice(
+        &self,
+        page: i64,
+        per_page: i64,
+    ) -> Result<diesel_repository::Slice<Tagged<'a, T>>, diesel::result::Error> {
+        let diesel_repository::PageRequest { page, per_page } =
+            diesel_repository::PageRequest::try_with_limits(page, per_page, None, None)?;
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.repo().pool).await?;
+        diesel_repository :: diesel_async :: AsyncConnection :: transaction :: < _ , diesel :: result :: Error , _ > (& mut * connection , | conn | { diesel_repository :: diesel_async :: scoped_futures :: ScopedFutureExt :: scope_boxed (async move { use diesel :: { ExpressionMethods , QueryDsl } ; diesel_repository :: SessionContext :: apply_async (self . context () , conn) . await ? ; let offset = (page - 1) * per_page ; let page_query = crate :: tags :: table . limit (per_page . saturating_add (1)) . offset (offset) ; let items = diesel_repository :: diesel_async :: RunQueryDsl :: load (page_query , conn) . await ? ; # [allow (unused_mut)] let mut record = diesel_repository :: Slice :: from_overfetch (items , page , per_page) ; Ok (record) }) }) . await
+    }
+}
+#[diesel_repository::async_trait]
+impl<'ctx, 'a, T: Clone> diesel_repository::asynchronous::FindAllPaged<Tagged<'a, T>>
+    for TagStoreWithContext<'ctx, 'a, T>
+where
+    T: Send,
+{
+    async fn find_all_paged(
+        &self,
+        page: i64,
+        per_page: i64,
+    ) -> Result<diesel_repository::Paged<Tagged<'a, T>>, diesel::result::Error> {
+        let diesel_repository::PageRequest { page, per_page } =
+            diesel_repository::PageRequest::try_with_limits(page, per_page, None, None)?;
+        let mut connection =
+            diesel_repository::AsyncConnectionSource::connection(&*self.repo().pool).await?;
+        diesel_repository :: diesel_async :: AsyncConnection :: transaction :: < _ , diesel :: result :: Error , _ > (& mut * connection , | conn | { diesel_repository :: diesel_async :: scoped_futures :: ScopedFutureExt :: scope_boxed (async move { use diesel :: { ExpressionMethods , QueryDsl } ; diesel_repository :: SessionContext :: apply_async (self . context () , conn) . await ? ; let offset = (page - 1) * per_page ; let total_count : i64 = diesel_repository :: diesel_async :: RunQueryDsl :: get_result (crate :: tags :: table . count () , conn) . await ? ; let items = diesel_repository :: diesel_async :: RunQueryDsl :: load (crate :: tags :: table . limit (per_page) . offset (offset) , conn) . await ? ; # [allow (unused_mut)] let mut record = diesel_repository :: Paged { items , total_count , page , per_page } ; Ok (record) }) }) . await
+    }
+}
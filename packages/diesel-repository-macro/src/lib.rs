@@ -1,27 +1,41 @@
 extern crate proc_macro;
 
-use diesel_repository_macro_core::{FromDeriveInput, Options};
+use diesel_repository_macro_core::{derive, FromDeriveInput, Options, PatchOpts};
 use proc_macro::TokenStream;
-use proc_macro_error2::{abort, proc_macro_error};
+use proc_macro_error2::proc_macro_error;
 use syn::{parse_macro_input, DeriveInput};
 
-#[cfg(not(feature = "async"))]
-use diesel_repository_macro_sync::derive;
-
-#[cfg(feature = "async")]
-use diesel_repository_macro_async::derive;
+mod patch;
 
 /// The derive macro for `repo`.
 #[proc_macro_error]
-#[proc_macro_derive(Repo, attributes(repository, repo_type, crud_repo, paging_repo))]
+#[proc_macro_derive(
+    Repo,
+    attributes(repository, repo_type, crud_repo, paging_repo, batch_repo, repo_sql)
+)]
 pub fn repository_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let opts = match Options::from_derive_input(&input) {
         Ok(r) => r,
-        Err(e) => abort!(input, "Failed to parse #[derive(Repo)] attributes: {}", e),
+        Err(e) => return e.write_errors().into(),
     };
 
     derive(opts)
         .unwrap_or_else(|err| err.to_compile_error())
         .into()
 }
+
+/// The derive macro for `patch`, generating an all-`Option` changeset struct.
+#[proc_macro_error]
+#[proc_macro_derive(Patch, attributes(patch))]
+pub fn patch_derive(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let opts = match PatchOpts::from_derive_input(&input) {
+        Ok(r) => r,
+        Err(e) => return e.write_errors().into(),
+    };
+
+    patch::derive(&input, opts)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
@@ -0,0 +1,72 @@
+use diesel_repository_macro_core::{infer, FromDeriveInput, Options, PatchOpts};
+use proc_macro2::TokenStream;
+use proc_macro_error2::abort;
+use quote::{format_ident, quote};
+use syn::DeriveInput;
+
+pub fn derive(input: &DeriveInput, opts: PatchOpts) -> syn::Result<TokenStream> {
+    let struct_name = &opts.ident;
+    let vis = &opts.vis;
+
+    // Generate patch type name: e.g. `AccountPatch` for struct `Account`
+    let patch_name = opts
+        .name
+        .clone()
+        .unwrap_or_else(|| format_ident!("{}Patch", struct_name));
+
+    // Fall back to the table declared for the repository or for Diesel, if any.
+    let diesel_table = match opts.table_name {
+        Some(path) => path,
+        None => match Options::from_derive_input(input)
+            .ok()
+            .and_then(|o| o.repo.table_name)
+        {
+            Some(path) => path,
+            None => abort!(
+                struct_name,
+                "Missing 'table_name' attribute in #[patch] or #[repository]"
+            ),
+        },
+    };
+
+    let fields = match opts.data.take_struct() {
+        Some(fields) => fields.fields,
+        None => abort!(struct_name, "#[derive(Patch)] only supports named structs"),
+    };
+
+    let primary_key = infer::primary_key(&input.attrs);
+
+    let mut patch_fields = TokenStream::new();
+    for field in fields {
+        if field.skip {
+            continue;
+        }
+        let Some(ident) = field.ident else {
+            continue;
+        };
+        // The primary key identifies the row and is never part of the changeset.
+        if infer::column_name(Some(&ident), &field.attrs).is_some_and(|c| primary_key.contains(&c))
+        {
+            continue;
+        }
+
+        let field_vis = field.vis;
+        let field_attrs = field.attrs;
+        let ty = field.ty;
+        // Nullable columns become `Option<Option<_>>`: `Some(None)` writes NULL.
+        patch_fields.extend(quote! {
+            #(#field_attrs)*
+            #field_vis #ident: Option<#ty>,
+        });
+    }
+
+    let expanded = quote! {
+        #[derive(Default, diesel::AsChangeset)]
+        #[diesel(table_name = #diesel_table)]
+        #vis struct #patch_name {
+            #patch_fields
+        }
+    };
+
+    Ok(expanded)
+}
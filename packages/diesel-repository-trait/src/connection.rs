@@ -0,0 +1,81 @@
+use diesel::result::QueryResult;
+use std::ops::DerefMut;
+
+/// The pool a generated repository checks out the connections its methods run on.
+///
+/// `#[repository(pool = ...)]` names the type; implement this trait for it, e.g. for an
+/// r2d2 pool:
+///
+/// ```ignore
+/// impl ConnectionSource for DbPool {
+///     type Connection = PgConnection;
+///     type Guard<'a> = PooledConnection<ConnectionManager<PgConnection>>;
+///
+///     fn connection(&self) -> QueryResult<Self::Guard<'_>> {
+///         self.get().map_err(|err| Error::QueryBuilderError(err.into()))
+///     }
+/// }
+/// ```
+///
+/// A `Mutex` around a single connection is a source as well, e.g. for tests.
+pub trait ConnectionSource {
+    /// The connection the queries run on.
+    type Connection: diesel::Connection;
+    /// Gives access to a checked-out connection and returns it when dropped.
+    type Guard<'a>: DerefMut<Target = Self::Connection>
+    where
+        Self: 'a;
+
+    /// Checks out a connection.
+    fn connection(&self) -> QueryResult<Self::Guard<'_>>;
+}
+
+impl<C: diesel::Connection> ConnectionSource for std::sync::Mutex<C> {
+    type Connection = C;
+    type Guard<'a>
+        = std::sync::MutexGuard<'a, C>
+    where
+        C: 'a;
+
+    fn connection(&self) -> QueryResult<Self::Guard<'_>> {
+        // A panic while holding the connection rolls back through its transaction manager.
+        Ok(self
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner))
+    }
+}
+
+/// The pool an async repository checks out the connections its methods run on, e.g. a
+/// diesel-async `deadpool` or `bb8` pool.
+///
+/// A `tokio::sync::Mutex` around a single connection is a source as well, e.g. for tests.
+#[cfg(feature = "async")]
+#[async_trait::async_trait]
+pub trait AsyncConnectionSource: Send + Sync {
+    /// The connection the queries run on.
+    type Connection: diesel_async::AsyncConnection + Send;
+    /// Gives access to a checked-out connection and returns it when dropped.
+    type Guard<'a>: DerefMut<Target = Self::Connection> + Send
+    where
+        Self: 'a;
+
+    /// Checks out a connection.
+    async fn connection(&self) -> QueryResult<Self::Guard<'_>>;
+}
+
+#[cfg(feature = "async")]
+#[async_trait::async_trait]
+impl<C> AsyncConnectionSource for tokio::sync::Mutex<C>
+where
+    C: diesel_async::AsyncConnection + Send,
+{
+    type Connection = C;
+    type Guard<'a>
+        = tokio::sync::MutexGuard<'a, C>
+    where
+        C: 'a;
+
+    async fn connection(&self) -> QueryResult<Self::Guard<'_>> {
+        Ok(self.lock().await)
+    }
+}
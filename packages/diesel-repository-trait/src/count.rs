@@ -0,0 +1,102 @@
+//! Postgres support for `#[paging_repo(count_strategy = "estimate")]`.
+//!
+//! Estimates come from the planner's statistics, so they are only as fresh as the last
+//! `ANALYZE` of the table; use them where an approximate total is acceptable.
+
+use crate::ViewCount;
+use diesel::connection::LoadConnection;
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use diesel::query_builder::{AstPass, Query, QueryFragment, QueryId};
+use diesel::sql_types::Text;
+
+const TABLE_ESTIMATE: &str =
+    "SELECT GREATEST(reltuples, 0)::bigint AS count FROM pg_class WHERE oid = to_regclass($1)";
+
+/// Estimates the number of rows of a whole table from `pg_class.reltuples`.
+///
+/// Returns 0 for a table that was never analyzed.
+pub fn estimate_count<C>(conn: &mut C, table: &str) -> QueryResult<i64>
+where
+    C: Connection<Backend = Pg> + LoadConnection,
+{
+    diesel::sql_query(TABLE_ESTIMATE)
+        .bind::<Text, _>(table)
+        .get_result::<ViewCount>(conn)
+        .map(|row| row.count)
+}
+
+/// Estimates the number of rows of a whole table over an async connection, like
+/// [`estimate_count`].
+#[cfg(feature = "async")]
+pub async fn estimate_count_async<C>(conn: &mut C, table: &str) -> QueryResult<i64>
+where
+    C: diesel_async::AsyncConnection<Backend = Pg>,
+{
+    let query = diesel::sql_query(TABLE_ESTIMATE).bind::<Text, _>(table);
+    diesel_async::RunQueryDsl::get_result::<ViewCount>(query, conn)
+        .await
+        .map(|row| row.count)
+}
+
+/// `EXPLAIN (FORMAT JSON)` of a query, with the query's binds.
+struct Explain<Q>(Q);
+
+impl<Q> Query for Explain<Q> {
+    type SqlType = Text;
+}
+
+impl<Q> QueryId for Explain<Q> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<Q: QueryFragment<Pg>> QueryFragment<Pg> for Explain<Q> {
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Pg>) -> QueryResult<()> {
+        out.push_sql("EXPLAIN (FORMAT JSON) ");
+        self.0.walk_ast(out.reborrow())
+    }
+}
+
+impl<Q, C> RunQueryDsl<C> for Explain<Q> {}
+
+/// Estimates the number of rows `query` returns from the planner's `Plan Rows`, without
+/// running it. Raw SQL is estimated with `diesel::sql_query(...)` as the query.
+pub fn estimate_query_count<C, Q>(conn: &mut C, query: Q) -> QueryResult<i64>
+where
+    C: Connection<Backend = Pg> + LoadConnection,
+    Q: QueryFragment<Pg>,
+{
+    let plan: String = Explain(query).get_result(conn)?;
+    plan_rows(&plan)
+}
+
+/// Estimates the number of rows `query` returns over an async connection, like
+/// [`estimate_query_count`].
+#[cfg(feature = "async")]
+pub async fn estimate_query_count_async<C, Q>(conn: &mut C, query: Q) -> QueryResult<i64>
+where
+    C: diesel_async::AsyncConnection<Backend = Pg>,
+    Q: QueryFragment<Pg> + Send,
+{
+    let plan: String = diesel_async::RunQueryDsl::get_result(Explain(query), conn).await?;
+    plan_rows(&plan)
+}
+
+/// Reads the first `"Plan Rows"` of a JSON plan, the estimate of its top node.
+fn plan_rows(plan: &str) -> QueryResult<i64> {
+    let rows = plan.split_once("\"Plan Rows\":").map(|(_, rest)| {
+        rest.trim_start()
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || *c == '.')
+            .collect::<String>()
+    });
+    rows.and_then(|rows| rows.parse::<f64>().ok())
+        .map(|rows| rows as i64)
+        .ok_or_else(|| {
+            diesel::result::Error::DeserializationError(
+                format!("no `Plan Rows` in the plan `{}`", plan).into(),
+            )
+        })
+}
@@ -0,0 +1,130 @@
+use crate::Operation;
+use diesel::result::{Error, QueryResult};
+use std::time::SystemTime;
+
+/// A recorded change of a record, holding the row image from before the change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry<T> {
+    /// The write that replaced or removed the record.
+    pub operation: Operation,
+    /// Who performed the write, as reported by [`HistoryActor`].
+    pub actor: Option<String>,
+    /// When the write happened.
+    pub changed_at: SystemTime,
+    /// The record as it was before the write.
+    pub record: T,
+}
+
+impl<T> HistoryEntry<T> {
+    /// Builds an entry from a row of the history table: the previous row image, then the
+    /// `operation`, `actor` and `changed_at` columns.
+    pub fn from_row(
+        (record, operation, actor, changed_at): (T, String, Option<String>, SystemTime),
+    ) -> QueryResult<Self> {
+        let operation = Operation::parse(&operation).ok_or_else(|| {
+            Error::DeserializationError(
+                format!("unknown operation `{}` in the history table", operation).into(),
+            )
+        })?;
+        Ok(Self {
+            operation,
+            actor,
+            changed_at,
+            record,
+        })
+    }
+
+    /// Reconstructs the state of a record at `timestamp` from its history, oldest first,
+    /// and its current state (`None` once deleted).
+    ///
+    /// The state at `timestamp` is the previous image of the first change made after it;
+    /// without such a change it is the current state. A change made exactly at `timestamp`
+    /// counts as already made. Inserts are not recorded, so a record created after
+    /// `timestamp` is reported as it was at its first change.
+    pub fn state_at(
+        entries: Vec<HistoryEntry<T>>,
+        current: Option<T>,
+        timestamp: SystemTime,
+    ) -> Option<T> {
+        entries
+            .into_iter()
+            .find(|entry| entry.changed_at > timestamp)
+            .map(|entry| entry.record)
+            .or(current)
+    }
+}
+
+/// Provides the actor recorded in the history table for every change.
+///
+/// Required on the generated repository with `#[repository(history = ...)]`.
+pub trait HistoryActor {
+    /// Returns who is performing the current write, e.g. the authenticated user.
+    fn current_actor(&self) -> Option<String>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn at(seconds: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    fn entry(
+        operation: Operation,
+        seconds: u64,
+        record: &'static str,
+    ) -> HistoryEntry<&'static str> {
+        HistoryEntry {
+            operation,
+            actor: None,
+            changed_at: at(seconds),
+            record,
+        }
+    }
+
+    /// "a" was updated to "b" at 10, "b" to "c" at 20, and "c" deleted at 30.
+    fn history() -> Vec<HistoryEntry<&'static str>> {
+        vec![
+            entry(Operation::Update, 10, "a"),
+            entry(Operation::Update, 20, "b"),
+            entry(Operation::Delete, 30, "c"),
+        ]
+    }
+
+    #[test]
+    fn state_at_takes_the_first_change_after_the_timestamp() {
+        assert_eq!(HistoryEntry::state_at(history(), None, at(5)), Some("a"));
+        assert_eq!(HistoryEntry::state_at(history(), None, at(15)), Some("b"));
+        assert_eq!(HistoryEntry::state_at(history(), None, at(25)), Some("c"));
+        assert_eq!(HistoryEntry::state_at(history(), None, at(35)), None);
+        assert_eq!(
+            HistoryEntry::state_at(Vec::new(), Some("d"), at(35)),
+            Some("d")
+        );
+    }
+
+    #[test]
+    fn rows_parse_their_operation() {
+        let entry = HistoryEntry::from_row(("a", "delete".to_string(), None, at(10))).unwrap();
+        assert_eq!(entry, self::entry(Operation::Delete, 10, "a"));
+        assert!(matches!(
+            HistoryEntry::from_row(("a", "upsert".to_string(), None, at(10))),
+            Err(Error::DeserializationError(_))
+        ));
+    }
+
+    #[test]
+    fn state_at_a_change_includes_that_change() {
+        assert_eq!(HistoryEntry::state_at(history(), None, at(10)), Some("b"));
+        assert_eq!(HistoryEntry::state_at(history(), None, at(20)), Some("c"));
+        assert_eq!(HistoryEntry::state_at(history(), None, at(30)), None);
+
+        let updated = history().into_iter().take(2).collect();
+        assert_eq!(
+            HistoryEntry::state_at(updated, Some("c"), at(20)),
+            Some("c")
+        );
+    }
+}
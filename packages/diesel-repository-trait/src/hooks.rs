@@ -0,0 +1,100 @@
+use std::fmt;
+
+/// Extension points invoked by the generated repository methods around persistence.
+///
+/// Enabled with `#[repository(hooks)]`, in which case the repository must implement this
+/// trait; every method defaults to a no-op. Hooks run inside the same transaction as the
+/// write, so returning an error aborts the operation and rolls it back.
+///
+/// With `#[crud_repo(patch)]`, the hooks are implemented for the patch type as well, e.g.
+/// `impl RepoHooks<Account, String, NewAccount, Account, AccountPatch> for AccountRepo`.
+pub trait RepoHooks<T, ID, NewRecord = T, UpdateRecord = T, PatchRecord = UpdateRecord> {
+    /// Called by `save` and `save_batch` before a new record is inserted.
+    /// Batch inputs are copied first, so batch methods require `NewRecord: Clone`.
+    fn before_save(&self, _new_record: &mut NewRecord) -> Result<(), ValidationError> {
+        Ok(())
+    }
+
+    /// Called by `update` and `update_batch` before a record is updated.
+    /// Batch inputs are copied first, so batch methods require `UpdateRecord: Clone`.
+    fn before_update(&self, _update_record: &mut UpdateRecord) -> Result<(), ValidationError> {
+        Ok(())
+    }
+
+    /// Called by `patch` before the changes are applied to the record with the given ID.
+    fn before_patch(
+        &self,
+        _id: &ID,
+        _patch_record: &mut PatchRecord,
+    ) -> Result<(), ValidationError> {
+        Ok(())
+    }
+
+    /// Called by `replace` before a record is upserted.
+    fn before_replace(&self, _record: &mut T) -> Result<(), ValidationError> {
+        Ok(())
+    }
+
+    /// Called with the stored record after every insert, update, patch or replace, batched
+    /// or not.
+    fn after_save(&self, _record: &T) -> Result<(), ValidationError> {
+        Ok(())
+    }
+
+    /// Called by `delete` and `delete_batch` before a record is deleted.
+    fn before_delete(&self, _id: &ID) -> Result<(), ValidationError> {
+        Ok(())
+    }
+
+    /// Called for every record loaded by the find methods.
+    fn after_load(&self, _record: &mut T) -> Result<(), ValidationError> {
+        Ok(())
+    }
+}
+
+/// Error returned by a [`RepoHooks`] method to reject an operation.
+///
+/// Converted into [`diesel::result::Error::QueryBuilderError`], from which it can be
+/// recovered with `downcast_ref::<ValidationError>()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// The field that failed validation, if the error is specific to one.
+    pub field: Option<String>,
+    /// A human-readable description of the failure.
+    pub message: String,
+}
+
+impl ValidationError {
+    /// Creates an error that applies to the record as a whole.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            field: None,
+            message: message.into(),
+        }
+    }
+
+    /// Creates an error for a single field of the record.
+    pub fn for_field(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: Some(field.into()),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.field {
+            Some(field) => write!(f, "validation failed for `{}`: {}", field, self.message),
+            None => write!(f, "validation failed: {}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl From<ValidationError> for diesel::result::Error {
+    fn from(err: ValidationError) -> Self {
+        diesel::result::Error::QueryBuilderError(Box::new(err))
+    }
+}
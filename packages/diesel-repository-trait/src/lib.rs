@@ -1,10 +1,47 @@
+mod connection;
+#[cfg(feature = "postgres")]
+mod count;
+mod history;
+mod hooks;
+mod lock;
 mod model;
+mod outbox;
+mod queue;
 mod repo;
+mod schema;
+mod session;
+mod subscribe;
+mod web;
 
-pub use model::{ViewCount, Paged};
+#[cfg(feature = "async")]
+pub use connection::AsyncConnectionSource;
+pub use connection::ConnectionSource;
+#[cfg(feature = "postgres")]
+pub use count::*;
+pub use history::{HistoryActor, HistoryEntry};
+pub use hooks::{RepoHooks, ValidationError};
+pub use lock::{InTransaction, LockMode, LockStrength, LockWait, Locked};
+pub use model::{Operation, PageRequest, PageRequestError, Paged, Slice, ViewCount};
+pub use outbox::*;
+pub use queue::{Backoff, JobStatus, QueueTable};
+pub use repo::{BuildError, NewRecord, QueryFn, QueryRepository, Repository, UpdateRecord};
+pub use schema::*;
+pub use session::SessionContext;
+pub use subscribe::*;
+pub use web::{PageParams, PageParamsError, PageSpec, Sort, SortDirection, Unsorted};
 
-#[cfg(not(feature = "async"))]
+/// The blocking repository traits, also re-exported at the crate root.
+pub use repo::synchronous as sync;
 pub use repo::synchronous::*;
 
+/// The `async` repository traits, available with the `async` feature.
+#[cfg(feature = "async")]
+pub use repo::asynchronous;
+
+#[cfg(feature = "async")]
+pub use async_trait::async_trait;
+
+/// Used by the generated async methods, which run their queries with diesel-async.
 #[cfg(feature = "async")]
-pub use repo::asynchronous::*;
+#[doc(hidden)]
+pub use diesel_async;
@@ -0,0 +1,223 @@
+use crate::{QueryRepository, Repository};
+use diesel::backend::Backend;
+use diesel::query_builder::{AstPass, Query, QueryFragment, QueryId};
+use diesel::result::{Error, QueryResult};
+use diesel::RunQueryDsl;
+use std::fmt;
+
+/// How strongly `SELECT ... FOR ...` locks the selected rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LockStrength {
+    /// `FOR UPDATE`: exclusive, for rows about to be updated or deleted.
+    #[default]
+    Update,
+    /// `FOR NO KEY UPDATE`: exclusive, but allows `FOR KEY SHARE` on the same rows.
+    NoKeyUpdate,
+    /// `FOR SHARE`: shared, keeps the rows from changing.
+    Share,
+    /// `FOR KEY SHARE`: shared, only keeps the keys of the rows from changing.
+    KeyShare,
+}
+
+/// What a locking select does when a row is already locked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LockWait {
+    /// Waits for the other transaction.
+    #[default]
+    Wait,
+    /// `NOWAIT`: fails at once.
+    NoWait,
+    /// `SKIP LOCKED`: leaves the locked rows out of the result.
+    SkipLocked,
+}
+
+/// The locking clause of a locking select, e.g. `LockMode::for_update().skip_locked()`.
+///
+/// ```
+/// use diesel_repository_trait::LockMode;
+///
+/// assert_eq!(LockMode::for_share().nowait().to_string(), "FOR SHARE NOWAIT");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LockMode {
+    pub strength: LockStrength,
+    pub wait: LockWait,
+}
+
+impl LockMode {
+    /// `FOR UPDATE`.
+    pub fn for_update() -> Self {
+        Self::with_strength(LockStrength::Update)
+    }
+
+    /// `FOR NO KEY UPDATE`.
+    pub fn for_no_key_update() -> Self {
+        Self::with_strength(LockStrength::NoKeyUpdate)
+    }
+
+    /// `FOR SHARE`.
+    pub fn for_share() -> Self {
+        Self::with_strength(LockStrength::Share)
+    }
+
+    /// `FOR KEY SHARE`.
+    pub fn for_key_share() -> Self {
+        Self::with_strength(LockStrength::KeyShare)
+    }
+
+    /// Fails instead of waiting for locked rows.
+    pub fn nowait(self) -> Self {
+        Self {
+            wait: LockWait::NoWait,
+            ..self
+        }
+    }
+
+    /// Skips locked rows instead of waiting for them.
+    pub fn skip_locked(self) -> Self {
+        Self {
+            wait: LockWait::SkipLocked,
+            ..self
+        }
+    }
+
+    fn with_strength(strength: LockStrength) -> Self {
+        Self {
+            strength,
+            wait: LockWait::Wait,
+        }
+    }
+}
+
+impl fmt::Display for LockMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self.strength {
+            LockStrength::Update => "FOR UPDATE",
+            LockStrength::NoKeyUpdate => "FOR NO KEY UPDATE",
+            LockStrength::Share => "FOR SHARE",
+            LockStrength::KeyShare => "FOR KEY SHARE",
+        })?;
+        match self.wait {
+            LockWait::Wait => Ok(()),
+            LockWait::NoWait => f.write_str(" NOWAIT"),
+            LockWait::SkipLocked => f.write_str(" SKIP LOCKED"),
+        }
+    }
+}
+
+/// A select with a locking clause appended, e.g. `SELECT ... LIMIT 10 FOR UPDATE SKIP LOCKED`.
+///
+/// Built by the generated `Lock` methods, as Diesel's own `for_update()` is not available on
+/// boxed queries. The clause is written as is, so the backend has to support it: Postgres
+/// supports every [`LockMode`], SQLite none.
+#[derive(Debug, Clone, Copy)]
+pub struct Locked<Q> {
+    query: Q,
+    mode: LockMode,
+}
+
+impl<Q> Locked<Q> {
+    /// Locks the rows `query` selects with `mode`.
+    pub fn new(query: Q, mode: LockMode) -> Self {
+        Self { query, mode }
+    }
+}
+
+impl<Q: Query> Query for Locked<Q> {
+    type SqlType = Q::SqlType;
+}
+
+impl<Q> QueryId for Locked<Q> {
+    type QueryId = ();
+
+    // The clause depends on the mode, which is only known at runtime.
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<Q, DB> QueryFragment<DB> for Locked<Q>
+where
+    DB: Backend,
+    Q: QueryFragment<DB>,
+{
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, DB>) -> QueryResult<()> {
+        self.query.walk_ast(out.reborrow())?;
+        out.push_sql(" ");
+        out.push_sql(&self.mode.to_string());
+        Ok(())
+    }
+}
+
+impl<Q, Conn> RunQueryDsl<Conn> for Locked<Q> {}
+
+/// A repository bound to a connection inside an open transaction.
+///
+/// Row locks only last until the end of the transaction taking them, so the `Lock` traits
+/// are implemented on this view rather than on the repository: a lock is never taken on an
+/// autocommit connection, where it would be released as soon as the select returns.
+///
+/// ```ignore
+/// conn.transaction(|conn| {
+///     let mut tx = InTransaction::new(&repo, conn)?;
+///     let account = tx.find_by_id_locked(id, LockMode::for_update())?;
+///     // ... adjust the balance with the same `conn`
+/// })
+/// ```
+pub struct InTransaction<'r, 'c, R, C> {
+    repo: &'r R,
+    conn: &'c mut C,
+}
+
+impl<'r, 'c, R, C> InTransaction<'r, 'c, R, C>
+where
+    C: diesel::Connection,
+{
+    /// Binds `repo` to `conn`, failing with [`Error::NotInTransaction`] outside a transaction.
+    pub fn new(repo: &'r R, conn: &'c mut C) -> QueryResult<Self> {
+        use diesel::connection::TransactionManager;
+
+        let status = C::TransactionManager::transaction_manager_status_mut(conn);
+        match status.transaction_depth()? {
+            Some(_) => Ok(Self { repo, conn }),
+            None => Err(Error::NotInTransaction),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'r, 'c, R, C> InTransaction<'r, 'c, R, C>
+where
+    C: diesel_async::AsyncConnection,
+{
+    /// Binds `repo` to an async `conn`, failing with [`Error::NotInTransaction`] outside a
+    /// transaction.
+    pub fn new_async(repo: &'r R, conn: &'c mut C) -> QueryResult<Self> {
+        use diesel_async::TransactionManager;
+
+        let status = C::TransactionManager::transaction_manager_status_mut(conn);
+        match status.transaction_depth()? {
+            Some(_) => Ok(Self { repo, conn }),
+            None => Err(Error::NotInTransaction),
+        }
+    }
+}
+
+impl<'r, R, C> InTransaction<'r, '_, R, C> {
+    /// The repository this view was created from.
+    pub fn repo(&self) -> &'r R {
+        self.repo
+    }
+
+    /// The connection of the open transaction.
+    pub fn conn(&mut self) -> &mut C {
+        self.conn
+    }
+}
+
+impl<R: Repository, C> Repository for InTransaction<'_, '_, R, C> {
+    type Entity = R::Entity;
+    type Id = R::Id;
+}
+
+impl<R: QueryRepository, C> QueryRepository for InTransaction<'_, '_, R, C> {
+    type Query = R::Query;
+}
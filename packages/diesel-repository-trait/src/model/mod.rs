@@ -1,7 +1,22 @@
 use diesel::sql_types::BigInt;
 use diesel::QueryableByName;
+use std::fmt;
 
 /// A simple structure to hold paginated results.
+///
+/// With the `serde` feature it serializes as
+/// `{"items": [...], "page": 2, "per_page": 10, "total_count": 42, "total_pages": 5,
+/// "has_next": true, "has_previous": true}`.
+///
+/// ```
+/// use diesel_repository_trait::Paged;
+///
+/// let paged = Paged { items: vec![1, 2], total_count: 12, page: 2, per_page: 5 };
+/// assert_eq!(paged.total_pages(), 3);
+/// assert!(paged.has_next() && paged.has_previous());
+/// assert_eq!(paged.map(|n| n * 10).items, vec![10, 20]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Paged<T> {
     /// The records for the current page.
     pub items: Vec<T>,
@@ -13,8 +28,328 @@ pub struct Paged<T> {
     pub per_page: i64,
 }
 
+impl<T> Paged<T> {
+    /// Builds a page from rows selected with `COUNT(*) OVER ()`, the total of every row.
+    ///
+    /// A page past the end has no rows to carry the total, so its `total_count` is 0; the
+    /// generated `window` methods run a separate count for such a page instead.
+    pub fn from_window(rows: Vec<(T, i64)>, page: i64, per_page: i64) -> Self {
+        let total_count = rows.first().map_or(0, |(_, total)| *total);
+        Self {
+            items: rows.into_iter().map(|(item, _)| item).collect(),
+            total_count,
+            page,
+            per_page,
+        }
+    }
+
+    /// A page without records, e.g. for a query known to match nothing.
+    pub fn empty(page: i64, per_page: i64) -> Self {
+        Self {
+            items: Vec::new(),
+            total_count: 0,
+            page,
+            per_page,
+        }
+    }
+
+    /// The number of pages needed for `total_count` records, `0` when there are none.
+    pub fn total_pages(&self) -> i64 {
+        if self.per_page <= 0 || self.total_count <= 0 {
+            return 0;
+        }
+        (self.total_count + self.per_page - 1) / self.per_page
+    }
+
+    /// Whether a page follows this one.
+    pub fn has_next(&self) -> bool {
+        self.page < self.total_pages()
+    }
+
+    /// Whether a page precedes this one.
+    pub fn has_previous(&self) -> bool {
+        self.page > 1
+    }
+
+    /// Whether this is the first page.
+    pub fn is_first(&self) -> bool {
+        !self.has_previous()
+    }
+
+    /// Whether this is the last page; an empty result is both first and last.
+    pub fn is_last(&self) -> bool {
+        !self.has_next()
+    }
+
+    /// Converts the records, e.g. into DTOs, keeping the paging metadata.
+    pub fn map<U, F>(self, f: F) -> Paged<U>
+    where
+        F: FnMut(T) -> U,
+    {
+        Paged {
+            items: self.items.into_iter().map(f).collect(),
+            total_count: self.total_count,
+            page: self.page,
+            per_page: self.per_page,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for Paged<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("Paged", 7)?;
+        state.serialize_field("items", &self.items)?;
+        state.serialize_field("page", &self.page)?;
+        state.serialize_field("per_page", &self.per_page)?;
+        state.serialize_field("total_count", &self.total_count)?;
+        state.serialize_field("total_pages", &self.total_pages())?;
+        state.serialize_field("has_next", &self.has_next())?;
+        state.serialize_field("has_previous", &self.has_previous())?;
+        state.end()
+    }
+}
+
+/// A page without a total count, for `#[paging_repo(count_strategy = "none")]`.
+///
+/// Loaded by fetching `per_page + 1` rows: the extra row only tells whether a next page
+/// exists. With the `serde` feature it serializes as
+/// `{"items": [...], "page": 2, "per_page": 10, "has_next": true, "has_previous": true}`.
+///
+/// ```
+/// use diesel_repository_trait::Slice;
+///
+/// let slice = Slice::from_overfetch(vec![1, 2, 3], 1, 2);
+/// assert_eq!(slice.items, vec![1, 2]);
+/// assert!(slice.has_next);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slice<T> {
+    /// The records for the current page.
+    pub items: Vec<T>,
+    /// The current page number (e.g. 1-indexed).
+    pub page: i64,
+    /// The number of records per page.
+    pub per_page: i64,
+    /// Whether a page follows this one.
+    pub has_next: bool,
+}
+
+impl<T> Slice<T> {
+    /// Builds a page from up to `per_page + 1` rows, dropping the extra one.
+    pub fn from_overfetch(mut items: Vec<T>, page: i64, per_page: i64) -> Self {
+        let limit = usize::try_from(per_page).unwrap_or_default();
+        let has_next = items.len() > limit;
+        items.truncate(limit);
+        Self {
+            items,
+            page,
+            per_page,
+            has_next,
+        }
+    }
+
+    /// A page without records.
+    pub fn empty(page: i64, per_page: i64) -> Self {
+        Self {
+            items: Vec::new(),
+            page,
+            per_page,
+            has_next: false,
+        }
+    }
+
+    /// Whether a page precedes this one.
+    pub fn has_previous(&self) -> bool {
+        self.page > 1
+    }
+
+    /// Converts the records, e.g. into DTOs, keeping the paging metadata.
+    pub fn map<U, F>(self, f: F) -> Slice<U>
+    where
+        F: FnMut(T) -> U,
+    {
+        Slice {
+            items: self.items.into_iter().map(f).collect(),
+            page: self.page,
+            per_page: self.per_page,
+            has_next: self.has_next,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for Slice<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("Slice", 5)?;
+        state.serialize_field("items", &self.items)?;
+        state.serialize_field("page", &self.page)?;
+        state.serialize_field("per_page", &self.per_page)?;
+        state.serialize_field("has_next", &self.has_next)?;
+        state.serialize_field("has_previous", &self.has_previous())?;
+        state.end()
+    }
+}
+
+/// The page a caller asks for, e.g. parsed from `?page=2&per_page=10`.
+///
+/// With the `serde` feature it (de)serializes as `{"page": 2, "per_page": 10}`, rejecting
+/// the requests [`PageRequest::try_new`] rejects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RawPageRequest"))]
+pub struct PageRequest {
+    /// The requested page number, 1-indexed.
+    pub page: i64,
+    /// The requested number of records per page.
+    pub per_page: i64,
+}
+
+impl PageRequest {
+    /// Creates a request without checking it; see [`PageRequest::try_new`].
+    pub fn new(page: i64, per_page: i64) -> Self {
+        Self { page, per_page }
+    }
+
+    /// Creates a request for a page from 1 and at least one record per page, whose offset
+    /// fits in an `i64`.
+    pub fn try_new(page: i64, per_page: i64) -> Result<Self, PageRequestError> {
+        Self::try_with_limits(page, per_page, None, None)
+    }
+
+    /// Like [`PageRequest::try_new`], with the limits of `#[paging_repo(...)]`: a `per_page`
+    /// of 0 asks for `default_per_page`, and more than `max_per_page` is rejected.
+    pub fn try_with_limits(
+        page: i64,
+        per_page: i64,
+        default_per_page: Option<i64>,
+        max_per_page: Option<i64>,
+    ) -> Result<Self, PageRequestError> {
+        let per_page = match (per_page, default_per_page) {
+            (0, Some(default)) => default,
+            _ => per_page,
+        };
+        if page < 1 {
+            return Err(PageRequestError::InvalidPage(page));
+        }
+        if per_page < 1 {
+            return Err(PageRequestError::InvalidPerPage(per_page));
+        }
+        if let Some(max) = max_per_page.filter(|max| per_page > *max) {
+            return Err(PageRequestError::PerPageTooLarge { per_page, max });
+        }
+        if (page - 1).checked_mul(per_page).is_none() {
+            return Err(PageRequestError::OffsetOverflow { page, per_page });
+        }
+        Ok(Self { page, per_page })
+    }
+
+    /// The number of records to skip, for `OFFSET`.
+    pub fn offset(&self) -> i64 {
+        (self.page - 1).max(0).saturating_mul(self.per_page)
+    }
+}
+
+/// The unchecked fields of a deserialized [`PageRequest`].
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RawPageRequest {
+    page: i64,
+    per_page: i64,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RawPageRequest> for PageRequest {
+    type Error = PageRequestError;
+
+    fn try_from(raw: RawPageRequest) -> Result<Self, Self::Error> {
+        Self::try_new(raw.page, raw.per_page)
+    }
+}
+
+/// Why a [`PageRequest`] was rejected.
+///
+/// Generated paged methods return it as [`diesel::result::Error::QueryBuilderError`], from
+/// which it can be recovered with `downcast_ref::<PageRequestError>()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageRequestError {
+    /// The page is below 1.
+    InvalidPage(i64),
+    /// The number of records per page is below 1.
+    InvalidPerPage(i64),
+    /// The number of records per page exceeds the repository's `max_per_page`.
+    PerPageTooLarge { per_page: i64, max: i64 },
+    /// The offset of the page does not fit in an `i64`.
+    OffsetOverflow { page: i64, per_page: i64 },
+}
+
+impl fmt::Display for PageRequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PageRequestError::InvalidPage(page) => {
+                write!(f, "page must be at least 1, got {}", page)
+            }
+            PageRequestError::InvalidPerPage(per_page) => {
+                write!(f, "per_page must be at least 1, got {}", per_page)
+            }
+            PageRequestError::PerPageTooLarge { per_page, max } => {
+                write!(f, "per_page must be at most {}, got {}", max, per_page)
+            }
+            PageRequestError::OffsetOverflow { page, per_page } => write!(
+                f,
+                "page {} of {} records is beyond the largest offset",
+                page, per_page
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PageRequestError {}
+
+impl From<PageRequestError> for diesel::result::Error {
+    fn from(err: PageRequestError) -> Self {
+        diesel::result::Error::QueryBuilderError(Box::new(err))
+    }
+}
+
 #[derive(QueryableByName, Debug)]
 pub struct ViewCount {
     #[diesel(sql_type = BigInt)]
     pub count: i64,
 }
+
+/// The kind of write a repository performed on a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    Insert,
+    Update,
+    Replace,
+    Delete,
+}
+
+impl Operation {
+    /// Returns the lowercase name stored in outbox and history tables.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Operation::Insert => "insert",
+            Operation::Update => "update",
+            Operation::Replace => "replace",
+            Operation::Delete => "delete",
+        }
+    }
+
+    /// Parses a name returned by [`Operation::as_str`].
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "insert" => Some(Operation::Insert),
+            "update" => Some(Operation::Update),
+            "replace" => Some(Operation::Replace),
+            "delete" => Some(Operation::Delete),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use crate::Operation;
+
+/// Domain events raised by an entity when a repository writes it.
+///
+/// With `#[repository(outbox = outbox_events)]`, the generated `save`, `update`, `patch`,
+/// `replace` and `delete` methods collect the events of the written record and store them
+/// in the outbox table within the same transaction as the write.
+pub trait DomainEvents {
+    /// Returns the events to publish for the given write of this record.
+    fn domain_events(&self, operation: Operation) -> Vec<OutboxEvent>;
+}
+
+/// An event waiting in the outbox table to be published.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutboxEvent {
+    /// The kind of aggregate the event belongs to, e.g. `account`.
+    pub aggregate_type: String,
+    /// The identifier of the aggregate instance.
+    pub aggregate_id: String,
+    /// The name of the event, e.g. `account.created`.
+    pub event_type: String,
+    /// The serialized event payload, usually JSON.
+    pub payload: String,
+}
+
+#[cfg(feature = "postgres")]
+pub use self::postgres::*;
+
+/// Postgres support for writing and relaying outbox events.
+///
+/// The outbox table is expected to look like:
+///
+/// ```sql
+/// CREATE TABLE outbox_events (
+///     id BIGSERIAL PRIMARY KEY,
+///     aggregate_type TEXT NOT NULL,
+///     aggregate_id TEXT NOT NULL,
+///     event_type TEXT NOT NULL,
+///     payload TEXT NOT NULL,
+///     created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+///     published_at TIMESTAMPTZ
+/// );
+/// ```
+#[cfg(feature = "postgres")]
+mod postgres {
+    use super::OutboxEvent;
+    use diesel::connection::LoadConnection;
+    use diesel::pg::Pg;
+    use diesel::prelude::*;
+    use diesel::sql_types::{Array, BigInt, Text};
+    use std::fmt;
+    use std::time::Duration;
+
+    /// Stores the events in the outbox table.
+    ///
+    /// Call this with the connection of the transaction performing the write, so that the
+    /// events are committed or rolled back together with it.
+    pub fn write_events<C>(conn: &mut C, table: &str, events: &[OutboxEvent]) -> QueryResult<()>
+    where
+        C: Connection<Backend = Pg>,
+    {
+        let statement = format!(
+            "INSERT INTO {} (aggregate_type, aggregate_id, event_type, payload) VALUES ($1, $2, $3, $4)",
+            table
+        );
+        for event in events {
+            diesel::sql_query(&statement)
+                .bind::<Text, _>(&event.aggregate_type)
+                .bind::<Text, _>(&event.aggregate_id)
+                .bind::<Text, _>(&event.event_type)
+                .bind::<Text, _>(&event.payload)
+                .execute(conn)?;
+        }
+        Ok(())
+    }
+
+    /// Stores the events in the outbox table over an async connection, like [`write_events`].
+    #[cfg(feature = "async")]
+    pub async fn write_events_async<C>(
+        conn: &mut C,
+        table: &str,
+        events: &[OutboxEvent],
+    ) -> QueryResult<()>
+    where
+        C: diesel_async::AsyncConnection<Backend = Pg>,
+    {
+        let statement = format!(
+            "INSERT INTO {} (aggregate_type, aggregate_id, event_type, payload) VALUES ($1, $2, $3, $4)",
+            table
+        );
+        for event in events {
+            let query = diesel::sql_query(&statement)
+                .bind::<Text, _>(&event.aggregate_type)
+                .bind::<Text, _>(&event.aggregate_id)
+                .bind::<Text, _>(&event.event_type)
+                .bind::<Text, _>(&event.payload);
+            diesel_async::RunQueryDsl::execute(query, conn).await?;
+        }
+        Ok(())
+    }
+
+    /// An event claimed from the outbox table.
+    #[derive(QueryableByName, Debug, Clone, PartialEq, Eq)]
+    pub struct OutboxMessage {
+        /// The position of the event in the outbox table.
+        #[diesel(sql_type = BigInt)]
+        pub id: i64,
+        #[diesel(sql_type = Text)]
+        pub aggregate_type: String,
+        #[diesel(sql_type = Text)]
+        pub aggregate_id: String,
+        #[diesel(sql_type = Text)]
+        pub event_type: String,
+        #[diesel(sql_type = Text)]
+        pub payload: String,
+    }
+
+    impl From<OutboxMessage> for OutboxEvent {
+        fn from(message: OutboxMessage) -> Self {
+            OutboxEvent {
+                aggregate_type: message.aggregate_type,
+                aggregate_id: message.aggregate_id,
+                event_type: message.event_type,
+                payload: message.payload,
+            }
+        }
+    }
+
+    /// Hands outbox events over to a message broker.
+    pub trait OutboxPublisher {
+        /// Publishes one event. An error stops the current relay batch; the event and
+        /// everything after it are retried on the next poll.
+        fn publish(
+            &self,
+            message: &OutboxMessage,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+    }
+
+    /// Error returned by [`OutboxRelay`].
+    #[derive(Debug)]
+    pub enum RelayError {
+        /// Claiming or acknowledging events failed.
+        Database(diesel::result::Error),
+        /// The publisher rejected an event.
+        Publish(Box<dyn std::error::Error + Send + Sync>),
+    }
+
+    impl fmt::Display for RelayError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                RelayError::Database(err) => write!(f, "outbox database error: {}", err),
+                RelayError::Publish(err) => write!(f, "outbox publish error: {}", err),
+            }
+        }
+    }
+
+    impl std::error::Error for RelayError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            match self {
+                RelayError::Database(err) => Some(err),
+                RelayError::Publish(err) => Some(err.as_ref()),
+            }
+        }
+    }
+
+    impl From<diesel::result::Error> for RelayError {
+        fn from(err: diesel::result::Error) -> Self {
+            RelayError::Database(err)
+        }
+    }
+
+    /// Polls the outbox table and hands unpublished events to an [`OutboxPublisher`].
+    ///
+    /// Events are claimed with `FOR UPDATE SKIP LOCKED`, so several relays can poll the
+    /// same table concurrently without publishing an event twice while it is in flight.
+    /// Delivery is at-least-once: an event is marked as published only after the publisher
+    /// accepted it.
+    pub struct OutboxRelay<P> {
+        table: String,
+        batch_size: i64,
+        publisher: P,
+    }
+
+    impl<P: OutboxPublisher> OutboxRelay<P> {
+        pub fn new(table: impl Into<String>, publisher: P) -> Self {
+            Self {
+                table: table.into(),
+                batch_size: 100,
+                publisher,
+            }
+        }
+
+        /// Sets the maximum number of events claimed per poll (default: 100).
+        pub fn batch_size(mut self, batch_size: i64) -> Self {
+            self.batch_size = batch_size;
+            self
+        }
+
+        /// Claims one batch of events, publishes them in order and marks the published ones.
+        ///
+        /// Returns the number of events published.
+        pub fn relay_once<C>(&self, conn: &mut C) -> Result<usize, RelayError>
+        where
+            C: Connection<Backend = Pg> + LoadConnection,
+        {
+            conn.transaction(|conn| {
+                let claim = format!(
+                    "SELECT id, aggregate_type, aggregate_id, event_type, payload FROM {} \
+                     WHERE published_at IS NULL ORDER BY id LIMIT $1 FOR UPDATE SKIP LOCKED",
+                    self.table
+                );
+                let messages: Vec<OutboxMessage> = diesel::sql_query(claim)
+                    .bind::<BigInt, _>(self.batch_size)
+                    .load(conn)?;
+
+                let mut published = Vec::with_capacity(messages.len());
+                let mut failure = None;
+                for message in &messages {
+                    match self.publisher.publish(message) {
+                        Ok(()) => published.push(message.id),
+                        Err(err) => {
+                            failure = Some(err);
+                            break;
+                        }
+                    }
+                }
+
+                if !published.is_empty() {
+                    let acknowledge = format!(
+                        "UPDATE {} SET published_at = now() WHERE id = ANY($1)",
+                        self.table
+                    );
+                    diesel::sql_query(acknowledge)
+                        .bind::<Array<BigInt>, _>(&published)
+                        .execute(conn)?;
+                }
+
+                // Commit the acknowledged events even if a later one failed to publish.
+                Ok::<_, RelayError>((published.len(), failure))
+            })
+            .and_then(|(count, failure)| match failure {
+                Some(err) => Err(RelayError::Publish(err)),
+                None => Ok(count),
+            })
+        }
+
+        /// Relays events until an error occurs, sleeping for `interval` whenever the
+        /// outbox is drained.
+        pub fn run<C>(&self, conn: &mut C, interval: Duration) -> Result<(), RelayError>
+        where
+            C: Connection<Backend = Pg> + LoadConnection,
+        {
+            loop {
+                if self.relay_once(conn)? == 0 {
+                    std::thread::sleep(interval);
+                }
+            }
+        }
+    }
+}
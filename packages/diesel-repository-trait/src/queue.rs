@@ -0,0 +1,202 @@
+//! Postgres job queues for `#[repository(queue(...))]`.
+//!
+//! A queue is a plain table whose rows are jobs. Workers claim due jobs with
+//! `FOR UPDATE SKIP LOCKED`, so any number of them can poll the same table without
+//! claiming a job twice or waiting on each other's locks.
+
+use std::time::Duration;
+
+/// The lifecycle of a job, stored as text in the status column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    /// Waiting for its `run_at`, or due.
+    Pending,
+    /// Claimed by a worker.
+    Running,
+    /// Completed by a worker.
+    Done,
+    /// Gave up on after its last attempt failed.
+    Failed,
+}
+
+impl JobStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Running => "running",
+            JobStatus::Done => "done",
+            JobStatus::Failed => "failed",
+        }
+    }
+}
+
+/// How long a failed job waits before it is due again: `base * 2^(attempts - 1)`, capped
+/// at `max_delay`, until `max_attempts` attempts failed.
+///
+/// ```
+/// use diesel_repository_trait::Backoff;
+/// use std::time::Duration;
+///
+/// let backoff = Backoff::exponential(Duration::from_secs(1)).max_delay(Duration::from_secs(5));
+/// assert_eq!(backoff.delay(3), Duration::from_secs(4));
+/// assert_eq!(backoff.delay(4), Duration::from_secs(5));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    pub base: Duration,
+    pub max_delay: Duration,
+    pub max_attempts: i32,
+}
+
+impl Backoff {
+    /// Doubles the delay after every attempt, up to an hour, and gives up after 5 attempts.
+    pub fn exponential(base: Duration) -> Self {
+        Self {
+            base,
+            max_delay: Duration::from_secs(60 * 60),
+            max_attempts: 5,
+        }
+    }
+
+    /// Sets the longest delay between two attempts.
+    pub fn max_delay(self, max_delay: Duration) -> Self {
+        Self { max_delay, ..self }
+    }
+
+    /// Sets the number of attempts after which a failing job is marked as failed.
+    pub fn max_attempts(self, max_attempts: i32) -> Self {
+        Self {
+            max_attempts,
+            ..self
+        }
+    }
+
+    /// The delay before the next attempt of a job that failed its `attempts`-th attempt.
+    pub fn delay(&self, attempts: i32) -> Duration {
+        let exponent = (attempts.max(1) - 1).min(MAX_EXPONENT);
+        self.base
+            .checked_mul(1 << exponent)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+
+    /// Whether a job that failed its `attempts`-th attempt is not retried.
+    pub fn gives_up(&self, attempts: i32) -> bool {
+        attempts >= self.max_attempts
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::exponential(Duration::from_secs(1))
+    }
+}
+
+/// Keeps `2^exponent` within `u32` and `power(2, exponent)` within `double precision`.
+const MAX_EXPONENT: i32 = 31;
+
+/// The columns of a queue table, and the statements operating on it.
+///
+/// The table is expected to look like:
+///
+/// ```sql
+/// CREATE TABLE jobs (
+///     id BIGSERIAL PRIMARY KEY,
+///     payload TEXT NOT NULL,
+///     status TEXT NOT NULL DEFAULT 'pending',
+///     run_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+///     attempts INTEGER NOT NULL DEFAULT 0
+/// );
+/// CREATE INDEX ON jobs (run_at) WHERE status = 'pending';
+/// ```
+///
+/// While a job is running, `run_at` holds the time it was claimed. Durations are bound in
+/// milliseconds as `Double`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueTable {
+    pub table: &'static str,
+    pub id: &'static str,
+    pub status: &'static str,
+    pub run_at: &'static str,
+    pub attempts: &'static str,
+}
+
+impl QueueTable {
+    /// Claims up to `$1` due jobs, oldest first, skipping jobs claimed by other workers.
+    /// Claimed jobs become running and count one more attempt.
+    pub fn dequeue_sql(&self) -> String {
+        let QueueTable {
+            table,
+            id,
+            status,
+            run_at,
+            attempts,
+        } = self;
+        format!(
+            "WITH due AS (\
+                SELECT {id} FROM {table} WHERE {status} = '{pending}' AND {run_at} <= now() \
+                ORDER BY {run_at} LIMIT $1 FOR UPDATE SKIP LOCKED\
+             ) \
+             UPDATE {table} SET {status} = '{running}', {attempts} = {table}.{attempts} + 1, \
+             {run_at} = now() FROM due WHERE {table}.{id} = due.{id} RETURNING {table}.*",
+            pending = JobStatus::Pending.as_str(),
+            running = JobStatus::Running.as_str(),
+        )
+    }
+
+    /// Marks the running job `$1` as done.
+    pub fn complete_sql(&self) -> String {
+        format!(
+            "UPDATE {table} SET {status} = '{done}' WHERE {id} = $1 AND {status} = '{running}'",
+            table = self.table,
+            id = self.id,
+            status = self.status,
+            done = JobStatus::Done.as_str(),
+            running = JobStatus::Running.as_str(),
+        )
+    }
+
+    /// Reschedules the running job `$1` after a failed attempt, following a [`Backoff`]
+    /// bound as `$2` (`max_attempts`), `$3` (`base`) and `$4` (`max_delay`). Returns the job.
+    pub fn fail_sql(&self) -> String {
+        format!(
+            "UPDATE {table} SET \
+             {status} = CASE WHEN {attempts} >= $2 THEN '{failed}' ELSE '{pending}' END, \
+             {run_at} = now() + LEAST($3 * power(2, LEAST(GREATEST({attempts}, 1) - 1, {max})), $4) \
+             * interval '1 millisecond' \
+             WHERE {id} = $1 AND {status} = '{running}' RETURNING *",
+            table = self.table,
+            id = self.id,
+            status = self.status,
+            run_at = self.run_at,
+            attempts = self.attempts,
+            max = MAX_EXPONENT,
+            failed = JobStatus::Failed.as_str(),
+            pending = JobStatus::Pending.as_str(),
+            running = JobStatus::Running.as_str(),
+        )
+    }
+
+    /// Makes jobs running for longer than `$1` due again, e.g. after their worker crashed.
+    /// Like [`fail_sql`](Self::fail_sql), a job that already used `$2` (`max_attempts`)
+    /// attempts is marked as failed instead.
+    pub fn reap_sql(&self) -> String {
+        format!(
+            "UPDATE {table} SET \
+             {status} = CASE WHEN {attempts} >= $2 THEN '{failed}' ELSE '{pending}' END, \
+             {run_at} = now() \
+             WHERE {status} = '{running}' AND {run_at} < now() - $1 * interval '1 millisecond'",
+            table = self.table,
+            status = self.status,
+            run_at = self.run_at,
+            attempts = self.attempts,
+            failed = JobStatus::Failed.as_str(),
+            pending = JobStatus::Pending.as_str(),
+            running = JobStatus::Running.as_str(),
+        )
+    }
+
+    /// A duration in milliseconds, as bound by the statements above.
+    pub fn millis(duration: Duration) -> f64 {
+        duration.as_secs_f64() * 1000.0
+    }
+}
@@ -28,3 +28,32 @@ pub trait DeleteBatch<ID> {
     /// Deletes multiple records given a slice of IDs.
     async fn delete_batch(&self, ids: &[ID]) -> Result<(), Error>;
 }
+
+/// Trait for checking the existence of a batch of IDs.
+#[async_trait]
+pub trait ExistsByIdBatch<ID> {
+    /// Given a slice of IDs, returns the subset of IDs that exist.
+    async fn exists_by_id_batch(&self, ids: &[ID]) -> Result<Vec<ID>, Error>;
+}
+
+/// The batch operations of a repository for `T` keyed by `ID`.
+///
+/// Implemented for every repository that implements its parts.
+pub trait BatchRepository<T, ID, NewRecord, UpdateRecord>:
+    FindByIdBatch<T, ID>
+    + SaveBatch<T, NewRecord>
+    + UpdateBatch<T, UpdateRecord>
+    + DeleteBatch<ID>
+    + ExistsByIdBatch<ID>
+{
+}
+
+impl<R, T, ID, NewRecord, UpdateRecord> BatchRepository<T, ID, NewRecord, UpdateRecord> for R where
+    R: FindByIdBatch<T, ID>
+        + SaveBatch<T, NewRecord>
+        + UpdateBatch<T, UpdateRecord>
+        + DeleteBatch<ID>
+        + ExistsByIdBatch<ID>
+        + ?Sized
+{
+}
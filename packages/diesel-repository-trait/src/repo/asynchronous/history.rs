@@ -0,0 +1,17 @@
+use crate::HistoryEntry;
+use async_trait::async_trait;
+use diesel::result::Error;
+use std::time::SystemTime;
+
+/// Trait for reading the change history of a record.
+///
+/// The history table holds the previous row image of every update, replace and delete,
+/// together with the operation, the actor and the time of the change.
+#[async_trait]
+pub trait History<T, ID> {
+    /// Asynchronously returns every recorded change of the record with the given ID, oldest first.
+    async fn history_of(&self, id: ID) -> Result<Vec<HistoryEntry<T>>, Error>;
+
+    /// Reconstructs the record as it was at `timestamp`, or `None` if it was deleted by then.
+    async fn as_of(&self, id: ID, timestamp: SystemTime) -> Result<Option<T>, Error>;
+}
@@ -0,0 +1,18 @@
+use crate::{LockMode, QueryFn, QueryRepository};
+use async_trait::async_trait;
+use diesel::result::Error;
+
+/// Trait for loading records with a row lock, implemented on
+/// [`InTransaction`](crate::InTransaction) so the lock is held until the transaction ends.
+#[async_trait]
+pub trait Lock<T, ID>: QueryRepository {
+    /// Loads the record with the given ID, locking its row, e.g. `SELECT ... FOR UPDATE`.
+    async fn find_by_id_locked(&mut self, id: ID, mode: LockMode) -> Result<T, Error>;
+
+    /// Executes the query and returns the matching records, locking their rows.
+    async fn find_by_query_locked(
+        &mut self,
+        query: &QueryFn<'_, Self::Query>,
+        mode: LockMode,
+    ) -> Result<Vec<T>, Error>;
+}
@@ -1,7 +1,15 @@
 mod batch;
 mod crud;
+mod history;
+mod lock;
 mod paged;
+#[cfg(feature = "subscribe")]
+mod subscribe;
 
 pub use batch::*;
 pub use crud::*;
+pub use history::*;
+pub use lock::*;
 pub use paged::*;
+#[cfg(feature = "subscribe")]
+pub use subscribe::*;
@@ -0,0 +1,21 @@
+use crate::{ChangeStream, Repository};
+use async_trait::async_trait;
+use diesel::result::Error;
+
+/// Trait for following the changes of a repository's table as they are committed.
+#[async_trait]
+pub trait Subscribe<T, ID>: Repository {
+    /// The channel the table's trigger notifies.
+    const CHANNEL: &'static str;
+
+    /// Installs the trigger publishing the table's changes on [`Self::CHANNEL`]. Not needed
+    /// when a migration already creates it with `notify_trigger_sql`.
+    async fn install_trigger(&self) -> Result<(), Error>;
+
+    /// Opens a dedicated listener connection to `database_url` and streams the changes.
+    ///
+    /// A `LISTEN` only lasts as long as its connection, and pooled connections go back to
+    /// the pool between queries. The repository's pool can hand out connections but not
+    /// open one that the listener keeps, so the listener connects to `database_url` itself.
+    async fn changes(&self, database_url: &str) -> Result<ChangeStream<T, ID>, Error>;
+}
@@ -1,5 +1,64 @@
-#[cfg(not(feature = "async"))]
 pub mod synchronous;
 
 #[cfg(feature = "async")]
 pub mod asynchronous;
+
+/// Describes the entity and identifier types a generated repository works with.
+///
+/// Implemented by `#[derive(Repo)]` for every generated repository, so that other
+/// repositories can refer to its types (e.g. `<PostRepo as Repository>::Entity`).
+pub trait Repository {
+    /// The entity loaded and stored by the repository.
+    type Entity;
+    /// The primary key type of the entity.
+    type Id;
+}
+
+/// Describes the query that the query methods of a repository start from.
+///
+/// Implemented by `#[derive(Repo)]` with the boxed query of the entity's table, e.g.
+/// `accounts::BoxedQuery<'static, Pg>`, so that traits such as `FindByQuery` take a
+/// [`QueryFn`] instead of a generic query type and stay usable as trait objects.
+pub trait QueryRepository: Repository {
+    /// The boxed query of the entity's table.
+    type Query;
+}
+
+/// Refines the boxed query of a [`QueryRepository`], e.g. `&|q| q.filter(name.eq("x"))`.
+pub type QueryFn<'q, Q> = dyn Fn(Q) -> Q + Send + Sync + 'q;
+
+/// Returned by the `build` method of a generated repository builder when a field was not set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildError {
+    /// The field left unset.
+    pub field: &'static str,
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "repository field `{}` was not set", self.field)
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Implemented by the types a repository can insert into the table `T`, i.e. any
+/// `Insertable<T>`; `#[derive(Repo)]` checks its `new_type` against it.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not `Insertable` into `{T}`, so it cannot be the repository's `new_type`",
+    note = "derive `Insertable` on `{Self}`, or name an insertable struct with `#[repo_type(new_type = ...)]`"
+)]
+pub trait NewRecord<T> {}
+
+impl<T, U: diesel::Insertable<T>> NewRecord<T> for U {}
+
+/// Implemented by references to the types a repository can update rows of the table `T`
+/// with, i.e. any `Identifiable` `AsChangeset` of `T`; `#[derive(Repo)]` checks a reference
+/// to its `update_type` against it.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not an `Identifiable` `AsChangeset` of `{T}`, so it cannot be the repository's `update_type`",
+    note = "derive `Identifiable` and `AsChangeset`, or name such a struct with `#[repo_type(update_type = ...)]`"
+)]
+pub trait UpdateRecord<T> {}
+
+impl<T, U> UpdateRecord<T> for U where U: diesel::AsChangeset<Target = T> + diesel::Identifiable {}
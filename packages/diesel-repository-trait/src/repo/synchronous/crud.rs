@@ -1,3 +1,4 @@
+use crate::{QueryFn, QueryRepository};
 use diesel::result::Error;
 
 /// Trait for retrieving a record by its identifier.
@@ -8,15 +9,15 @@ pub trait FindById<T, ID> {
 
 /// Trait for retrieving a single record using a Diesel query.
 /// This is useful when you expect the query to return exactly one row.
-pub trait FindOneByQuery<T> {
+pub trait FindOneByQuery<T>: QueryRepository {
     /// Executes the query and returns one record.
-    fn find_one_by_query<Q: diesel::QueryDsl>(&self, query: Q) -> Result<T, Error>;
+    fn find_one_by_query(&self, query: &QueryFn<'_, Self::Query>) -> Result<T, Error>;
 }
 
 /// Trait for retrieving multiple records using a Diesel query.
-pub trait FindByQuery<T> {
+pub trait FindByQuery<T>: QueryRepository {
     /// Executes the query and returns a vector of matching records.
-    fn find_by_query<Q: diesel::QueryDsl>(&self, query: Q) -> Result<Vec<T>, Error>;
+    fn find_by_query(&self, query: &QueryFn<'_, Self::Query>) -> Result<Vec<T>, Error>;
 }
 
 /// Trait for retrieving all records from a table.
@@ -37,6 +38,14 @@ pub trait Update<T, UpdateRecord> {
     fn update(&self, update_record: UpdateRecord) -> Result<T, Error>;
 }
 
+/// Trait for partially updating an existing record.
+/// Only the fields set to `Some` in the patch record are written; a patch without any
+/// returns the current record unchanged.
+pub trait Patch<T, ID, PatchRecord> {
+    /// Applies the provided fields to the record with the given ID and returns the updated version.
+    fn patch(&self, id: ID, patch_record: PatchRecord) -> Result<T, Error>;
+}
+
 /// Trait for a “replace” operation (upsert).
 pub trait Replace<T, NewRecord> {
     /// Replaces a record if it exists or inserts it if not.
@@ -50,7 +59,57 @@ pub trait Delete<ID> {
 }
 
 /// (Optional) Trait for counting records matching a query.
-pub trait Count {
+pub trait Count: QueryRepository {
     /// Returns the count of records that match the provided query.
-    fn count<Q: diesel::QueryDsl>(&self, query: Q) -> Result<i64, Error>;
+    fn count(&self, query: &QueryFn<'_, Self::Query>) -> Result<i64, Error>;
+}
+
+/// Trait for checking whether a record exists by its identifier.
+pub trait ExistsById<ID> {
+    /// Returns `true` if a record with the given ID exists, using `SELECT EXISTS(...)`.
+    fn exists_by_id(&self, id: ID) -> Result<bool, Error>;
+}
+
+/// Trait for checking whether any record matches a Diesel query.
+pub trait ExistsByQuery: QueryRepository {
+    /// Returns `true` if the query matches at least one row, using `SELECT EXISTS(...)`.
+    fn exists_by_query(&self, query: &QueryFn<'_, Self::Query>) -> Result<bool, Error>;
+}
+
+/// Trait for loading records as a projection `P`, a `Selectable` DTO mapped to the same table.
+/// Only the columns of `P` are selected.
+pub trait Project<P>: QueryRepository {
+    /// Returns all records projected into `P`.
+    fn find_all_as(&self) -> Result<Vec<P>, Error>;
+
+    /// Executes the query and returns the matching records projected into `P`.
+    fn find_by_query_as(&self, query: &QueryFn<'_, Self::Query>) -> Result<Vec<P>, Error>;
+}
+
+/// The usual CRUD operations of a repository for `T` keyed by `ID`, created from `NewRecord`
+/// and updated from `UpdateRecord`, like Spring Data's `CrudRepository`.
+///
+/// Implemented for every repository that implements its parts, so services can bound on
+/// `R: CrudRepository<Account, String, NewAccount, Account>` alone.
+pub trait CrudRepository<T, ID, NewRecord, UpdateRecord>:
+    FindById<T, ID>
+    + FindAll<T>
+    + Save<T, NewRecord>
+    + Update<T, UpdateRecord>
+    + Delete<ID>
+    + ExistsById<ID>
+    + Count
+{
+}
+
+impl<R, T, ID, NewRecord, UpdateRecord> CrudRepository<T, ID, NewRecord, UpdateRecord> for R where
+    R: FindById<T, ID>
+        + FindAll<T>
+        + Save<T, NewRecord>
+        + Update<T, UpdateRecord>
+        + Delete<ID>
+        + ExistsById<ID>
+        + Count
+        + ?Sized
+{
 }
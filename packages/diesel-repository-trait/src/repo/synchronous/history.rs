@@ -0,0 +1,15 @@
+use crate::HistoryEntry;
+use diesel::result::Error;
+use std::time::SystemTime;
+
+/// Trait for reading the change history of a record.
+///
+/// The history table holds the previous row image of every update, replace and delete,
+/// together with the operation, the actor and the time of the change.
+pub trait History<T, ID> {
+    /// Returns every recorded change of the record with the given ID, oldest first.
+    fn history_of(&self, id: ID) -> Result<Vec<HistoryEntry<T>>, Error>;
+
+    /// Reconstructs the record as it was at `timestamp`, or `None` if it was deleted by then.
+    fn as_of(&self, id: ID, timestamp: SystemTime) -> Result<Option<T>, Error>;
+}
@@ -0,0 +1,16 @@
+use crate::{LockMode, QueryFn, QueryRepository};
+use diesel::result::Error;
+
+/// Trait for loading records with a row lock, implemented on
+/// [`InTransaction`](crate::InTransaction) so the lock is held until the transaction ends.
+pub trait Lock<T, ID>: QueryRepository {
+    /// Loads the record with the given ID, locking its row, e.g. `SELECT ... FOR UPDATE`.
+    fn find_by_id_locked(&mut self, id: ID, mode: LockMode) -> Result<T, Error>;
+
+    /// Executes the query and returns the matching records, locking their rows.
+    fn find_by_query_locked(
+        &mut self,
+        query: &QueryFn<'_, Self::Query>,
+        mode: LockMode,
+    ) -> Result<Vec<T>, Error>;
+}
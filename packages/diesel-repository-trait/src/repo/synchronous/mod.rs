@@ -1,7 +1,11 @@
 mod batch;
 mod crud;
+mod history;
+mod lock;
 mod paged;
 
 pub use batch::*;
 pub use crud::*;
+pub use history::*;
+pub use lock::*;
 pub use paged::*;
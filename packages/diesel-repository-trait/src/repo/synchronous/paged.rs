@@ -1,18 +1,18 @@
-use crate::Paged;
+use crate::{Paged, QueryFn, QueryRepository, Slice};
 use diesel::result::Error;
 
 /// Trait for executing a paged query using a Diesel query builder.
-pub trait FindByQueryPaged<T> {
+pub trait FindByQueryPaged<T>: QueryRepository {
     /// Executes the query with pagination.
     ///
-    /// - `query`: A Diesel query builder instance.
+    /// - `query`: Refines the boxed query of the table, e.g. `&|q| q.filter(...)`.
     /// - `page`: The page number (1-indexed).
     /// - `per_page`: The number of records per page.
     ///
     /// Returns a [`Paged<T>`] with the items and paging metadata.
-    fn find_by_query_paged<Q: diesel::QueryDsl>(
+    fn find_by_query_paged(
         &self,
-        query: Q,
+        query: &QueryFn<'_, Self::Query>,
         page: i64,
         per_page: i64,
     ) -> Result<Paged<T>, Error>;
@@ -23,3 +23,61 @@ pub trait FindAllPaged<T> {
     /// Returns all records paginated.
     fn find_all_paged(&self, page: i64, per_page: i64) -> Result<Paged<T>, Error>;
 }
+
+/// Trait for loading records as a projection `P` in a paginated form.
+pub trait ProjectPaged<P>: QueryRepository {
+    /// Returns all records projected into `P`, paginated.
+    fn find_all_as_paged(&self, page: i64, per_page: i64) -> Result<Paged<P>, Error>;
+
+    /// Executes the query with pagination and returns the records projected into `P`.
+    fn find_by_query_as_paged(
+        &self,
+        query: &QueryFn<'_, Self::Query>,
+        page: i64,
+        per_page: i64,
+    ) -> Result<Paged<P>, Error>;
+}
+
+/// Trait for executing a query in pages without a total count, for
+/// `#[paging_repo(count_strategy = "none")]`.
+pub trait FindByQuerySlice<T>: QueryRepository {
+    /// Executes the query and returns one page, fetching `per_page + 1` rows to learn
+    /// whether a next page exists.
+    fn find_by_query_slice(
+        &self,
+        query: &QueryFn<'_, Self::Query>,
+        page: i64,
+        per_page: i64,
+    ) -> Result<Slice<T>, Error>;
+}
+
+/// Trait for retrieving all records in pages without a total count.
+pub trait FindAllSlice<T> {
+    /// Returns one page of all records.
+    fn find_all_slice(&self, page: i64, per_page: i64) -> Result<Slice<T>, Error>;
+}
+
+/// Trait for loading records as a projection `P` in pages without a total count.
+pub trait ProjectSlice<P>: QueryRepository {
+    /// Returns one page of all records projected into `P`.
+    fn find_all_as_slice(&self, page: i64, per_page: i64) -> Result<Slice<P>, Error>;
+
+    /// Executes the query and returns one page of the records projected into `P`.
+    fn find_by_query_as_slice(
+        &self,
+        query: &QueryFn<'_, Self::Query>,
+        page: i64,
+        per_page: i64,
+    ) -> Result<Slice<P>, Error>;
+}
+
+/// Paged access to the records of `T`, like Spring Data's `PagingAndSortingRepository`.
+/// Sorting is part of the query, e.g. `&|q| q.order(name.asc())`.
+///
+/// Implemented for every repository that implements its parts.
+pub trait PagingAndSortingRepository<T>: FindAllPaged<T> + FindByQueryPaged<T> {}
+
+impl<R, T> PagingAndSortingRepository<T> for R where
+    R: FindAllPaged<T> + FindByQueryPaged<T> + ?Sized
+{
+}
@@ -0,0 +1,362 @@
+//! Startup verification of repository tables against the deployed schema.
+//!
+//! `#[repository(schema_check)]` records the columns of the entity with the SQL types of its
+//! `table!`; [`verify_schema`] compares them with what the database reports, so that drift
+//! fails at startup instead of as deserialization errors at runtime.
+
+use diesel::connection::LoadConnection;
+use diesel::prelude::*;
+use diesel::sql_types::*;
+use std::fmt;
+
+/// A SQL type of `table!`, with the column types a database may report for it.
+pub trait SchemaType {
+    /// The Postgres `udt_name`s of a matching column.
+    const POSTGRES: &'static [&'static str];
+    /// The SQLite affinity of a matching column, `None` to accept any declared type.
+    const SQLITE: Option<SqliteAffinity>;
+    /// Whether the column holds `NULL`s, i.e. is declared `Nullable<...>`.
+    const NULLABLE: bool = false;
+}
+
+/// How SQLite stores the values of a column, derived from its declared type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqliteAffinity {
+    Integer,
+    Text,
+    Blob,
+    Real,
+    Numeric,
+}
+
+impl SqliteAffinity {
+    /// The affinity of a declared column type, following SQLite's rules in order.
+    pub fn of(declared: &str) -> Self {
+        let declared = declared.to_ascii_uppercase();
+        if declared.contains("INT") {
+            SqliteAffinity::Integer
+        } else if ["CHAR", "CLOB", "TEXT"]
+            .iter()
+            .any(|t| declared.contains(t))
+        {
+            SqliteAffinity::Text
+        } else if declared.is_empty() || declared.contains("BLOB") {
+            SqliteAffinity::Blob
+        } else if ["REAL", "FLOA", "DOUB"]
+            .iter()
+            .any(|t| declared.contains(t))
+        {
+            SqliteAffinity::Real
+        } else {
+            SqliteAffinity::Numeric
+        }
+    }
+}
+
+impl<T: SchemaType> SchemaType for Nullable<T> {
+    const POSTGRES: &'static [&'static str] = T::POSTGRES;
+    const SQLITE: Option<SqliteAffinity> = T::SQLITE;
+    const NULLABLE: bool = true;
+}
+
+macro_rules! schema_types {
+    ($($sql_type:ty => [$($postgres:literal),*], $sqlite:expr;)*) => {
+        $(
+            impl SchemaType for $sql_type {
+                const POSTGRES: &'static [&'static str] = &[$($postgres),*];
+                const SQLITE: Option<SqliteAffinity> = $sqlite;
+            }
+        )*
+    };
+}
+
+schema_types! {
+    SmallInt => ["int2"], Some(SqliteAffinity::Integer);
+    Integer => ["int4"], Some(SqliteAffinity::Integer);
+    BigInt => ["int8"], Some(SqliteAffinity::Integer);
+    Float => ["float4"], Some(SqliteAffinity::Real);
+    Double => ["float8"], Some(SqliteAffinity::Real);
+    Numeric => ["numeric"], None;
+    Bool => ["bool"], None;
+    Text => ["text", "varchar", "bpchar", "citext", "name"], Some(SqliteAffinity::Text);
+    Binary => ["bytea"], Some(SqliteAffinity::Blob);
+    Date => ["date"], None;
+    Time => ["time"], None;
+    Timestamp => ["timestamp"], None;
+}
+
+#[cfg(feature = "postgres")]
+schema_types! {
+    Timestamptz => ["timestamptz"], None;
+    Interval => ["interval"], None;
+    Uuid => ["uuid"], None;
+    Json => ["json"], None;
+    Jsonb => ["jsonb"], None;
+    Array<SmallInt> => ["_int2"], None;
+    Array<Integer> => ["_int4"], None;
+    Array<BigInt> => ["_int8"], None;
+    Array<Double> => ["_float8"], None;
+    Array<Bool> => ["_bool"], None;
+    Array<Text> => ["_text", "_varchar"], None;
+    Array<Uuid> => ["_uuid"], None;
+}
+
+/// A column as declared by `table!`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColumnSchema {
+    pub name: &'static str,
+    pub postgres: &'static [&'static str],
+    pub sqlite: Option<SqliteAffinity>,
+    pub nullable: bool,
+}
+
+impl ColumnSchema {
+    /// The column `name` of SQL type `T`, e.g. `ColumnSchema::of::<SqlTypeOf<accounts::id>>("id")`.
+    pub const fn of<T: SchemaType>(name: &'static str) -> Self {
+        Self {
+            name,
+            postgres: T::POSTGRES,
+            sqlite: T::SQLITE,
+            nullable: T::NULLABLE,
+        }
+    }
+}
+
+/// The columns a repository reads and writes in its table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TableSchema {
+    pub table: &'static str,
+    pub columns: &'static [ColumnSchema],
+}
+
+/// Implemented by `#[repository(schema_check)]` with the columns of the entity.
+pub trait SchemaCheck {
+    const SCHEMA: TableSchema;
+}
+
+/// A difference between a [`TableSchema`] and the deployed table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaIssue {
+    MissingTable {
+        table: &'static str,
+    },
+    MissingColumn {
+        table: &'static str,
+        column: &'static str,
+    },
+    TypeMismatch {
+        table: &'static str,
+        column: &'static str,
+        expected: String,
+        found: String,
+    },
+    NullabilityMismatch {
+        table: &'static str,
+        column: &'static str,
+        /// Whether `table!` declares the column `Nullable`.
+        nullable: bool,
+    },
+}
+
+impl fmt::Display for SchemaIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaIssue::MissingTable { table } => write!(f, "table `{}` does not exist", table),
+            SchemaIssue::MissingColumn { table, column } => {
+                write!(f, "column `{}.{}` does not exist", table, column)
+            }
+            SchemaIssue::TypeMismatch {
+                table,
+                column,
+                expected,
+                found,
+            } => write!(
+                f,
+                "column `{}.{}` has type `{}`, expected {}",
+                table, column, found, expected
+            ),
+            SchemaIssue::NullabilityMismatch {
+                table,
+                column,
+                nullable: true,
+            } => write!(
+                f,
+                "column `{}.{}` is NOT NULL but declared `Nullable`",
+                table, column
+            ),
+            SchemaIssue::NullabilityMismatch { table, column, .. } => write!(
+                f,
+                "column `{}.{}` is nullable but not declared `Nullable`",
+                table, column
+            ),
+        }
+    }
+}
+
+/// Error returned by [`verify_schema`].
+#[derive(Debug)]
+pub enum SchemaError {
+    /// Reading the deployed schema failed.
+    Database(diesel::result::Error),
+    /// The deployed schema differs from the declared one.
+    Drift(Vec<SchemaIssue>),
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaError::Database(err) => write!(f, "schema verification failed: {}", err),
+            SchemaError::Drift(issues) => {
+                write!(f, "the database schema differs from `table!`:")?;
+                for issue in issues {
+                    write!(f, "\n  - {}", issue)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SchemaError::Database(err) => Some(err),
+            SchemaError::Drift(_) => None,
+        }
+    }
+}
+
+impl From<diesel::result::Error> for SchemaError {
+    fn from(err: diesel::result::Error) -> Self {
+        SchemaError::Database(err)
+    }
+}
+
+/// A column of the deployed table.
+#[derive(QueryableByName, Debug, Clone, PartialEq, Eq)]
+pub struct DeployedColumn {
+    #[diesel(sql_type = Text)]
+    pub column_name: String,
+    #[diesel(sql_type = Text)]
+    pub data_type: String,
+    #[diesel(sql_type = Bool)]
+    pub nullable: bool,
+}
+
+/// A backend whose deployed schema can be read.
+pub trait SchemaBackend: diesel::backend::Backend + Sized {
+    /// The columns of `table`, empty if it does not exist.
+    fn deployed_columns<C>(conn: &mut C, table: &str) -> QueryResult<Vec<DeployedColumn>>
+    where
+        C: Connection<Backend = Self> + LoadConnection;
+
+    /// Describes the types matching `column`, or `None` if `found` matches.
+    fn type_mismatch(column: &ColumnSchema, found: &str) -> Option<String>;
+}
+
+#[cfg(feature = "postgres")]
+impl SchemaBackend for diesel::pg::Pg {
+    fn deployed_columns<C>(conn: &mut C, table: &str) -> QueryResult<Vec<DeployedColumn>>
+    where
+        C: Connection<Backend = Self> + LoadConnection,
+    {
+        diesel::sql_query(
+            "SELECT column_name::text, udt_name::text AS data_type, is_nullable = 'YES' AS nullable \
+             FROM information_schema.columns \
+             WHERE table_schema = current_schema() AND table_name = $1",
+        )
+        .bind::<Text, _>(table)
+        .load(conn)
+    }
+
+    fn type_mismatch(column: &ColumnSchema, found: &str) -> Option<String> {
+        if column.postgres.contains(&found) {
+            return None;
+        }
+        let expected: Vec<_> = column
+            .postgres
+            .iter()
+            .map(|name| format!("`{}`", name))
+            .collect();
+        Some(expected.join(" or "))
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl SchemaBackend for diesel::sqlite::Sqlite {
+    fn deployed_columns<C>(conn: &mut C, table: &str) -> QueryResult<Vec<DeployedColumn>>
+    where
+        C: Connection<Backend = Self> + LoadConnection,
+    {
+        // An `INTEGER PRIMARY KEY` aliases the rowid and is never NULL, whatever it declares.
+        diesel::sql_query(
+            "SELECT name AS column_name, type AS data_type, \"notnull\" = 0 AND pk = 0 AS nullable \
+             FROM pragma_table_info(?)",
+        )
+        .bind::<Text, _>(table)
+        .load(conn)
+    }
+
+    fn type_mismatch(column: &ColumnSchema, found: &str) -> Option<String> {
+        match column.sqlite {
+            Some(affinity) if SqliteAffinity::of(found) != affinity => {
+                Some(format!("a type of {:?} affinity", affinity))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Compares the tables of the repositories with the deployed schema, reporting every missing
+/// table or column and every type or nullability difference. Extra columns are ignored.
+///
+/// ```ignore
+/// verify_schema(&mut conn, &[AccountRepo::SCHEMA, PostRepo::SCHEMA])?;
+/// ```
+pub fn verify_schema<C>(conn: &mut C, tables: &[TableSchema]) -> Result<(), SchemaError>
+where
+    C: Connection + LoadConnection,
+    C::Backend: SchemaBackend,
+{
+    let mut issues = Vec::new();
+    for schema in tables {
+        let deployed = C::Backend::deployed_columns(conn, schema.table)?;
+        if deployed.is_empty() {
+            issues.push(SchemaIssue::MissingTable {
+                table: schema.table,
+            });
+            continue;
+        }
+
+        for column in schema.columns {
+            let Some(found) = deployed.iter().find(|c| c.column_name == column.name) else {
+                issues.push(SchemaIssue::MissingColumn {
+                    table: schema.table,
+                    column: column.name,
+                });
+                continue;
+            };
+            if let Some(expected) = C::Backend::type_mismatch(column, &found.data_type) {
+                issues.push(SchemaIssue::TypeMismatch {
+                    table: schema.table,
+                    column: column.name,
+                    expected,
+                    found: found.data_type.clone(),
+                });
+            }
+            if found.nullable != column.nullable {
+                issues.push(SchemaIssue::NullabilityMismatch {
+                    table: schema.table,
+                    column: column.name,
+                    nullable: column.nullable,
+                });
+            }
+        }
+    }
+
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(SchemaError::Drift(issues))
+    }
+}
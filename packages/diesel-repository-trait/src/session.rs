@@ -0,0 +1,98 @@
+/// Settings applied with `set_config(name, value, true)` at the start of every transaction
+/// of a `with_context` view of a `#[repository(session_context)]`, e.g. for Postgres
+/// row-level security policies reading `current_setting('app.current_user')`.
+///
+/// The settings are local to the transaction, so they never leak to the next user of a
+/// pooled connection.
+///
+/// ```
+/// use diesel_repository_trait::SessionContext;
+///
+/// let context = SessionContext::new()
+///     .set("app.current_user", "alice")
+///     .set("app.tenant", "acme");
+/// assert_eq!(context.settings()[0], ("app.current_user".to_string(), "alice".to_string()));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionContext {
+    settings: Vec<(String, String)>,
+}
+
+impl SessionContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a setting, applied after the ones added before it. `role` switches the role
+    /// like `SET LOCAL ROLE`.
+    pub fn set(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.settings.push((name.into(), value.into()));
+        self
+    }
+
+    /// The settings in the order they are applied.
+    pub fn settings(&self) -> &[(String, String)] {
+        &self.settings
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.settings.is_empty()
+    }
+}
+
+#[cfg(feature = "postgres")]
+mod postgres {
+    use super::SessionContext;
+    use diesel::pg::Pg;
+    use diesel::result::{Error, QueryResult};
+    use diesel::sql_types::Text;
+
+    const SET_CONFIG: &str = "SELECT set_config($1, $2, true)";
+
+    impl SessionContext {
+        /// Applies the settings to the open transaction of `conn`, failing with
+        /// [`Error::NotInTransaction`] outside a transaction, where they would not last.
+        pub fn apply<C>(&self, conn: &mut C) -> QueryResult<()>
+        where
+            C: diesel::Connection<Backend = Pg>,
+        {
+            use diesel::connection::TransactionManager;
+            use diesel::RunQueryDsl;
+
+            let status = C::TransactionManager::transaction_manager_status_mut(conn);
+            if status.transaction_depth()?.is_none() {
+                return Err(Error::NotInTransaction);
+            }
+            for (name, value) in &self.settings {
+                diesel::sql_query(SET_CONFIG)
+                    .bind::<Text, _>(name)
+                    .bind::<Text, _>(value)
+                    .execute(conn)?;
+            }
+            Ok(())
+        }
+
+        /// Applies the settings to the open transaction of an async `conn`, failing with
+        /// [`Error::NotInTransaction`] outside a transaction.
+        #[cfg(feature = "async")]
+        pub async fn apply_async<C>(&self, conn: &mut C) -> QueryResult<()>
+        where
+            C: diesel_async::AsyncConnection<Backend = Pg>,
+        {
+            use diesel_async::{RunQueryDsl, TransactionManager};
+
+            let status = C::TransactionManager::transaction_manager_status_mut(conn);
+            if status.transaction_depth()?.is_none() {
+                return Err(Error::NotInTransaction);
+            }
+            for (name, value) in &self.settings {
+                diesel::sql_query(SET_CONFIG)
+                    .bind::<Text, _>(name)
+                    .bind::<Text, _>(value)
+                    .execute(conn)
+                    .await?;
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Row-change notifications for `#[repository(subscribe)]`.
+//!
+//! A trigger on the table publishes every insert, update and delete on a channel with
+//! `pg_notify`, as `INSERT:<id>`, `UPDATE:<id>` or `DELETE:<id>`. A listener on its own
+//! connection turns these notices into [`Change`]s, loading inserted and updated rows.
+
+/// A change of a row of a subscribed table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change<T, ID> {
+    /// The row was inserted; holds the row as loaded after the notice.
+    Insert(T),
+    /// The row was updated; holds the row as loaded after the notice.
+    Update(T),
+    /// The row with this ID was deleted.
+    Delete(ID),
+}
+
+/// The statements installing the trigger that notifies `channel` of the changes of `table`,
+/// whose primary key is the single column `id`. Running them again replaces the trigger.
+pub fn notify_trigger_sql(table: &str, id: &str, channel: &str) -> String {
+    format!(
+        "CREATE OR REPLACE FUNCTION {table}_notify_change() RETURNS trigger LANGUAGE plpgsql AS $$
+         BEGIN
+             IF TG_OP = 'DELETE' THEN
+                 PERFORM pg_notify('{channel}', TG_OP || ':' || OLD.{id}::text);
+             ELSE
+                 PERFORM pg_notify('{channel}', TG_OP || ':' || NEW.{id}::text);
+             END IF;
+             RETURN NULL;
+         END
+         $$;
+         DROP TRIGGER IF EXISTS {table}_notify_change ON {table};
+         CREATE TRIGGER {table}_notify_change AFTER INSERT OR UPDATE OR DELETE ON {table}
+             FOR EACH ROW EXECUTE FUNCTION {table}_notify_change();"
+    )
+}
+
+#[cfg(feature = "subscribe")]
+pub use self::listener::*;
+
+#[cfg(feature = "subscribe")]
+mod listener {
+    use super::Change;
+    use diesel::result::{DatabaseErrorKind, Error, QueryResult};
+    use diesel_async::{AsyncPgConnection, SimpleAsyncConnection};
+    use futures_util::future::BoxFuture;
+    use futures_util::stream::{self, BoxStream, StreamExt};
+    use std::str::FromStr;
+    use tokio::sync::mpsc;
+    use tokio_postgres::AsyncMessage;
+
+    /// The changes of a subscribed table, in the order they were committed.
+    pub type ChangeStream<T, ID> = BoxStream<'static, QueryResult<Change<T, ID>>>;
+
+    /// Loads an inserted or updated row on the listener's connection; `None` skips the
+    /// change, e.g. when the row was deleted in the meantime.
+    pub type LoadChanged<T, ID> =
+        for<'c> fn(&'c mut AsyncPgConnection, ID) -> BoxFuture<'c, QueryResult<Option<T>>>;
+
+    /// Installs the trigger of [`notify_trigger_sql`](super::notify_trigger_sql).
+    pub async fn install_notify_trigger<C>(
+        conn: &mut C,
+        table: &str,
+        id: &str,
+        channel: &str,
+    ) -> QueryResult<()>
+    where
+        C: SimpleAsyncConnection + Send,
+    {
+        conn.batch_execute(&super::notify_trigger_sql(table, id, channel))
+            .await
+    }
+
+    /// A dedicated connection listening on one channel.
+    ///
+    /// Pooled connections are handed to other tasks between queries, so they cannot hold a
+    /// `LISTEN`; the listener opens its own connection and keeps it for as long as it lives.
+    pub struct ChangeListener {
+        conn: AsyncPgConnection,
+        notices: mpsc::UnboundedReceiver<Result<String, String>>,
+    }
+
+    impl ChangeListener {
+        /// Connects to `database_url` and listens on `channel`.
+        pub async fn connect(database_url: &str, channel: &str) -> QueryResult<Self> {
+            let (client, mut connection) =
+                tokio_postgres::connect(database_url, tokio_postgres::NoTls)
+                    .await
+                    .map_err(database_error)?;
+
+            // Notifications arrive through the connection, which is driven until the client
+            // is dropped with the listener.
+            let (sender, notices) = mpsc::unbounded_channel();
+            tokio::spawn(async move {
+                let mut messages = stream::poll_fn(move |cx| connection.poll_message(cx));
+                while let Some(message) = messages.next().await {
+                    let notice = match message {
+                        Ok(AsyncMessage::Notification(notification)) => {
+                            Ok(notification.payload().to_string())
+                        }
+                        Ok(_) => continue,
+                        Err(err) => Err(err.to_string()),
+                    };
+                    let failed = notice.is_err();
+                    if sender.send(notice).is_err() || failed {
+                        break;
+                    }
+                }
+            });
+
+            client
+                .batch_execute(&format!("LISTEN \"{}\"", channel.replace('"', "\"\"")))
+                .await
+                .map_err(database_error)?;
+            let conn = AsyncPgConnection::try_from(client)
+                .await
+                .map_err(database_error)?;
+            Ok(Self { conn, notices })
+        }
+
+        /// The listener's connection, e.g. to install the trigger.
+        pub fn connection(&mut self) -> &mut AsyncPgConnection {
+            &mut self.conn
+        }
+
+        /// Streams the changes, loading inserted and updated rows with `load`.
+        ///
+        /// The stream ends with an error if the connection is lost; notices sent while no
+        /// listener was connected are not replayed.
+        pub fn changes<T, ID>(self, load: LoadChanged<T, ID>) -> ChangeStream<T, ID>
+        where
+            T: Send + 'static,
+            ID: FromStr + Send + 'static,
+        {
+            stream::unfold(self, move |mut listener| async move {
+                loop {
+                    let change = match listener.notices.recv().await? {
+                        Ok(payload) => listener.change(&payload, load).await,
+                        Err(err) => Err(database_error(err)),
+                    };
+                    match change {
+                        Ok(None) => continue,
+                        Ok(Some(change)) => return Some((Ok(change), listener)),
+                        Err(err) => return Some((Err(err), listener)),
+                    }
+                }
+            })
+            .boxed()
+        }
+
+        async fn change<T, ID>(
+            &mut self,
+            payload: &str,
+            load: LoadChanged<T, ID>,
+        ) -> QueryResult<Option<Change<T, ID>>>
+        where
+            ID: FromStr,
+        {
+            let malformed = || {
+                Error::DeserializationError(format!("malformed change notice `{}`", payload).into())
+            };
+            let (operation, id) = payload.split_once(':').ok_or_else(malformed)?;
+            let id = id.parse::<ID>().map_err(|_| malformed())?;
+            match operation {
+                "INSERT" => Ok(load(&mut self.conn, id).await?.map(Change::Insert)),
+                "UPDATE" => Ok(load(&mut self.conn, id).await?.map(Change::Update)),
+                "DELETE" => Ok(Some(Change::Delete(id))),
+                _ => Err(malformed()),
+            }
+        }
+    }
+
+    fn database_error(err: impl ToString) -> Error {
+        Error::DatabaseError(DatabaseErrorKind::Unknown, Box::new(err.to_string()))
+    }
+}
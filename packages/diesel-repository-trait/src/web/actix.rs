@@ -0,0 +1,36 @@
+use super::{PageParams, PageParamsError, PageSpec};
+use crate::Paged;
+use actix_web::body::BoxBody;
+use actix_web::dev::Payload;
+use actix_web::http::header::LINK;
+use actix_web::http::StatusCode;
+use actix_web::{FromRequest, HttpRequest, HttpResponse, Responder, ResponseError};
+use std::future::{ready, Ready};
+
+impl<S: PageSpec> FromRequest for PageParams<S> {
+    type Error = PageParamsError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(PageParams::from_query(req.query_string()))
+    }
+}
+
+impl ResponseError for PageParamsError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+}
+
+/// Responds with the page as JSON, its `X-Total-Count` and a `Link` header to the pages
+/// of the same request.
+impl<T: serde::Serialize> Responder for Paged<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok()
+            .insert_header(("X-Total-Count", self.total_count))
+            .insert_header((LINK, self.link_header(req.path(), req.query_string())))
+            .json(self)
+    }
+}
@@ -0,0 +1,48 @@
+use super::{PageParams, PageParamsError, PageSpec};
+use crate::Paged;
+use axum::extract::FromRequestParts;
+use axum::http::header::{HeaderName, HeaderValue, LINK};
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+
+impl<S, St> FromRequestParts<St> for PageParams<S>
+where
+    S: PageSpec,
+    St: Send + Sync,
+{
+    type Rejection = PageParamsError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &St) -> Result<Self, Self::Rejection> {
+        PageParams::from_query(parts.uri.query().unwrap_or_default())
+    }
+}
+
+impl IntoResponse for PageParamsError {
+    fn into_response(self) -> Response {
+        (StatusCode::BAD_REQUEST, self.to_string()).into_response()
+    }
+}
+
+/// Responds with the page as JSON, its `X-Total-Count` and a `Link` header.
+///
+/// The response does not know the request, so the links are relative to its path and only
+/// carry `page` and `per_page`; use [`Paged::link_header`] to keep other parameters.
+impl<T: serde::Serialize> IntoResponse for Paged<T> {
+    fn into_response(self) -> Response {
+        let link = self.link_header("", "");
+        let total_count = self.total_count;
+        let mut response = Json(self).into_response();
+
+        let headers = response.headers_mut();
+        headers.insert(
+            HeaderName::from_static("x-total-count"),
+            HeaderValue::from(total_count),
+        );
+        if let Ok(link) = HeaderValue::from_str(&link) {
+            headers.insert(LINK, link);
+        }
+        response
+    }
+}
@@ -0,0 +1,241 @@
+//! Paging parameters of HTTP requests and paging headers of HTTP responses.
+//!
+//! The parsing is framework-agnostic; the `axum` and `actix` features add the extractors
+//! and responses on top of it.
+
+use crate::{PageRequest, Paged};
+use std::fmt;
+use std::marker::PhantomData;
+
+#[cfg(feature = "actix")]
+mod actix;
+#[cfg(feature = "axum")]
+mod axum;
+
+/// Limits and sort keys accepted by a [`PageParams`] extractor.
+///
+/// ```
+/// use diesel_repository_trait::PageSpec;
+///
+/// struct AccountPages;
+///
+/// impl PageSpec for AccountPages {
+///     const SORT_KEYS: &'static [&'static str] = &["name", "created_at"];
+///     const MAX_PER_PAGE: i64 = 50;
+/// }
+/// ```
+pub trait PageSpec {
+    /// The keys `sort` may name; empty rejects any `sort` parameter.
+    const SORT_KEYS: &'static [&'static str] = &[];
+    /// The largest `per_page` accepted.
+    const MAX_PER_PAGE: i64 = 100;
+    /// The `per_page` used when the request does not set one.
+    const DEFAULT_PER_PAGE: i64 = 20;
+}
+
+/// The default [`PageSpec`]: no sorting, at most 100 records per page, 20 by default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Unsorted;
+
+impl PageSpec for Unsorted {}
+
+/// The direction of a [`Sort`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// One `sort=key[,asc|desc]` parameter, with `key` taken from [`PageSpec::SORT_KEYS`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sort {
+    pub key: &'static str,
+    pub direction: SortDirection,
+}
+
+/// Paging parameters of a request, e.g. `?page=2&per_page=50&sort=name,desc`.
+///
+/// `page` defaults to 1 and `per_page` to [`PageSpec::DEFAULT_PER_PAGE`]; `sort` may be
+/// repeated and is applied in order. Other query parameters are ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageParams<S = Unsorted> {
+    pub page: i64,
+    pub per_page: i64,
+    pub sort: Vec<Sort>,
+    _spec: PhantomData<fn() -> S>,
+}
+
+impl<S: PageSpec> PageParams<S> {
+    /// Parses and validates the parameters of a URL query string.
+    pub fn from_query(query: &str) -> Result<Self, PageParamsError> {
+        let mut page = 1;
+        let mut per_page = S::DEFAULT_PER_PAGE;
+        let mut sort = Vec::new();
+
+        for (name, value) in query_pairs(query) {
+            match name.as_str() {
+                "page" => page = parse_number("page", &value)?,
+                "per_page" => per_page = parse_number("per_page", &value)?,
+                "sort" => sort.push(parse_sort::<S>(&value)?),
+                _ => {}
+            }
+        }
+
+        if page < 1 {
+            return Err(PageParamsError::InvalidValue {
+                param: "page",
+                value: page.to_string(),
+            });
+        }
+        if per_page < 1 {
+            return Err(PageParamsError::InvalidValue {
+                param: "per_page",
+                value: per_page.to_string(),
+            });
+        }
+        if per_page > S::MAX_PER_PAGE {
+            return Err(PageParamsError::PerPageTooLarge {
+                max: S::MAX_PER_PAGE,
+            });
+        }
+
+        Ok(Self {
+            page,
+            per_page,
+            sort,
+            _spec: PhantomData,
+        })
+    }
+
+    /// The requested page, for the `page` and `per_page` arguments of the paged traits.
+    pub fn request(&self) -> PageRequest {
+        PageRequest::new(self.page, self.per_page)
+    }
+}
+
+/// Why the paging parameters of a request were rejected; a `400 Bad Request`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PageParamsError {
+    /// `page` or `per_page` is not a positive integer.
+    InvalidValue { param: &'static str, value: String },
+    /// `per_page` exceeds [`PageSpec::MAX_PER_PAGE`].
+    PerPageTooLarge { max: i64 },
+    /// `sort` names a key missing from [`PageSpec::SORT_KEYS`].
+    UnknownSortKey(String),
+    /// `sort` has a direction other than `asc` or `desc`.
+    InvalidSortDirection(String),
+}
+
+impl fmt::Display for PageParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PageParamsError::InvalidValue { param, value } => {
+                write!(f, "`{}` must be a positive integer, got `{}`", param, value)
+            }
+            PageParamsError::PerPageTooLarge { max } => {
+                write!(f, "`per_page` must be at most {}", max)
+            }
+            PageParamsError::UnknownSortKey(key) => write!(f, "cannot sort by `{}`", key),
+            PageParamsError::InvalidSortDirection(direction) => write!(
+                f,
+                "sort direction must be `asc` or `desc`, got `{}`",
+                direction
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PageParamsError {}
+
+impl<T> Paged<T> {
+    /// The `Link` header of this page: `first`, `prev`, `next` and `last` links to `path`
+    /// with `query`, whose `page` and `per_page` are replaced.
+    pub fn link_header(&self, path: &str, query: &str) -> String {
+        let kept: Vec<&str> = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .filter(|pair| {
+                let name = pair.split('=').next().unwrap_or_default();
+                name != "page" && name != "per_page"
+            })
+            .collect();
+        let link = |page: i64, rel: &str| {
+            let mut pairs = kept.clone();
+            let paging = format!("page={}&per_page={}", page, self.per_page);
+            pairs.push(&paging);
+            format!("<{}?{}>; rel=\"{}\"", path, pairs.join("&"), rel)
+        };
+
+        let last = self.total_pages().max(1);
+        let mut links = vec![link(1, "first")];
+        if self.has_previous() {
+            links.push(link((self.page - 1).min(last), "prev"));
+        }
+        if self.has_next() {
+            links.push(link(self.page + 1, "next"));
+        }
+        links.push(link(last, "last"));
+        links.join(", ")
+    }
+}
+
+/// The decoded `name=value` pairs of a query string.
+fn query_pairs(query: &str) -> impl Iterator<Item = (String, String)> + '_ {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(name), decode(value))
+        })
+}
+
+/// Decodes `+` and `%XX` escapes; invalid escapes are kept as written.
+fn decode(raw: &str) -> String {
+    let bytes = raw.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = |b: u8| (b as char).to_digit(16);
+                match (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                    (Some(high), Some(low)) => {
+                        decoded.push((high * 16 + low) as u8);
+                        i += 2;
+                    }
+                    _ => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn parse_number(param: &'static str, value: &str) -> Result<i64, PageParamsError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| PageParamsError::InvalidValue {
+            param,
+            value: value.to_string(),
+        })
+}
+
+fn parse_sort<S: PageSpec>(value: &str) -> Result<Sort, PageParamsError> {
+    let (key, direction) = value.split_once(',').unwrap_or((value, "asc"));
+    let key = S::SORT_KEYS
+        .iter()
+        .find(|allowed| **allowed == key.trim())
+        .ok_or_else(|| PageParamsError::UnknownSortKey(key.to_string()))?;
+    let direction = match direction.trim().to_ascii_lowercase().as_str() {
+        "asc" => SortDirection::Asc,
+        "desc" => SortDirection::Desc,
+        _ => return Err(PageParamsError::InvalidSortDirection(direction.to_string())),
+    };
+    Ok(Sort { key, direction })
+}
@@ -1,7 +1,3 @@
 pub use diesel_repository_trait::*;
 
-#[cfg(not(feature = "async"))]
 pub use diesel_repository_macro::*;
-
-#[cfg(feature = "async")]
-pub use diesel_repository_macro_async::*;
\ No newline at end of file
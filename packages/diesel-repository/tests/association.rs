@@ -0,0 +1,173 @@
+//! Loads the associations of sync and async repositories on SQLite in memory: `has_many`
+//! through the related entity's `BelongsTo` impl, `belongs_to` on the foreign key.
+#![cfg(feature = "sqlite")]
+
+use diesel::prelude::*;
+use diesel_repository::Repo;
+
+diesel::table! {
+    association_test_authors {
+        id -> Integer,
+        name -> Text,
+    }
+}
+
+diesel::table! {
+    association_test_books {
+        id -> Integer,
+        author_id -> Integer,
+        title -> Text,
+    }
+}
+
+const SCHEMA: &str =
+    "CREATE TABLE association_test_authors (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+     CREATE TABLE association_test_books (
+         id INTEGER PRIMARY KEY, author_id INTEGER NOT NULL, title TEXT NOT NULL
+     );
+     INSERT INTO association_test_authors VALUES (1, 'le guin'), (2, 'pratchett'), (3, 'nobody');
+     INSERT INTO association_test_books VALUES
+         (1, 2, 'mort'), (2, 1, 'the dispossessed'), (3, 2, 'guards! guards!');";
+
+fn titles<'a>(books: impl IntoIterator<Item = &'a String>) -> Vec<&'a str> {
+    books.into_iter().map(String::as_str).collect()
+}
+
+mod sync {
+    use super::*;
+    use diesel::connection::SimpleConnection;
+    use std::sync::{Arc, Mutex};
+
+    pub mod db {
+        pub type DbPool = std::sync::Mutex<diesel::SqliteConnection>;
+    }
+
+    #[derive(Queryable, Identifiable, Repo, Debug, PartialEq)]
+    #[diesel(table_name = association_test_authors, check_for_backend(diesel::sqlite::Sqlite))]
+    #[repository(pool = db::DbPool, has_many(books = BookRepo))]
+    pub struct Author {
+        pub id: i32,
+        pub name: String,
+    }
+
+    #[derive(Queryable, Identifiable, Associations, Repo, Debug, PartialEq)]
+    #[diesel(
+        table_name = association_test_books,
+        belongs_to(Author),
+        check_for_backend(diesel::sqlite::Sqlite)
+    )]
+    #[repository(pool = db::DbPool, belongs_to(author = AuthorRepo))]
+    pub struct Book {
+        pub id: i32,
+        pub author_id: i32,
+        pub title: String,
+    }
+
+    #[test]
+    fn associations_load_without_n_plus_one() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.batch_execute(SCHEMA).unwrap();
+        let pool = Arc::new(Mutex::new(conn));
+        let authors = AuthorRepo::new(pool.clone());
+        let books = BookRepo::new(pool);
+
+        let (author, written) = authors.find_with_books(2).unwrap();
+        assert_eq!(author.name, "pratchett");
+        assert_eq!(
+            titles(written.iter().map(|book| &book.title)),
+            ["mort", "guards! guards!"]
+        );
+        assert!(matches!(
+            authors.find_with_books(9),
+            Err(diesel::result::Error::NotFound)
+        ));
+
+        let parents = [
+            Author {
+                id: 3,
+                name: "nobody".into(),
+            },
+            Author {
+                id: 1,
+                name: "le guin".into(),
+            },
+        ];
+        let grouped = authors.load_books_for(&parents).unwrap();
+        assert_eq!(grouped.len(), 2);
+        assert!(grouped[0].is_empty());
+        assert_eq!(
+            titles(grouped[1].iter().map(|book| &book.title)),
+            ["the dispossessed"]
+        );
+        assert!(authors.load_books_for(&[]).unwrap().is_empty());
+
+        let by_author = books.find_by_author_id(2).unwrap();
+        assert_eq!(
+            by_author.iter().map(|book| book.id).collect::<Vec<_>>(),
+            [1, 3]
+        );
+        assert!(by_author.iter().all(|book| book.author_id == 2));
+    }
+}
+
+#[cfg(feature = "async")]
+mod asynchronous {
+    use super::*;
+    use diesel::connection::SimpleConnection;
+    use diesel_async::sync_connection_wrapper::SyncConnectionWrapper;
+    use std::sync::Arc;
+
+    pub mod db {
+        pub type DbPool = tokio::sync::Mutex<
+            diesel_async::sync_connection_wrapper::SyncConnectionWrapper<diesel::SqliteConnection>,
+        >;
+    }
+
+    #[derive(Queryable, Identifiable, Repo, Debug, PartialEq)]
+    #[diesel(table_name = association_test_authors, check_for_backend(diesel::sqlite::Sqlite))]
+    #[repository(mode = "async", pool = db::DbPool, has_many(books = BookRepo))]
+    pub struct Author {
+        pub id: i32,
+        pub name: String,
+    }
+
+    #[derive(Queryable, Identifiable, Associations, Repo, Debug, PartialEq)]
+    #[diesel(
+        table_name = association_test_books,
+        belongs_to(Author),
+        check_for_backend(diesel::sqlite::Sqlite)
+    )]
+    #[repository(mode = "async", pool = db::DbPool, belongs_to(author = AuthorRepo))]
+    pub struct Book {
+        pub id: i32,
+        pub author_id: i32,
+        pub title: String,
+    }
+
+    #[tokio::test]
+    async fn associations_load_without_n_plus_one() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.batch_execute(SCHEMA).unwrap();
+        let pool = Arc::new(tokio::sync::Mutex::new(SyncConnectionWrapper::new(conn)));
+        let authors = AuthorRepo::new(pool.clone());
+        let books = BookRepo::new(pool);
+
+        let (author, written) = authors.find_with_books(1).await.unwrap();
+        assert_eq!(author.name, "le guin");
+        assert_eq!(
+            titles(written.iter().map(|book| &book.title)),
+            ["the dispossessed"]
+        );
+
+        let (parents, _) = authors.find_with_books(2).await.unwrap();
+        let grouped = authors.load_books_for(&[parents]).await.unwrap();
+        assert_eq!(
+            titles(grouped[0].iter().map(|book| &book.title)),
+            ["mort", "guards! guards!"]
+        );
+
+        let by_author = books.find_by_author_id(1).await.unwrap();
+        assert_eq!(by_author.len(), 1);
+        assert_eq!(by_author[0].author_id, 1);
+    }
+}
@@ -0,0 +1,11 @@
+#[test]
+fn compile_fail() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+    // With `async`, rustc tells the blocking traits from the async ones by their full path.
+    #[cfg(not(feature = "async"))]
+    t.compile_fail("tests/ui/sync/*.rs");
+    // Generated methods that run queries need a connection type, i.e. a backend feature.
+    #[cfg(feature = "sqlite")]
+    t.pass("tests/pass/*.rs");
+}
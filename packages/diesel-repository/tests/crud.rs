@@ -0,0 +1,265 @@
+//! Runs the generated find and batch methods of sync and async repositories on SQLite in
+//! memory, through the `CrudRepository` and `BatchRepository` supertraits.
+#![cfg(feature = "sqlite")]
+
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel_repository::Repo;
+
+diesel::table! {
+    crud_test_accounts {
+        id -> Integer,
+        name -> Text,
+    }
+}
+
+const SCHEMA: &str = "CREATE TABLE crud_test_accounts (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+     INSERT INTO crud_test_accounts VALUES (1, 'alice'), (2, 'bob'), (3, 'alice');";
+
+mod sync {
+    use super::*;
+    use diesel::connection::SimpleConnection;
+    use diesel_repository::{
+        BatchRepository, Count, CrudRepository, Delete, FindAll, FindById, FindByIdBatch,
+        FindByQuery, FindOneByQuery, Patch, Replace, Save, SaveBatch, UpdateBatch,
+    };
+    use std::sync::{Arc, Mutex};
+
+    pub mod db {
+        pub type DbPool = std::sync::Mutex<diesel::SqliteConnection>;
+    }
+
+    #[derive(
+        Queryable, Insertable, Identifiable, AsChangeset, Repo, Patch, Debug, Clone, PartialEq,
+    )]
+    #[diesel(table_name = crud_test_accounts, check_for_backend(diesel::sqlite::Sqlite))]
+    #[repository(pool = db::DbPool)]
+    #[crud_repo(
+        find_one,
+        find_one_query,
+        find_query,
+        find_all,
+        save,
+        update,
+        patch,
+        replace,
+        delete,
+        count,
+        exists
+    )]
+    #[batch_repo(find, save, update, delete, exists)]
+    pub struct Account {
+        pub id: i32,
+        pub name: String,
+    }
+
+    fn account(id: i32, name: &str) -> Account {
+        Account {
+            id,
+            name: name.into(),
+        }
+    }
+
+    fn repository() -> AccountRepo {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.batch_execute(SCHEMA).unwrap();
+        AccountRepo::new(Arc::new(Mutex::new(conn)))
+    }
+
+    /// Renames an account through the `CrudRepository` supertrait alone.
+    fn rename<R>(repo: &R, id: i32, to: &str) -> Result<Account, Error>
+    where
+        R: CrudRepository<Account, i32, Account, Account>,
+    {
+        let mut account = repo.find_by_id(id)?;
+        account.name = to.into();
+        repo.update(account)
+    }
+
+    #[test]
+    fn finds_run_on_the_table() {
+        use crud_test_accounts::{id, name};
+
+        let repo = repository();
+        assert_eq!(repo.find_by_id(2).unwrap(), account(2, "bob"));
+        assert!(matches!(repo.find_by_id(9), Err(Error::NotFound)));
+        assert_eq!(repo.find_all().unwrap().len(), 3);
+
+        let alices = repo
+            .find_by_query(&|q| q.filter(name.eq("alice")).order(id.desc()))
+            .unwrap();
+        assert_eq!(alices, [account(3, "alice"), account(1, "alice")]);
+        assert_eq!(
+            repo.find_one_by_query(&|q| q.filter(name.eq("alice")).order(id))
+                .unwrap(),
+            account(1, "alice")
+        );
+        assert!(matches!(
+            repo.find_one_by_query(&|q| q.filter(name.eq("carol"))),
+            Err(Error::NotFound)
+        ));
+    }
+
+    #[test]
+    fn writes_return_the_stored_record() {
+        let repo = repository();
+        assert_eq!(rename(&repo, 2, "bobby").unwrap(), account(2, "bobby"));
+        assert_eq!(repo.save(account(4, "carol")).unwrap(), account(4, "carol"));
+        assert_eq!(
+            repo.replace(account(4, "carla")).unwrap(),
+            account(4, "carla")
+        );
+        repo.delete(4).unwrap();
+        assert!(matches!(repo.delete(4), Err(Error::NotFound)));
+        assert_eq!(repo.count(&|q| q).unwrap(), 3);
+    }
+
+    #[test]
+    fn patches_write_only_their_columns() {
+        let repo = repository();
+        let patch = AccountPatch {
+            name: Some("bobby".into()),
+        };
+        assert_eq!(repo.patch(2, patch).unwrap(), account(2, "bobby"));
+
+        // A patch without changes writes nothing and returns the current row.
+        assert_eq!(
+            repo.patch(2, AccountPatch::default()).unwrap(),
+            account(2, "bobby")
+        );
+        assert!(matches!(
+            repo.patch(9, AccountPatch::default()),
+            Err(Error::NotFound)
+        ));
+    }
+
+    #[test]
+    fn batches_run_in_one_transaction() {
+        fn batch<R>(repo: &R) -> Result<Vec<Account>, Error>
+        where
+            R: BatchRepository<Account, i32, Account, Account>,
+        {
+            repo.save_batch(&[account(4, "carol"), account(5, "dave")])?;
+            repo.update_batch(&[account(1, "alicia"), account(4, "carla")])?;
+            repo.delete_batch(&[2, 5])?;
+            repo.find_by_id_batch(&[1, 2, 3, 4, 5])
+        }
+
+        let repo = repository();
+        let mut accounts = batch(&repo).unwrap();
+        accounts.sort_by_key(|account| account.id);
+        assert_eq!(
+            accounts,
+            [
+                account(1, "alicia"),
+                account(3, "alice"),
+                account(4, "carla")
+            ]
+        );
+        assert!(repo.find_by_id_batch(&[]).unwrap().is_empty());
+        assert!(repo.save_batch(&[]).unwrap().is_empty());
+
+        // The second record already exists, so the whole batch is rolled back.
+        assert!(repo
+            .update_batch(&[account(1, "alice"), account(9, "nobody")])
+            .is_err());
+        assert_eq!(repo.find_by_id(1).unwrap().name, "alicia");
+    }
+}
+
+#[cfg(feature = "async")]
+mod asynchronous {
+    use super::*;
+    use diesel::connection::SimpleConnection;
+    use diesel_async::sync_connection_wrapper::SyncConnectionWrapper;
+    use diesel_repository::asynchronous::{
+        CrudRepository, DeleteBatch, FindAll, FindById, FindByIdBatch, FindByQuery, Patch as _,
+        SaveBatch, UpdateBatch,
+    };
+    use std::sync::Arc;
+
+    pub mod db {
+        pub type DbPool = tokio::sync::Mutex<
+            diesel_async::sync_connection_wrapper::SyncConnectionWrapper<diesel::SqliteConnection>,
+        >;
+    }
+
+    #[derive(
+        Queryable,
+        Insertable,
+        Identifiable,
+        AsChangeset,
+        Repo,
+        diesel_repository::Patch,
+        Debug,
+        Clone,
+        PartialEq,
+    )]
+    #[diesel(table_name = crud_test_accounts, check_for_backend(diesel::sqlite::Sqlite))]
+    #[repository(mode = "async", pool = db::DbPool)]
+    #[crud_repo(
+        find_one, find_query, find_all, save, update, patch, delete, count, exists
+    )]
+    #[batch_repo(find, save, update, delete, exists)]
+    pub struct Account {
+        pub id: i32,
+        pub name: String,
+    }
+
+    fn account(id: i32, name: &str) -> Account {
+        Account {
+            id,
+            name: name.into(),
+        }
+    }
+
+    fn repository() -> AccountRepo {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.batch_execute(SCHEMA).unwrap();
+        AccountRepo::new(Arc::new(tokio::sync::Mutex::new(
+            SyncConnectionWrapper::new(conn),
+        )))
+    }
+
+    async fn rename<R>(repo: &R, id: i32, to: &str) -> Result<Account, Error>
+    where
+        R: CrudRepository<Account, i32, Account, Account>,
+    {
+        let mut account = repo.find_by_id(id).await?;
+        account.name = to.into();
+        repo.update(account).await
+    }
+
+    #[tokio::test]
+    async fn finds_writes_and_batches_run_on_the_table() {
+        use crud_test_accounts::name;
+
+        let repo = repository();
+        assert_eq!(repo.find_by_id(2).await.unwrap(), account(2, "bob"));
+        assert!(matches!(repo.find_by_id(9).await, Err(Error::NotFound)));
+        let alices = repo
+            .find_by_query(&|q| q.filter(name.eq("alice")))
+            .await
+            .unwrap();
+        assert_eq!(alices.len(), 2);
+        assert_eq!(
+            rename(&repo, 2, "bobby").await.unwrap(),
+            account(2, "bobby")
+        );
+        // A patch without changes writes nothing and returns the current row.
+        assert_eq!(
+            repo.patch(2, AccountPatch::default()).await.unwrap(),
+            account(2, "bobby")
+        );
+
+        repo.save_batch(&[account(4, "carol"), account(5, "dave")])
+            .await
+            .unwrap();
+        repo.update_batch(&[account(4, "carla")]).await.unwrap();
+        repo.delete_batch(&[1, 5]).await.unwrap();
+        let mut accounts = repo.find_by_id_batch(&[1, 4, 5]).await.unwrap();
+        accounts.sort_by_key(|account| account.id);
+        assert_eq!(accounts, [account(4, "carla")]);
+        assert_eq!(repo.find_all().await.unwrap().len(), 3);
+    }
+}
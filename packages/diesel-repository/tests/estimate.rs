@@ -0,0 +1,147 @@
+//! Pages with `count_strategy = "estimate"` on the Postgres database at `DATABASE_URL`, taking
+//! the totals from the planner's statistics. Run with:
+//!
+//! ```sh
+//! DATABASE_URL=postgres://... cargo test -p diesel-repository --features postgres,async --test estimate -- --ignored
+//! ```
+#![cfg(feature = "postgres")]
+
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+use diesel_repository::Repo;
+use std::ops::RangeInclusive;
+
+diesel::table! {
+    estimate_test_accounts {
+        id -> Integer,
+        name -> Text,
+    }
+}
+
+diesel::table! {
+    estimate_test_async_accounts {
+        id -> Integer,
+        name -> Text,
+    }
+}
+
+/// The planner's guesses for `id <= 30` stay close to the exact count after `ANALYZE`.
+const ABOUT_30: RangeInclusive<i64> = 20..=40;
+
+fn connect() -> PgConnection {
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL should name a Postgres database");
+    PgConnection::establish(&url).expect("DATABASE_URL should accept connections")
+}
+
+/// Creates the table with 100 rows and analyzes it, so that its statistics are exact.
+fn create_table(conn: &mut PgConnection, table: &str) {
+    conn.batch_execute(&format!(
+        "DROP TABLE IF EXISTS {table};
+         CREATE TABLE {table} (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+         INSERT INTO {table} SELECT n, 'account ' || n FROM generate_series(1, 100) AS n;
+         ANALYZE {table};"
+    ))
+    .unwrap();
+}
+
+mod sync {
+    use super::*;
+    use diesel_repository::{FindAllPaged, FindByQueryPaged};
+    use std::sync::{Arc, Mutex};
+
+    pub mod db {
+        pub type DbPool = std::sync::Mutex<diesel::PgConnection>;
+    }
+
+    #[derive(Queryable, Selectable, Debug)]
+    #[diesel(table_name = estimate_test_accounts, check_for_backend(diesel::pg::Pg))]
+    pub struct AccountName {
+        pub name: String,
+    }
+
+    #[derive(Queryable, Repo, Debug)]
+    #[diesel(table_name = estimate_test_accounts, check_for_backend(diesel::pg::Pg))]
+    #[repository(pool = db::DbPool, projections(AccountName))]
+    #[paging_repo(find_all, find_query, count_strategy = "estimate")]
+    pub struct Account {
+        pub id: i32,
+        pub name: String,
+    }
+
+    #[test]
+    #[ignore = "needs a Postgres database at DATABASE_URL"]
+    fn pages_take_their_total_from_the_statistics() {
+        use estimate_test_accounts::id;
+
+        let mut conn = connect();
+        create_table(&mut conn, "estimate_test_accounts");
+        let repo = AccountRepo::new(Arc::new(Mutex::new(conn)));
+
+        let page = repo.find_all_paged(2, 10).unwrap();
+        assert_eq!(page.total_count, 100);
+        let first = &page.items[0];
+        assert_eq!((first.id, first.name.as_str()), (11, "account 11"));
+
+        let page = repo
+            .find_by_query_paged(&|q| q.filter(id.le(30)).order(id), 1, 25)
+            .unwrap();
+        assert!(ABOUT_30.contains(&page.total_count), "{}", page.total_count);
+        assert_eq!(page.items.len(), 25);
+
+        let names = repo.find_all_as_paged::<AccountName>(1, 2).unwrap();
+        assert_eq!(names.total_count, 100);
+        assert_eq!(names.items[1].name, "account 2");
+
+        let mut conn = repo.pool.lock().unwrap();
+        let raw = diesel_repository::estimate_query_count(
+            &mut *conn,
+            diesel::sql_query("SELECT * FROM estimate_test_accounts WHERE id <= 30"),
+        )
+        .unwrap();
+        assert!(ABOUT_30.contains(&raw), "{}", raw);
+    }
+}
+
+#[cfg(feature = "async")]
+mod asynchronous {
+    use super::*;
+    use diesel_async::{AsyncConnection, AsyncPgConnection};
+    use diesel_repository::asynchronous::{FindAllPaged, FindByQueryPaged};
+    use std::sync::Arc;
+
+    pub mod db {
+        pub type DbPool = tokio::sync::Mutex<diesel_async::AsyncPgConnection>;
+    }
+
+    #[derive(Queryable, Repo, Debug)]
+    #[diesel(table_name = estimate_test_async_accounts, check_for_backend(diesel::pg::Pg))]
+    #[repository(mode = "async", pool = db::DbPool)]
+    #[paging_repo(find_all, find_query, count_strategy = "estimate")]
+    pub struct Account {
+        pub id: i32,
+        pub name: String,
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres database at DATABASE_URL"]
+    async fn pages_take_their_total_from_the_statistics() {
+        use estimate_test_async_accounts::id;
+
+        create_table(&mut connect(), "estimate_test_async_accounts");
+        let url = std::env::var("DATABASE_URL").unwrap();
+        let conn = AsyncPgConnection::establish(&url).await.unwrap();
+        let repo = AccountRepo::new(Arc::new(tokio::sync::Mutex::new(conn)));
+
+        let page = repo.find_all_paged(10, 10).await.unwrap();
+        assert_eq!(page.total_count, 100);
+        let last = page.items.last().unwrap();
+        assert_eq!((last.id, last.name.as_str()), (100, "account 100"));
+
+        let page = repo
+            .find_by_query_paged(&|q| q.filter(id.le(30)), 1, 10)
+            .await
+            .unwrap();
+        assert!(ABOUT_30.contains(&page.total_count), "{}", page.total_count);
+        assert!(page.items.iter().all(|account| account.id <= 30));
+    }
+}
@@ -0,0 +1,255 @@
+//! Records the history of writes into a history table of the Postgres database at
+//! `DATABASE_URL` and reads it back with `history_of` and `as_of`. Run with:
+//!
+//! ```sh
+//! DATABASE_URL=postgres://... cargo test -p diesel-repository --features postgres,async --test history -- --ignored
+//! ```
+#![cfg(feature = "postgres")]
+
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+use diesel_repository::{HistoryActor, HistoryEntry, Operation, Patch, Repo};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+diesel::table! {
+    history_test_accounts {
+        id -> Integer,
+        name -> Text,
+    }
+}
+
+diesel::table! {
+    history_test_account_history (history_id) {
+        history_id -> BigInt,
+        id -> Integer,
+        name -> Text,
+        operation -> Text,
+        actor -> Nullable<Text>,
+        changed_at -> Timestamp,
+    }
+}
+
+pub mod db {
+    pub type DbPool = std::sync::Mutex<diesel::PgConnection>;
+}
+
+#[derive(
+    Queryable, Insertable, Identifiable, AsChangeset, Repo, Patch, Debug, Clone, PartialEq,
+)]
+#[diesel(table_name = history_test_accounts, check_for_backend(diesel::pg::Pg))]
+#[repository(
+    pool = db::DbPool,
+    history = history_test_account_history,
+    fields(actor: String)
+)]
+#[crud_repo(find_one, save, update, patch, replace, delete)]
+#[batch_repo(save, update, delete)]
+pub struct Account {
+    pub id: i32,
+    pub name: String,
+}
+
+impl HistoryActor for AccountRepo {
+    fn current_actor(&self) -> Option<String> {
+        Some(self.actor.clone())
+    }
+}
+
+fn account(id: i32, name: &str) -> Account {
+    Account {
+        id,
+        name: name.into(),
+    }
+}
+
+fn connect() -> PgConnection {
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL should name a Postgres database");
+    PgConnection::establish(&url).expect("DATABASE_URL should accept connections")
+}
+
+fn create_tables(conn: &mut PgConnection, table: &str, history: &str) {
+    conn.batch_execute(&format!(
+        "DROP TABLE IF EXISTS {table}, {history};
+         CREATE TABLE {table} (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+         CREATE TABLE {history} (
+             history_id BIGSERIAL PRIMARY KEY,
+             id INTEGER NOT NULL,
+             name TEXT NOT NULL,
+             operation TEXT NOT NULL,
+             actor TEXT,
+             changed_at TIMESTAMP NOT NULL
+         );"
+    ))
+    .unwrap();
+}
+
+/// A time strictly between the previous write and the next.
+fn instant() -> SystemTime {
+    std::thread::sleep(Duration::from_millis(5));
+    let now = SystemTime::now();
+    std::thread::sleep(Duration::from_millis(5));
+    now
+}
+
+fn changes(entries: &[HistoryEntry<Account>]) -> Vec<(Operation, &str)> {
+    entries
+        .iter()
+        .map(|entry| (entry.operation, entry.record.name.as_str()))
+        .collect()
+}
+
+#[test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+fn writes_record_the_previous_row() {
+    use diesel_repository::{
+        Delete, DeleteBatch, History, Replace, Save, SaveBatch, Update, UpdateBatch,
+    };
+
+    create_tables(
+        &mut connect(),
+        "history_test_accounts",
+        "history_test_account_history",
+    );
+    let repo = AccountRepo::new(Arc::new(Mutex::new(connect())), "alice".into());
+
+    repo.save(account(1, "a")).unwrap();
+    let before_update = instant();
+    repo.update(account(1, "b")).unwrap();
+    let before_patch = instant();
+    let patch = AccountPatch {
+        name: Some("c".into()),
+    };
+    repo.patch(1, patch).unwrap();
+    let before_replace = instant();
+    repo.replace(account(1, "d")).unwrap();
+    let before_delete = instant();
+    repo.delete(1).unwrap();
+    let after_delete = instant();
+
+    let history = repo.history_of(1).unwrap();
+    assert_eq!(
+        changes(&history),
+        [
+            (Operation::Update, "a"),
+            (Operation::Update, "b"),
+            (Operation::Replace, "c"),
+            (Operation::Delete, "d"),
+        ]
+    );
+    assert!(history
+        .iter()
+        .all(|entry| entry.actor.as_deref() == Some("alice")));
+
+    let as_of = |timestamp| {
+        repo.as_of(1, timestamp)
+            .unwrap()
+            .map(|account| account.name)
+    };
+    assert_eq!(as_of(before_update).as_deref(), Some("a"));
+    assert_eq!(as_of(before_patch).as_deref(), Some("b"));
+    assert_eq!(as_of(before_replace).as_deref(), Some("c"));
+    assert_eq!(as_of(before_delete).as_deref(), Some("d"));
+    assert_eq!(as_of(after_delete), None);
+
+    // Batch writes record every row they change; inserts record nothing.
+    repo.save_batch(&[account(2, "e"), account(3, "f")])
+        .unwrap();
+    repo.update_batch(&[account(2, "g"), account(3, "h")])
+        .unwrap();
+    repo.delete_batch(&[2, 3]).unwrap();
+    assert_eq!(
+        changes(&repo.history_of(3).unwrap()),
+        [(Operation::Update, "f"), (Operation::Delete, "h")]
+    );
+    assert!(repo.history_of(4).unwrap().is_empty());
+    assert_eq!(repo.as_of(4, after_delete).unwrap(), None);
+}
+
+#[cfg(feature = "async")]
+mod asynchronous {
+    use super::*;
+    use diesel_async::{AsyncConnection, AsyncPgConnection};
+
+    diesel::table! {
+        history_test_async_accounts {
+            id -> Integer,
+            name -> Text,
+        }
+    }
+
+    diesel::table! {
+        history_test_async_account_history (history_id) {
+            history_id -> BigInt,
+            id -> Integer,
+            name -> Text,
+            operation -> Text,
+            actor -> Nullable<Text>,
+            changed_at -> Timestamp,
+        }
+    }
+
+    pub mod db {
+        pub type DbPool = tokio::sync::Mutex<diesel_async::AsyncPgConnection>;
+    }
+
+    #[derive(Queryable, Insertable, Identifiable, AsChangeset, Repo, Debug, Clone, PartialEq)]
+    #[diesel(table_name = history_test_async_accounts, check_for_backend(diesel::pg::Pg))]
+    #[repository(
+        mode = "async",
+        pool = db::DbPool,
+        history = history_test_async_account_history
+    )]
+    #[crud_repo(save, update, delete)]
+    pub struct Account {
+        pub id: i32,
+        pub name: String,
+    }
+
+    impl HistoryActor for AccountRepo {
+        fn current_actor(&self) -> Option<String> {
+            None
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres database at DATABASE_URL"]
+    async fn writes_record_the_previous_row() {
+        use diesel_repository::asynchronous::{Delete, History, Save, Update};
+
+        create_tables(
+            &mut connect(),
+            "history_test_async_accounts",
+            "history_test_async_account_history",
+        );
+        let url = std::env::var("DATABASE_URL").unwrap();
+        let conn = AsyncPgConnection::establish(&url).await.unwrap();
+        let repo = AccountRepo::new(Arc::new(tokio::sync::Mutex::new(conn)));
+
+        let name = |name: &str| Account {
+            id: 1,
+            name: name.into(),
+        };
+        repo.save(name("a")).await.unwrap();
+        let before_update = instant();
+        repo.update(name("b")).await.unwrap();
+        let before_delete = instant();
+        repo.delete(1).await.unwrap();
+
+        let history = repo.history_of(1).await.unwrap();
+        let changes: Vec<_> = history
+            .iter()
+            .map(|entry| (entry.operation, entry.record.name.as_str(), &entry.actor))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                (Operation::Update, "a", &None),
+                (Operation::Delete, "b", &None)
+            ]
+        );
+        assert_eq!(repo.as_of(1, before_update).await.unwrap(), Some(name("a")));
+        assert_eq!(repo.as_of(1, before_delete).await.unwrap(), Some(name("b")));
+        assert_eq!(repo.as_of(1, instant()).await.unwrap(), None);
+    }
+}
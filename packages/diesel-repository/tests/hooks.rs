@@ -0,0 +1,426 @@
+//! Runs the `RepoHooks` of sync and async repositories on SQLite in memory, rejecting writes
+//! and loads with `ValidationError`s that reach the caller as `QueryBuilderError`s.
+#![cfg(feature = "sqlite")]
+
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel_repository::{Patch, Repo, RepoHooks, ValidationError};
+
+diesel::table! {
+    hooks_test_accounts {
+        id -> Integer,
+        name -> Text,
+    }
+}
+
+const SCHEMA: &str =
+    "CREATE TABLE hooks_test_accounts (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+     INSERT INTO hooks_test_accounts VALUES (1, 'root');";
+
+fn rejection(err: Error) -> ValidationError {
+    match err {
+        Error::QueryBuilderError(err) => err
+            .downcast_ref::<ValidationError>()
+            .expect("the hook's error should be a ValidationError")
+            .clone(),
+        err => panic!("expected a QueryBuilderError, got {:?}", err),
+    }
+}
+
+fn check_secret(name: &str) -> Result<(), ValidationError> {
+    if name == "secret" {
+        return Err(ValidationError::for_field("name", "must not be read"));
+    }
+    Ok(())
+}
+
+fn check_name(name: &str) -> Result<(), ValidationError> {
+    if name.is_empty() {
+        return Err(ValidationError::for_field("name", "must not be empty"));
+    }
+    Ok(())
+}
+
+mod sync {
+    use super::*;
+    use diesel_repository::{
+        Delete, DeleteBatch, FindAll, FindById, FindByIdBatch, Save, SaveBatch, Update, UpdateBatch,
+    };
+    use std::sync::{Arc, Mutex};
+
+    pub mod db {
+        pub type DbPool = std::sync::Mutex<diesel::SqliteConnection>;
+    }
+
+    #[derive(
+        Queryable, Insertable, Identifiable, AsChangeset, Repo, Patch, Debug, Clone, PartialEq,
+    )]
+    #[diesel(table_name = hooks_test_accounts, check_for_backend(diesel::sqlite::Sqlite))]
+    #[repository(pool = db::DbPool, hooks)]
+    #[crud_repo(find_one, find_all, save, update, patch, delete)]
+    #[batch_repo(find, save, update, delete)]
+    pub struct Account {
+        pub id: i32,
+        pub name: String,
+    }
+
+    impl RepoHooks<Account, i32, Account, Account, AccountPatch> for AccountRepo {
+        fn before_save(&self, record: &mut Account) -> Result<(), ValidationError> {
+            record.name = record.name.trim().to_string();
+            check_name(&record.name)
+        }
+
+        fn before_update(&self, record: &mut Account) -> Result<(), ValidationError> {
+            check_name(&record.name)
+        }
+
+        fn before_patch(&self, _id: &i32, patch: &mut AccountPatch) -> Result<(), ValidationError> {
+            patch.name.as_deref().map_or(Ok(()), check_name)
+        }
+
+        fn after_save(&self, record: &Account) -> Result<(), ValidationError> {
+            if record.name == "forbidden" {
+                return Err(ValidationError::new("forbidden names are rolled back"));
+            }
+            Ok(())
+        }
+
+        fn before_delete(&self, id: &i32) -> Result<(), ValidationError> {
+            if *id == 1 {
+                return Err(ValidationError::new("the root account is protected"));
+            }
+            Ok(())
+        }
+
+        fn after_load(&self, record: &mut Account) -> Result<(), ValidationError> {
+            check_secret(&record.name)?;
+            record.name = record.name.to_uppercase();
+            Ok(())
+        }
+    }
+
+    fn names(repo: &AccountRepo) -> Vec<String> {
+        let mut conn = repo.pool.lock().unwrap();
+        hooks_test_accounts::table
+            .select(hooks_test_accounts::name)
+            .order(hooks_test_accounts::id)
+            .load(&mut *conn)
+            .unwrap()
+    }
+
+    #[test]
+    fn hooks_run_around_every_write() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.batch_execute(SCHEMA).unwrap();
+        let repo = AccountRepo::new(Arc::new(Mutex::new(conn)));
+
+        let saved = repo
+            .save(Account {
+                id: 2,
+                name: "  alice ".into(),
+            })
+            .unwrap();
+        assert_eq!(saved.name, "alice");
+
+        let err = repo
+            .save(Account {
+                id: 3,
+                name: " ".into(),
+            })
+            .unwrap_err();
+        assert_eq!(
+            rejection(err),
+            ValidationError::for_field("name", "must not be empty")
+        );
+
+        let err = repo
+            .update(Account {
+                id: 2,
+                name: String::new(),
+            })
+            .unwrap_err();
+        assert_eq!(rejection(err).field.as_deref(), Some("name"));
+
+        let patch = AccountPatch {
+            name: Some(String::new()),
+        };
+        assert_eq!(
+            rejection(repo.patch(2, patch).unwrap_err())
+                .field
+                .as_deref(),
+            Some("name")
+        );
+        let patch = AccountPatch {
+            name: Some("alicia".into()),
+        };
+        assert_eq!(repo.patch(2, patch).unwrap().name, "alicia");
+
+        // `after_save` runs in the write's transaction, so its rejection rolls the write back.
+        let err = repo
+            .update(Account {
+                id: 2,
+                name: "forbidden".into(),
+            })
+            .unwrap_err();
+        assert_eq!(rejection(err).field, None);
+
+        assert!(rejection(repo.delete(1).unwrap_err())
+            .message
+            .contains("protected"));
+        assert_eq!(names(&repo), ["root", "alicia"]);
+    }
+
+    fn account(id: i32, name: &str) -> Account {
+        Account {
+            id,
+            name: name.into(),
+        }
+    }
+
+    #[test]
+    fn hooks_run_on_loads_and_batches() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.batch_execute(SCHEMA).unwrap();
+        let repo = AccountRepo::new(Arc::new(Mutex::new(conn)));
+
+        assert_eq!(repo.find_by_id(1).unwrap().name, "ROOT");
+        let saved = repo
+            .save_batch(&[account(2, " alice "), account(3, "secret")])
+            .unwrap();
+        assert_eq!(saved, [account(2, "alice"), account(3, "secret")]);
+
+        // `after_load` rejects the read of a secret record, whichever method loads it.
+        assert_eq!(
+            rejection(repo.find_by_id(3).unwrap_err()).message,
+            "must not be read"
+        );
+        assert!(repo.find_all().is_err());
+        assert!(repo.find_by_id_batch(&[2, 3]).is_err());
+        let mut loaded = repo.find_by_id_batch(&[1, 2]).unwrap();
+        loaded.sort_by_key(|account| account.id);
+        assert_eq!(loaded, [account(1, "ROOT"), account(2, "ALICE")]);
+
+        // A rejected record rolls back the whole batch, before or after its write.
+        let err = repo
+            .save_batch(&[account(4, "carol"), account(5, " ")])
+            .unwrap_err();
+        assert_eq!(rejection(err).field.as_deref(), Some("name"));
+        let err = repo
+            .update_batch(&[account(2, "alicia"), account(3, "forbidden")])
+            .unwrap_err();
+        assert_eq!(rejection(err).field, None);
+        assert!(rejection(repo.delete_batch(&[3, 1]).unwrap_err())
+            .message
+            .contains("protected"));
+        assert_eq!(names(&repo), ["root", "alice", "secret"]);
+
+        repo.update_batch(&[account(3, "bob")]).unwrap();
+        repo.delete_batch(&[2]).unwrap();
+        assert_eq!(
+            repo.find_all()
+                .unwrap()
+                .into_iter()
+                .map(|account| account.name)
+                .collect::<Vec<_>>(),
+            ["ROOT", "BOB"]
+        );
+    }
+}
+
+#[cfg(feature = "async")]
+mod asynchronous {
+    use super::*;
+    use diesel_async::sync_connection_wrapper::SyncConnectionWrapper;
+    use diesel_repository::asynchronous::{
+        Delete, DeleteBatch, FindAll, FindById, FindByIdBatch, Patch as _, Save, SaveBatch, Update,
+        UpdateBatch,
+    };
+    use std::sync::Arc;
+
+    pub mod db {
+        pub type DbPool = tokio::sync::Mutex<
+            diesel_async::sync_connection_wrapper::SyncConnectionWrapper<diesel::SqliteConnection>,
+        >;
+    }
+
+    #[derive(
+        Queryable, Insertable, Identifiable, AsChangeset, Repo, Patch, Debug, Clone, PartialEq,
+    )]
+    #[diesel(table_name = hooks_test_accounts, check_for_backend(diesel::sqlite::Sqlite))]
+    #[repository(mode = "async", pool = db::DbPool, hooks)]
+    #[crud_repo(find_one, find_all, save, update, patch, delete)]
+    #[batch_repo(find, save, update, delete)]
+    pub struct Account {
+        pub id: i32,
+        pub name: String,
+    }
+
+    impl RepoHooks<Account, i32, Account, Account, AccountPatch> for AccountRepo {
+        fn before_save(&self, record: &mut Account) -> Result<(), ValidationError> {
+            record.name = record.name.trim().to_string();
+            check_name(&record.name)
+        }
+
+        fn before_update(&self, record: &mut Account) -> Result<(), ValidationError> {
+            check_name(&record.name)
+        }
+
+        fn before_patch(&self, _id: &i32, patch: &mut AccountPatch) -> Result<(), ValidationError> {
+            patch.name.as_deref().map_or(Ok(()), check_name)
+        }
+
+        fn after_save(&self, record: &Account) -> Result<(), ValidationError> {
+            if record.name == "forbidden" {
+                return Err(ValidationError::new("forbidden names are rolled back"));
+            }
+            Ok(())
+        }
+
+        fn before_delete(&self, id: &i32) -> Result<(), ValidationError> {
+            if *id == 1 {
+                return Err(ValidationError::new("the root account is protected"));
+            }
+            Ok(())
+        }
+
+        fn after_load(&self, record: &mut Account) -> Result<(), ValidationError> {
+            check_secret(&record.name)?;
+            record.name = record.name.to_uppercase();
+            Ok(())
+        }
+    }
+
+    async fn names(repo: &AccountRepo) -> Vec<String> {
+        use diesel_async::RunQueryDsl;
+
+        let mut conn = repo.pool.lock().await;
+        let query = hooks_test_accounts::table
+            .select(hooks_test_accounts::name)
+            .order(hooks_test_accounts::id);
+        RunQueryDsl::load(query, &mut *conn).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn hooks_run_around_every_write() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.batch_execute(SCHEMA).unwrap();
+        let repo = AccountRepo::new(Arc::new(tokio::sync::Mutex::new(
+            SyncConnectionWrapper::new(conn),
+        )));
+
+        let saved = repo
+            .save(Account {
+                id: 2,
+                name: "  alice ".into(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(saved.name, "alice");
+
+        let err = repo
+            .save(Account {
+                id: 3,
+                name: " ".into(),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(
+            rejection(err),
+            ValidationError::for_field("name", "must not be empty")
+        );
+
+        let err = repo
+            .update(Account {
+                id: 2,
+                name: String::new(),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(rejection(err).field.as_deref(), Some("name"));
+
+        let patch = AccountPatch {
+            name: Some(String::new()),
+        };
+        assert_eq!(
+            rejection(repo.patch(2, patch).await.unwrap_err())
+                .field
+                .as_deref(),
+            Some("name")
+        );
+        let patch = AccountPatch {
+            name: Some("alicia".into()),
+        };
+        assert_eq!(repo.patch(2, patch).await.unwrap().name, "alicia");
+
+        let err = repo
+            .update(Account {
+                id: 2,
+                name: "forbidden".into(),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(rejection(err).field, None);
+
+        assert!(rejection(repo.delete(1).await.unwrap_err())
+            .message
+            .contains("protected"));
+        assert_eq!(names(&repo).await, ["root", "alicia"]);
+    }
+
+    fn account(id: i32, name: &str) -> Account {
+        Account {
+            id,
+            name: name.into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn hooks_run_on_loads_and_batches() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.batch_execute(SCHEMA).unwrap();
+        let repo = AccountRepo::new(Arc::new(tokio::sync::Mutex::new(
+            SyncConnectionWrapper::new(conn),
+        )));
+
+        assert_eq!(repo.find_by_id(1).await.unwrap().name, "ROOT");
+        let saved = repo
+            .save_batch(&[account(2, " alice "), account(3, "secret")])
+            .await
+            .unwrap();
+        assert_eq!(saved, [account(2, "alice"), account(3, "secret")]);
+
+        assert_eq!(
+            rejection(repo.find_by_id(3).await.unwrap_err()).message,
+            "must not be read"
+        );
+        assert!(repo.find_all().await.is_err());
+        assert!(repo.find_by_id_batch(&[2, 3]).await.is_err());
+
+        let err = repo
+            .save_batch(&[account(4, "carol"), account(5, " ")])
+            .await
+            .unwrap_err();
+        assert_eq!(rejection(err).field.as_deref(), Some("name"));
+        let err = repo
+            .update_batch(&[account(2, "alicia"), account(3, "forbidden")])
+            .await
+            .unwrap_err();
+        assert_eq!(rejection(err).field, None);
+        assert!(rejection(repo.delete_batch(&[3, 1]).await.unwrap_err())
+            .message
+            .contains("protected"));
+        assert_eq!(names(&repo).await, ["root", "alice", "secret"]);
+
+        repo.update_batch(&[account(3, "bob")]).await.unwrap();
+        repo.delete_batch(&[2]).await.unwrap();
+        let names: Vec<_> = repo
+            .find_all()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|account| account.name)
+            .collect();
+        assert_eq!(names, ["ROOT", "BOB"]);
+    }
+}
@@ -0,0 +1,306 @@
+//! Binds repositories to SQLite connections in memory with `InTransaction`, which only
+//! accepts connections inside an open transaction, and takes row locks with it on the
+//! Postgres database at `DATABASE_URL`. Run the Postgres tests with:
+//!
+//! ```sh
+//! DATABASE_URL=postgres://... cargo test -p diesel-repository --features postgres,async --test lock -- --ignored
+//! ```
+#![cfg(any(feature = "sqlite", feature = "postgres"))]
+
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel_repository::{InTransaction, Repo};
+
+diesel::table! {
+    lock_test_accounts {
+        id -> Integer,
+        name -> Text,
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sync {
+    use super::*;
+    use diesel_repository::Lock;
+    use std::sync::{Arc, Mutex};
+
+    pub mod db {
+        pub type DbPool = std::sync::Mutex<diesel::SqliteConnection>;
+    }
+
+    #[derive(Queryable, Repo, Debug, PartialEq)]
+    #[diesel(table_name = crate::lock_test_accounts, check_for_backend(diesel::sqlite::Sqlite))]
+    #[repository(pool = db::DbPool)]
+    #[crud_repo(find_one, lock)]
+    pub struct Account {
+        pub id: i32,
+        pub name: String,
+    }
+
+    fn is_lock<L: Lock<Account, i32>>(_: &L) {}
+
+    #[test]
+    fn views_need_an_open_transaction() {
+        let conn = SqliteConnection::establish(":memory:").unwrap();
+        let repo = AccountRepo::new(Arc::new(Mutex::new(conn)));
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+
+        assert!(matches!(
+            InTransaction::new(&repo, &mut conn),
+            Err(Error::NotInTransaction)
+        ));
+
+        conn.transaction(|conn| {
+            let tx = InTransaction::new(&repo, conn)?;
+            is_lock(&tx);
+
+            // Savepoints are transactions too.
+            conn.transaction(|conn| InTransaction::new(&repo, conn).map(drop))
+        })
+        .unwrap();
+
+        // The view is gone with the transaction it was created in.
+        assert!(matches!(
+            InTransaction::new(&repo, &mut conn),
+            Err(Error::NotInTransaction)
+        ));
+    }
+}
+
+#[cfg(all(feature = "sqlite", feature = "async"))]
+mod asynchronous {
+    use super::*;
+    use diesel_async::scoped_futures::ScopedFutureExt;
+    use diesel_async::sync_connection_wrapper::SyncConnectionWrapper;
+    use diesel_async::AsyncConnection;
+    use diesel_repository::asynchronous::Lock;
+    use std::sync::Arc;
+
+    pub mod db {
+        pub type DbPool = tokio::sync::Mutex<
+            diesel_async::sync_connection_wrapper::SyncConnectionWrapper<diesel::SqliteConnection>,
+        >;
+    }
+
+    #[derive(Queryable, Repo, Debug, PartialEq)]
+    #[diesel(table_name = crate::lock_test_accounts, check_for_backend(diesel::sqlite::Sqlite))]
+    #[repository(mode = "async", pool = db::DbPool)]
+    #[crud_repo(find_one, lock)]
+    pub struct Account {
+        pub id: i32,
+        pub name: String,
+    }
+
+    fn is_lock<L: Lock<Account, i32>>(_: &L) {}
+
+    #[tokio::test]
+    async fn views_need_an_open_transaction() {
+        let conn = SyncConnectionWrapper::<SqliteConnection>::establish(":memory:")
+            .await
+            .unwrap();
+        let repo = AccountRepo::new(Arc::new(tokio::sync::Mutex::new(conn)));
+        let mut conn = SyncConnectionWrapper::<SqliteConnection>::establish(":memory:")
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            InTransaction::new_async(&repo, &mut conn),
+            Err(Error::NotInTransaction)
+        ));
+
+        let repo = &repo;
+        conn.transaction::<_, Error, _>(|conn| {
+            async move {
+                let tx = InTransaction::new_async(repo, conn)?;
+                is_lock(&tx);
+                Ok(())
+            }
+            .scope_boxed()
+        })
+        .await
+        .unwrap();
+
+        assert!(matches!(
+            InTransaction::new_async(&repo, &mut conn),
+            Err(Error::NotInTransaction)
+        ));
+    }
+}
+
+#[cfg(feature = "postgres")]
+mod postgres {
+    use super::*;
+    use diesel::connection::SimpleConnection;
+    use diesel_repository::{Lock, LockMode};
+    use std::sync::{Arc, Mutex};
+
+    pub mod db {
+        pub type DbPool = std::sync::Mutex<diesel::PgConnection>;
+    }
+
+    #[derive(Queryable, Repo, Debug, PartialEq)]
+    #[diesel(table_name = crate::lock_test_accounts, check_for_backend(diesel::pg::Pg))]
+    #[repository(pool = db::DbPool)]
+    #[crud_repo(lock)]
+    pub struct Account {
+        pub id: i32,
+        pub name: String,
+    }
+
+    pub(super) fn connect() -> PgConnection {
+        let url =
+            std::env::var("DATABASE_URL").expect("DATABASE_URL should name a Postgres database");
+        PgConnection::establish(&url).expect("DATABASE_URL should accept connections")
+    }
+
+    pub(super) fn create_table(conn: &mut PgConnection, table: &str) {
+        conn.batch_execute(&format!(
+            "DROP TABLE IF EXISTS {table};
+             CREATE TABLE {table} (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+             INSERT INTO {table} VALUES (1, 'alice'), (2, 'bob');"
+        ))
+        .unwrap();
+    }
+
+    fn is_lock_error(err: Error) -> bool {
+        matches!(err, Error::DatabaseError(_, info) if info.message().contains("could not obtain lock"))
+    }
+
+    #[test]
+    #[ignore = "needs a Postgres database at DATABASE_URL"]
+    fn locked_finds_hold_their_rows() {
+        use lock_test_accounts::{id, name};
+
+        let mut holder = connect();
+        create_table(&mut holder, "lock_test_accounts");
+        let repo = AccountRepo::new(Arc::new(Mutex::new(connect())));
+        let mut other = connect();
+
+        holder
+            .transaction(|conn| {
+                let mut tx = InTransaction::new(&repo, conn)?;
+                let alice = tx.find_by_id_locked(1, LockMode::for_no_key_update())?;
+                assert_eq!(alice.name, "alice");
+
+                other.transaction(|conn| {
+                    let mut tx = InTransaction::new(&repo, conn)?;
+                    // `FOR KEY SHARE` is compatible with `FOR NO KEY UPDATE`, `FOR SHARE` is not.
+                    assert_eq!(
+                        tx.find_by_id_locked(1, LockMode::for_key_share().nowait())?
+                            .name,
+                        "alice"
+                    );
+                    let err = tx
+                        .find_by_id_locked(1, LockMode::for_share().nowait())
+                        .unwrap_err();
+                    assert!(is_lock_error(err));
+                    Ok::<_, Error>(())
+                })?;
+
+                other.transaction(|conn| {
+                    let mut tx = InTransaction::new(&repo, conn)?;
+                    let unlocked = tx.find_by_query_locked(
+                        &|q| q.order(id),
+                        LockMode::for_update().skip_locked(),
+                    )?;
+                    assert_eq!(
+                        unlocked,
+                        [Account {
+                            id: 2,
+                            name: "bob".into()
+                        }]
+                    );
+                    let err = tx
+                        .find_by_query_locked(
+                            &|q| q.filter(name.eq("alice")),
+                            LockMode::for_update().nowait(),
+                        )
+                        .unwrap_err();
+                    assert!(is_lock_error(err));
+                    Ok::<_, Error>(())
+                })
+            })
+            .unwrap();
+
+        // The lock ends with the holder's transaction.
+        other
+            .transaction(|conn| {
+                let mut tx = InTransaction::new(&repo, conn)?;
+                tx.find_by_id_locked(1, LockMode::for_update().nowait())
+                    .map(drop)
+            })
+            .unwrap();
+    }
+}
+
+#[cfg(all(feature = "postgres", feature = "async"))]
+mod postgres_async {
+    use super::postgres::{connect, create_table};
+    use super::*;
+    use diesel_async::scoped_futures::ScopedFutureExt;
+    use diesel_async::{AsyncConnection, AsyncPgConnection};
+    use diesel_repository::asynchronous::Lock;
+    use diesel_repository::LockMode;
+    use std::sync::Arc;
+
+    diesel::table! {
+        lock_test_async_accounts {
+            id -> Integer,
+            name -> Text,
+        }
+    }
+
+    pub mod db {
+        pub type DbPool = tokio::sync::Mutex<diesel_async::AsyncPgConnection>;
+    }
+
+    #[derive(Queryable, Repo, Debug, PartialEq)]
+    #[diesel(table_name = lock_test_async_accounts, check_for_backend(diesel::pg::Pg))]
+    #[repository(mode = "async", pool = db::DbPool)]
+    #[crud_repo(lock)]
+    pub struct Account {
+        pub id: i32,
+        pub name: String,
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres database at DATABASE_URL"]
+    async fn locked_finds_skip_rows_locked_elsewhere() {
+        let mut holder = connect();
+        create_table(&mut holder, "lock_test_async_accounts");
+        let url = std::env::var("DATABASE_URL").unwrap();
+        let repo = AccountRepo::new(Arc::new(tokio::sync::Mutex::new(
+            AsyncPgConnection::establish(&url).await.unwrap(),
+        )));
+        let mut other = AsyncPgConnection::establish(&url).await.unwrap();
+
+        holder.begin_test_transaction().unwrap();
+        diesel::sql_query("SELECT id FROM lock_test_async_accounts WHERE id = 2 FOR UPDATE")
+            .execute(&mut holder)
+            .unwrap();
+
+        let repo = &repo;
+        let (alice, unlocked) = other
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let mut tx = InTransaction::new_async(repo, conn)?;
+                    let alice = tx.find_by_id_locked(1, LockMode::for_update()).await?;
+                    let unlocked = tx
+                        .find_by_query_locked(&|q| q, LockMode::for_share().skip_locked())
+                        .await?;
+                    Ok((alice, unlocked))
+                }
+                .scope_boxed()
+            })
+            .await
+            .unwrap();
+        assert_eq!(alice.name, "alice");
+        assert_eq!(
+            unlocked
+                .iter()
+                .map(|account| account.id)
+                .collect::<Vec<_>>(),
+            [1]
+        );
+    }
+}
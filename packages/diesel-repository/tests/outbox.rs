@@ -0,0 +1,336 @@
+//! Writes entities with domain events and relays the events through the Postgres database at
+//! `DATABASE_URL`. Run with:
+//!
+//! ```sh
+//! DATABASE_URL=postgres://... cargo test -p diesel-repository --features postgres,async --test outbox -- --ignored
+//! ```
+#![cfg(feature = "postgres")]
+
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+use diesel::result::{DatabaseErrorKind, Error};
+use diesel::sql_types::{BigInt, Nullable, Text};
+use diesel_repository::{
+    write_events, Delete, DomainEvents, Operation, OutboxEvent, OutboxMessage, OutboxPublisher,
+    OutboxRelay, RelayError, Repo, Save, Update,
+};
+use std::sync::{Arc, Mutex};
+
+diesel::table! {
+    outbox_test_accounts {
+        id -> Integer,
+        name -> Text,
+    }
+}
+
+pub mod db {
+    pub type DbPool = std::sync::Mutex<diesel::PgConnection>;
+}
+
+#[derive(Queryable, Insertable, Identifiable, AsChangeset, Repo, Debug, Clone, PartialEq)]
+#[diesel(table_name = outbox_test_accounts, check_for_backend(diesel::pg::Pg))]
+#[repository(pool = db::DbPool, outbox = outbox_test_events)]
+#[crud_repo(save, update, delete)]
+pub struct Account {
+    pub id: i32,
+    pub name: String,
+}
+
+impl DomainEvents for Account {
+    fn domain_events(&self, operation: Operation) -> Vec<OutboxEvent> {
+        // The outbox table rejects this event type, failing the write's transaction.
+        let event_type = if self.name == "rejected" {
+            "account.rejected".to_string()
+        } else {
+            format!("account.{}", operation.as_str())
+        };
+        vec![OutboxEvent {
+            aggregate_type: "account".into(),
+            aggregate_id: self.id.to_string(),
+            event_type,
+            payload: self.name.clone(),
+        }]
+    }
+}
+
+#[derive(QueryableByName, Debug, PartialEq, Eq)]
+struct Row {
+    #[diesel(sql_type = Text)]
+    event_type: String,
+    #[diesel(sql_type = Nullable<Text>)]
+    published_at: Option<String>,
+}
+
+#[derive(QueryableByName)]
+struct Total {
+    #[diesel(sql_type = BigInt)]
+    total: i64,
+}
+
+fn connect() -> PgConnection {
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL should name a Postgres database");
+    PgConnection::establish(&url).expect("DATABASE_URL should accept connections")
+}
+
+fn create_outbox(conn: &mut PgConnection, table: &str) {
+    conn.batch_execute(&format!(
+        "DROP TABLE IF EXISTS {table};
+         CREATE TABLE {table} (
+             id BIGSERIAL PRIMARY KEY,
+             aggregate_type TEXT NOT NULL,
+             aggregate_id TEXT NOT NULL,
+             event_type TEXT NOT NULL CHECK (event_type <> 'account.rejected'),
+             payload TEXT NOT NULL,
+             created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+             published_at TIMESTAMPTZ
+         );"
+    ))
+    .unwrap();
+}
+
+fn events(conn: &mut PgConnection, table: &str) -> Vec<Row> {
+    diesel::sql_query(format!(
+        "SELECT event_type, published_at::text FROM {} ORDER BY id",
+        table
+    ))
+    .load(conn)
+    .unwrap()
+}
+
+fn accounts(conn: &mut PgConnection) -> i64 {
+    diesel::sql_query("SELECT count(*) AS total FROM outbox_test_accounts")
+        .get_result::<Total>(conn)
+        .unwrap()
+        .total
+}
+
+#[test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+fn writes_store_their_events_in_the_same_transaction() {
+    let mut conn = connect();
+    conn.batch_execute(
+        "DROP TABLE IF EXISTS outbox_test_accounts;
+         CREATE TABLE outbox_test_accounts (id INTEGER PRIMARY KEY, name TEXT NOT NULL);",
+    )
+    .unwrap();
+    create_outbox(&mut conn, "outbox_test_events");
+    let repo = AccountRepo::new(Arc::new(Mutex::new(connect())));
+
+    let alice = repo
+        .save(Account {
+            id: 1,
+            name: "alice".into(),
+        })
+        .unwrap();
+    repo.update(Account {
+        name: "alicia".into(),
+        ..alice
+    })
+    .unwrap();
+    repo.delete(1).unwrap();
+    let types: Vec<_> = events(&mut conn, "outbox_test_events")
+        .into_iter()
+        .map(|row| row.event_type)
+        .collect();
+    assert_eq!(
+        types,
+        ["account.insert", "account.update", "account.delete"]
+    );
+
+    // The outbox rejects the event, so the row it was raised for is rolled back with it.
+    let err = repo
+        .save(Account {
+            id: 2,
+            name: "rejected".into(),
+        })
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        Error::DatabaseError(DatabaseErrorKind::CheckViolation, _)
+    ));
+    assert_eq!(accounts(&mut conn), 0);
+    assert_eq!(events(&mut conn, "outbox_test_events").len(), 3);
+
+    // A write that fails stores no events.
+    repo.save(Account {
+        id: 3,
+        name: "bob".into(),
+    })
+    .unwrap();
+    assert!(repo
+        .save(Account {
+            id: 3,
+            name: "bobby".into(),
+        })
+        .is_err());
+    assert!(matches!(repo.delete(4), Err(Error::NotFound)));
+    assert_eq!(events(&mut conn, "outbox_test_events").len(), 4);
+}
+
+struct Collect {
+    published: Mutex<Vec<String>>,
+}
+
+impl OutboxPublisher for &Collect {
+    fn publish(
+        &self,
+        message: &OutboxMessage,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if message.event_type == "poison" {
+            return Err("the broker rejected the event".into());
+        }
+        self.published
+            .lock()
+            .unwrap()
+            .push(message.event_type.clone());
+        Ok(())
+    }
+}
+
+fn event(event_type: &str) -> OutboxEvent {
+    OutboxEvent {
+        aggregate_type: "account".into(),
+        aggregate_id: "1".into(),
+        event_type: event_type.into(),
+        payload: String::new(),
+    }
+}
+
+#[test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+fn relay_publishes_claimed_events_once() {
+    let mut conn = connect();
+    create_outbox(&mut conn, "outbox_test_relay");
+    let collect = Collect {
+        published: Mutex::new(Vec::new()),
+    };
+    let relay = OutboxRelay::new("outbox_test_relay", &collect).batch_size(2);
+
+    write_events(
+        &mut conn,
+        "outbox_test_relay",
+        &[event("a"), event("b"), event("c")],
+    )
+    .unwrap();
+    assert_eq!(relay.relay_once(&mut conn).unwrap(), 2);
+    assert_eq!(relay.relay_once(&mut conn).unwrap(), 1);
+    assert_eq!(relay.relay_once(&mut conn).unwrap(), 0);
+    assert_eq!(*collect.published.lock().unwrap(), ["a", "b", "c"]);
+    assert!(events(&mut conn, "outbox_test_relay")
+        .iter()
+        .all(|row| row.published_at.is_some()));
+
+    // An event claimed by another relay is skipped instead of waited for.
+    write_events(&mut conn, "outbox_test_relay", &[event("d"), event("e")]).unwrap();
+    let mut other = connect();
+    other
+        .batch_execute(
+            "BEGIN;
+             SELECT id FROM outbox_test_relay WHERE published_at IS NULL
+             ORDER BY id LIMIT 1 FOR UPDATE;",
+        )
+        .unwrap();
+    assert_eq!(relay.relay_once(&mut conn).unwrap(), 1);
+    other.batch_execute("ROLLBACK").unwrap();
+    assert_eq!(relay.relay_once(&mut conn).unwrap(), 1);
+    assert_eq!(
+        *collect.published.lock().unwrap(),
+        ["a", "b", "c", "e", "d"]
+    );
+
+    // Events published before a rejected one stay published; the rest are retried.
+    write_events(
+        &mut conn,
+        "outbox_test_relay",
+        &[event("f"), event("poison")],
+    )
+    .unwrap();
+    assert!(matches!(
+        relay.relay_once(&mut conn),
+        Err(RelayError::Publish(_))
+    ));
+    let pending: Vec<_> = events(&mut conn, "outbox_test_relay")
+        .into_iter()
+        .filter(|row| row.published_at.is_none())
+        .map(|row| row.event_type)
+        .collect();
+    assert_eq!(pending, ["poison"]);
+}
+
+#[cfg(feature = "async")]
+mod asynchronous {
+    use super::*;
+    use diesel_async::{AsyncConnection, AsyncPgConnection};
+    use diesel_repository::asynchronous::Save;
+
+    diesel::table! {
+        outbox_test_async_accounts {
+            id -> Integer,
+            name -> Text,
+        }
+    }
+
+    pub mod db {
+        pub type DbPool = tokio::sync::Mutex<diesel_async::AsyncPgConnection>;
+    }
+
+    #[derive(Queryable, Insertable, Repo, Debug, Clone, PartialEq)]
+    #[diesel(table_name = outbox_test_async_accounts, check_for_backend(diesel::pg::Pg))]
+    #[repository(mode = "async", pool = db::DbPool, outbox = outbox_test_async_events)]
+    #[repo_type(id_type = i32)]
+    #[crud_repo(save)]
+    pub struct Account {
+        pub id: i32,
+        pub name: String,
+    }
+
+    impl DomainEvents for Account {
+        fn domain_events(&self, operation: Operation) -> Vec<OutboxEvent> {
+            super::Account {
+                id: self.id,
+                name: self.name.clone(),
+            }
+            .domain_events(operation)
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres database at DATABASE_URL"]
+    async fn writes_store_their_events_in_the_same_transaction() {
+        let mut conn = connect();
+        conn.batch_execute(
+            "DROP TABLE IF EXISTS outbox_test_async_accounts;
+             CREATE TABLE outbox_test_async_accounts (id INTEGER PRIMARY KEY, name TEXT NOT NULL);",
+        )
+        .unwrap();
+        create_outbox(&mut conn, "outbox_test_async_events");
+        let url = std::env::var("DATABASE_URL").unwrap();
+        let pool = AsyncPgConnection::establish(&url).await.unwrap();
+        let repo = AccountRepo::new(Arc::new(tokio::sync::Mutex::new(pool)));
+
+        repo.save(Account {
+            id: 1,
+            name: "alice".into(),
+        })
+        .await
+        .unwrap();
+        assert!(repo
+            .save(Account {
+                id: 2,
+                name: "rejected".into(),
+            })
+            .await
+            .is_err());
+
+        let types: Vec<_> = events(&mut conn, "outbox_test_async_events")
+            .into_iter()
+            .map(|row| row.event_type)
+            .collect();
+        assert_eq!(types, ["account.insert"]);
+        let total = diesel::sql_query("SELECT count(*) AS total FROM outbox_test_async_accounts")
+            .get_result::<Total>(&mut conn)
+            .unwrap()
+            .total;
+        assert_eq!(total, 1);
+    }
+}
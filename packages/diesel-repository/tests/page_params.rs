@@ -0,0 +1,72 @@
+use diesel_repository::{PageParams, PageParamsError, PageSpec, Paged, Sort, SortDirection};
+
+struct AccountPages;
+
+impl PageSpec for AccountPages {
+    const SORT_KEYS: &'static [&'static str] = &["name", "created_at"];
+    const MAX_PER_PAGE: i64 = 50;
+}
+
+#[test]
+fn parses_page_and_sort() {
+    let params = PageParams::<AccountPages>::from_query(
+        "page=2&per_page=25&sort=name%2Cdesc&sort=created_at&q=x",
+    )
+    .unwrap();
+
+    assert_eq!((params.page, params.per_page), (2, 25));
+    assert_eq!(
+        params.sort,
+        vec![
+            Sort {
+                key: "name",
+                direction: SortDirection::Desc
+            },
+            Sort {
+                key: "created_at",
+                direction: SortDirection::Asc
+            },
+        ]
+    );
+}
+
+#[test]
+fn defaults_and_limits() {
+    let params = PageParams::<AccountPages>::from_query("").unwrap();
+    assert_eq!((params.page, params.per_page), (1, 20));
+
+    assert_eq!(
+        PageParams::<AccountPages>::from_query("per_page=51").err(),
+        Some(PageParamsError::PerPageTooLarge { max: 50 })
+    );
+    assert_eq!(
+        PageParams::<AccountPages>::from_query("page=0").err(),
+        Some(PageParamsError::InvalidValue {
+            param: "page",
+            value: "0".into()
+        })
+    );
+    assert_eq!(
+        PageParams::<AccountPages>::from_query("sort=password").err(),
+        Some(PageParamsError::UnknownSortKey("password".into()))
+    );
+    assert!(PageParams::<diesel_repository::Unsorted>::from_query("sort=name").is_err());
+}
+
+#[test]
+fn link_header_keeps_other_parameters() {
+    let paged = Paged {
+        items: vec![(); 10],
+        total_count: 35,
+        page: 2,
+        per_page: 10,
+    };
+
+    assert_eq!(
+        paged.link_header("/accounts", "sort=name&page=2&per_page=10"),
+        "</accounts?sort=name&page=1&per_page=10>; rel=\"first\", \
+         </accounts?sort=name&page=1&per_page=10>; rel=\"prev\", \
+         </accounts?sort=name&page=3&per_page=10>; rel=\"next\", \
+         </accounts?sort=name&page=4&per_page=10>; rel=\"last\""
+    );
+}
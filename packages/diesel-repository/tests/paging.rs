@@ -0,0 +1,155 @@
+//! Runs the generated paged methods of each count strategy on SQLite in memory.
+#![cfg(feature = "sqlite")]
+
+use diesel::prelude::*;
+use diesel_repository::{
+    FindAllSlice, FindByQueryPaged, FindByQuerySlice, PagingAndSortingRepository, Repo,
+};
+use std::sync::{Arc, Mutex};
+
+diesel::table! {
+    paging_test_accounts {
+        id -> Integer,
+        name -> Text,
+    }
+}
+
+pub mod db {
+    pub type DbPool = std::sync::Mutex<diesel::SqliteConnection>;
+}
+
+#[derive(Queryable, Repo, Debug, PartialEq)]
+#[diesel(table_name = paging_test_accounts, check_for_backend(diesel::sqlite::Sqlite))]
+#[repository(pool = db::DbPool, name = SeparateRepo)]
+#[paging_repo(find_all, find_query)]
+pub struct Account {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Queryable, Repo, Debug, PartialEq)]
+#[diesel(table_name = paging_test_accounts, check_for_backend(diesel::sqlite::Sqlite))]
+#[repository(pool = db::DbPool, name = WindowRepo)]
+#[paging_repo(find_all, find_query, count_strategy = "window")]
+pub struct WindowAccount {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Queryable, Repo, Debug, PartialEq)]
+#[diesel(table_name = paging_test_accounts, check_for_backend(diesel::sqlite::Sqlite))]
+#[repository(pool = db::DbPool, name = SliceRepo)]
+#[paging_repo(find_all, find_query, count_strategy = "none")]
+pub struct SliceAccount {
+    pub id: i32,
+    pub name: String,
+}
+
+/// Five accounts, three of them named alice.
+fn pool() -> Arc<db::DbPool> {
+    let mut conn = SqliteConnection::establish(":memory:").unwrap();
+    diesel::sql_query(
+        "CREATE TABLE paging_test_accounts (id INTEGER PRIMARY KEY, name TEXT NOT NULL)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    diesel::sql_query(
+        "INSERT INTO paging_test_accounts VALUES
+         (1, 'alice'), (2, 'bob'), (3, 'alice'), (4, 'carol'), (5, 'alice')",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    Arc::new(Mutex::new(conn))
+}
+
+/// The ids and total of pages 1, 3 (the last) and 4 (past the end) of two accounts.
+fn pages<T: HasId>(repo: &impl PagingAndSortingRepository<T>) -> Vec<(Vec<i32>, i64)> {
+    [1, 3, 4]
+        .into_iter()
+        .map(|page| {
+            let paged = repo.find_all_paged(page, 2).unwrap();
+            (
+                paged.items.iter().map(HasId::id).collect(),
+                paged.total_count,
+            )
+        })
+        .collect()
+}
+
+trait HasId {
+    fn id(&self) -> i32;
+}
+
+impl HasId for Account {
+    fn id(&self) -> i32 {
+        self.id
+    }
+}
+
+impl HasId for WindowAccount {
+    fn id(&self) -> i32 {
+        self.id
+    }
+}
+
+impl HasId for SliceAccount {
+    fn id(&self) -> i32 {
+        self.id
+    }
+}
+
+#[test]
+fn every_strategy_reports_the_total_past_the_end() {
+    let expected = vec![(vec![1, 2], 5), (vec![5], 5), (vec![], 5)];
+    assert_eq!(pages(&SeparateRepo::new(pool())), expected);
+    assert_eq!(pages(&WindowRepo::new(pool())), expected);
+    // Slice repositories still implement the paged traits, with a separate count.
+    assert_eq!(pages(&SliceRepo::new(pool())), expected);
+}
+
+#[test]
+fn queries_are_paged_after_refining() {
+    use paging_test_accounts::{id, name};
+
+    let repo = WindowRepo::new(pool());
+    let paged = repo
+        .find_by_query_paged(&|q| q.filter(name.eq("alice")).order(id.desc()), 1, 2)
+        .unwrap();
+    assert_eq!(
+        paged.items.iter().map(HasId::id).collect::<Vec<_>>(),
+        [5, 3]
+    );
+    assert_eq!((paged.total_count, paged.has_next()), (3, true));
+
+    let paged = repo
+        .find_by_query_paged(&|q| q.filter(name.eq("alice")), 3, 2)
+        .unwrap();
+    assert!(paged.items.is_empty());
+    assert_eq!(paged.total_count, 3);
+
+    let repo = SeparateRepo::new(pool());
+    let paged = repo
+        .find_by_query_paged(&|q| q.filter(name.ne("alice")), 1, 5)
+        .unwrap();
+    assert_eq!(paged.total_count, 2);
+}
+
+#[test]
+fn slices_fetch_one_extra_row() {
+    use paging_test_accounts::name;
+
+    let repo = SliceRepo::new(pool());
+    let slice = repo.find_all_slice(2, 2).unwrap();
+    assert_eq!(
+        slice.items.iter().map(HasId::id).collect::<Vec<_>>(),
+        [3, 4]
+    );
+    assert!(slice.has_next);
+    assert!(!repo.find_all_slice(3, 2).unwrap().has_next);
+
+    let slice = repo
+        .find_by_query_slice(&|q| q.filter(name.eq("alice")), 1, 3)
+        .unwrap();
+    assert_eq!(slice.items.len(), 3);
+    assert!(!slice.has_next);
+}
@@ -0,0 +1,37 @@
+use diesel::prelude::*;
+use diesel_repository::{Count, ExistsById, ExistsByIdBatch, ExistsByQuery, Repo};
+
+table! {
+    accounts {
+        id -> Text,
+        name -> Text,
+        active -> Bool,
+    }
+}
+
+pub mod db {
+    pub type DbPool = std::sync::Mutex<diesel::SqliteConnection>;
+}
+
+#[derive(Queryable, Repo)]
+#[diesel(table_name = accounts, check_for_backend(diesel::sqlite::Sqlite))]
+#[repository(pool = db::DbPool)]
+#[crud_repo(count, exists, exists_query)]
+#[batch_repo(exists)]
+pub struct Account {
+    pub id: String,
+    pub name: String,
+    pub active: bool,
+}
+
+fn summary(repo: &AccountRepo) -> QueryResult<(i64, bool, bool, Vec<String>)> {
+    let active: i64 = repo.count(&|q| q.filter(accounts::active.eq(true)))?;
+    let known: bool = repo.exists_by_id("a".to_string())?;
+    let named: bool = repo.exists_by_query(&|q| q.filter(accounts::name.eq("alice")))?;
+    let existing: Vec<String> = repo.exists_by_id_batch(&["a".to_string(), "b".to_string()])?;
+    Ok((active, known, named, existing))
+}
+
+fn main() {
+    let _ = summary;
+}
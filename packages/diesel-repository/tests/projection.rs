@@ -0,0 +1,176 @@
+//! Loads projections of sync and async repositories on SQLite in memory, selecting only the
+//! columns of the projected `Selectable`.
+#![cfg(feature = "sqlite")]
+
+use diesel::prelude::*;
+use diesel_repository::Repo;
+
+diesel::table! {
+    projection_test_accounts {
+        id -> Integer,
+        name -> Text,
+        email -> Text,
+    }
+}
+
+const SCHEMA: &str = "CREATE TABLE projection_test_accounts (
+         id INTEGER PRIMARY KEY, name TEXT NOT NULL, email TEXT NOT NULL
+     );
+     INSERT INTO projection_test_accounts VALUES
+         (1, 'alice', 'alice@example.com'),
+         (2, 'bob', 'bob@example.com'),
+         (3, 'carol', 'carol@example.com');";
+
+#[derive(Queryable, Selectable, Debug, PartialEq)]
+#[diesel(table_name = projection_test_accounts, check_for_backend(diesel::sqlite::Sqlite))]
+pub struct AccountName {
+    pub name: String,
+}
+
+#[derive(Queryable, Selectable, Debug, PartialEq)]
+#[diesel(table_name = projection_test_accounts, check_for_backend(diesel::sqlite::Sqlite))]
+pub struct AccountContact {
+    pub id: i32,
+    pub email: String,
+}
+
+fn names(projections: Vec<AccountName>) -> Vec<String> {
+    projections
+        .into_iter()
+        .map(|projection| projection.name)
+        .collect()
+}
+
+mod sync {
+    use super::*;
+    use diesel::connection::SimpleConnection;
+    use diesel_repository::FindById;
+    use std::sync::{Arc, Mutex};
+
+    pub mod db {
+        pub type DbPool = std::sync::Mutex<diesel::SqliteConnection>;
+    }
+
+    #[derive(Queryable, Repo)]
+    #[diesel(table_name = projection_test_accounts, check_for_backend(diesel::sqlite::Sqlite))]
+    #[repository(pool = db::DbPool, projections(AccountName, AccountContact))]
+    #[crud_repo(find_one)]
+    pub struct Account {
+        pub id: i32,
+        pub name: String,
+        pub email: String,
+    }
+
+    #[test]
+    fn projections_select_their_columns() {
+        use projection_test_accounts::{id, name};
+
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.batch_execute(SCHEMA).unwrap();
+        let repo = AccountRepo::new(Arc::new(Mutex::new(conn)));
+
+        // The entity still loads every column.
+        let account = repo.find_by_id(2).unwrap();
+        assert_eq!(
+            (account.id, account.name, account.email),
+            (2, "bob".into(), "bob@example.com".into())
+        );
+        assert_eq!(
+            names(repo.find_all_as::<AccountName>().unwrap()),
+            ["alice", "bob", "carol"]
+        );
+        let contacts: Vec<AccountContact> = repo
+            .find_by_query_as(&|q| q.filter(name.ne("bob")).order(id.desc()))
+            .unwrap();
+        assert_eq!(
+            contacts,
+            [
+                AccountContact {
+                    id: 3,
+                    email: "carol@example.com".into()
+                },
+                AccountContact {
+                    id: 1,
+                    email: "alice@example.com".into()
+                },
+            ]
+        );
+
+        let page = repo.find_all_as_paged::<AccountName>(2, 2).unwrap();
+        assert_eq!(
+            (page.total_count, names(page.items)),
+            (3, vec!["carol".into()])
+        );
+        let page = repo
+            .find_by_query_as_paged::<AccountName>(&|q| q.order(id.desc()), 1, 2)
+            .unwrap();
+        assert_eq!(
+            (page.total_count, names(page.items)),
+            (3, vec!["carol".into(), "bob".into()])
+        );
+    }
+}
+
+#[cfg(feature = "async")]
+mod asynchronous {
+    use super::*;
+    use diesel::connection::SimpleConnection;
+    use diesel_async::sync_connection_wrapper::SyncConnectionWrapper;
+    use diesel_repository::asynchronous::FindById;
+    use std::sync::Arc;
+
+    pub mod db {
+        pub type DbPool = tokio::sync::Mutex<
+            diesel_async::sync_connection_wrapper::SyncConnectionWrapper<diesel::SqliteConnection>,
+        >;
+    }
+
+    #[derive(Queryable, Repo)]
+    #[diesel(table_name = projection_test_accounts, check_for_backend(diesel::sqlite::Sqlite))]
+    #[repository(mode = "async", pool = db::DbPool, projections(AccountName))]
+    #[crud_repo(find_one)]
+    #[paging_repo(count_strategy = "window")]
+    pub struct Account {
+        pub id: i32,
+        pub name: String,
+        pub email: String,
+    }
+
+    #[tokio::test]
+    async fn projections_select_their_columns() {
+        use projection_test_accounts::{id, name};
+
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.batch_execute(SCHEMA).unwrap();
+        let repo = AccountRepo::new(Arc::new(tokio::sync::Mutex::new(
+            SyncConnectionWrapper::new(conn),
+        )));
+
+        let account = repo.find_by_id(2).await.unwrap();
+        assert_eq!(
+            (account.id, account.name, account.email),
+            (2, "bob".into(), "bob@example.com".into())
+        );
+        assert_eq!(
+            names(repo.find_all_as::<AccountName>().await.unwrap()),
+            ["alice", "bob", "carol"]
+        );
+        let found = repo
+            .find_by_query_as::<AccountName>(&|q| q.filter(name.eq("bob")))
+            .await
+            .unwrap();
+        assert_eq!(names(found), ["bob"]);
+
+        // Window pages count every matching row in the same query.
+        let page = repo
+            .find_by_query_as_paged::<AccountName>(&|q| q.order(id.desc()), 1, 2)
+            .await
+            .unwrap();
+        assert_eq!(
+            (page.total_count, names(page.items)),
+            (3, vec!["carol".into(), "bob".into()])
+        );
+        let page = repo.find_all_as_paged::<AccountName>(3, 2).await.unwrap();
+        assert_eq!((page.total_count, page.items.len()), (3, 0));
+    }
+}
@@ -0,0 +1,126 @@
+//! Runs the generated query methods on SQLite in memory, through trait objects and generic
+//! bounds over the object-safe query traits, and the methods of associations.
+#![cfg(feature = "sqlite")]
+
+use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use diesel_repository::{Count, ExistsById, ExistsByIdBatch, ExistsByQuery, Repo};
+use std::sync::{Arc, Mutex};
+
+diesel::table! {
+    query_test_accounts {
+        id -> Integer,
+        name -> Text,
+    }
+}
+
+diesel::table! {
+    query_test_notes {
+        id -> Integer,
+        written_by -> Integer,
+        body -> Text,
+    }
+}
+
+pub mod db {
+    pub type DbPool = std::sync::Mutex<diesel::SqliteConnection>;
+}
+
+#[allow(dead_code)]
+#[derive(Queryable, Repo)]
+#[diesel(table_name = query_test_accounts, check_for_backend(diesel::sqlite::Sqlite))]
+#[repository(pool = db::DbPool)]
+#[crud_repo(count, exists, exists_query)]
+#[batch_repo(exists)]
+pub struct Account {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Queryable, Repo, Debug, PartialEq)]
+#[diesel(table_name = query_test_notes, check_for_backend(diesel::sqlite::Sqlite))]
+#[repository(pool = db::DbPool, belongs_to(author(repo = AccountRepo, foreign_key = written_by)))]
+pub struct Note {
+    pub id: i32,
+    pub written_by: i32,
+    pub body: String,
+}
+
+type Query = query_test_accounts::BoxedQuery<'static, Sqlite>;
+
+fn repository() -> AccountRepo {
+    let mut conn = SqliteConnection::establish(":memory:").unwrap();
+    diesel::sql_query(
+        "CREATE TABLE query_test_accounts (id INTEGER PRIMARY KEY, name TEXT NOT NULL)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    diesel::sql_query(
+        "INSERT INTO query_test_accounts VALUES (1, 'alice'), (2, 'bob'), (3, 'alice')",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    AccountRepo::new(Arc::new(Mutex::new(conn)))
+}
+
+fn named<R>(repo: &R, name: &str) -> QueryResult<i64>
+where
+    R: Count<Query = Query> + ?Sized,
+{
+    repo.count(&|q| q.filter(query_test_accounts::name.eq(name.to_owned())))
+}
+
+#[test]
+fn count_and_exists_run_through_trait_objects() {
+    let counter: Box<dyn Count<Entity = Account, Id = i32, Query = Query>> = Box::new(repository());
+    assert_eq!(counter.count(&|q| q).unwrap(), 3);
+    assert_eq!(named(counter.as_ref(), "alice").unwrap(), 2);
+    assert_eq!(named(counter.as_ref(), "carol").unwrap(), 0);
+
+    let finder: Box<dyn ExistsByQuery<Entity = Account, Id = i32, Query = Query>> =
+        Box::new(repository());
+    assert!(finder
+        .exists_by_query(&|q| q.filter(query_test_accounts::name.eq("bob")))
+        .unwrap());
+    assert!(!finder
+        .exists_by_query(&|q| q.filter(query_test_accounts::id.gt(3)))
+        .unwrap());
+}
+
+#[test]
+fn exists_by_id_checks_single_and_batched_ids() {
+    let repo = repository();
+    let by_id: &dyn ExistsById<i32> = &repo;
+    assert!(by_id.exists_by_id(2).unwrap());
+    assert!(!by_id.exists_by_id(4).unwrap());
+
+    let mut existing = repo.exists_by_id_batch(&[4, 3, 1]).unwrap();
+    existing.sort_unstable();
+    assert_eq!(existing, vec![1, 3]);
+    assert!(repo.exists_by_id_batch(&[]).unwrap().is_empty());
+}
+
+#[test]
+fn belongs_to_filters_on_the_declared_foreign_key() {
+    let mut conn = SqliteConnection::establish(":memory:").unwrap();
+    diesel::sql_query(
+        "CREATE TABLE query_test_notes (id INTEGER PRIMARY KEY, written_by INTEGER NOT NULL, body TEXT NOT NULL)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    diesel::sql_query(
+        "INSERT INTO query_test_notes VALUES (1, 1, 'hi'), (2, 2, 'hey'), (3, 1, 'bye')",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    let repo = NoteRepo::new(Arc::new(Mutex::new(conn)));
+
+    let bodies: Vec<_> = repo
+        .find_by_written_by(1)
+        .unwrap()
+        .into_iter()
+        .map(|note| note.body)
+        .collect();
+    assert_eq!(bodies, ["hi", "bye"]);
+    assert!(repo.find_by_written_by(3).unwrap().is_empty());
+}
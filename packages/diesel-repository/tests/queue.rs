@@ -0,0 +1,279 @@
+//! Works through job queues with the generated queue methods on the Postgres database at
+//! `DATABASE_URL`. Run with:
+//!
+//! ```sh
+//! DATABASE_URL=postgres://... cargo test -p diesel-repository --features postgres,async --test queue -- --ignored
+//! ```
+#![cfg(feature = "postgres")]
+
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel_repository::{Backoff, JobStatus, Repo};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+diesel::table! {
+    queue_test_jobs {
+        id -> BigInt,
+        payload -> Text,
+        status -> Text,
+        run_at -> Timestamp,
+        attempts -> Integer,
+    }
+}
+
+diesel::table! {
+    queue_test_retries {
+        id -> BigInt,
+        payload -> Text,
+        status -> Text,
+        run_at -> Timestamp,
+        attempts -> Integer,
+    }
+}
+
+pub mod db {
+    pub type DbPool = std::sync::Mutex<diesel::PgConnection>;
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = queue_test_jobs)]
+pub struct NewJob {
+    pub payload: String,
+}
+
+#[derive(Queryable, QueryableByName, Repo, Debug)]
+#[diesel(table_name = queue_test_jobs, check_for_backend(diesel::pg::Pg))]
+#[repository(pool = db::DbPool, queue(status = status, run_at = run_at, attempts = attempts))]
+#[repo_type(new_type = NewJob)]
+pub struct Job {
+    pub id: i64,
+    pub payload: String,
+    pub status: String,
+    pub run_at: SystemTime,
+    pub attempts: i32,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = queue_test_retries)]
+pub struct NewRetry {
+    pub payload: String,
+}
+
+#[derive(Queryable, QueryableByName, Repo, Debug)]
+#[diesel(table_name = queue_test_retries, check_for_backend(diesel::pg::Pg))]
+#[repository(pool = db::DbPool, queue(status = status, run_at = run_at, attempts = attempts))]
+#[repo_type(new_type = NewRetry)]
+pub struct Retry {
+    pub id: i64,
+    pub payload: String,
+    pub status: String,
+    pub run_at: SystemTime,
+    pub attempts: i32,
+}
+
+fn connect() -> PgConnection {
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL should name a Postgres database");
+    PgConnection::establish(&url).expect("DATABASE_URL should accept connections")
+}
+
+fn create_queue(conn: &mut PgConnection, table: &str) {
+    conn.batch_execute(&format!(
+        "DROP TABLE IF EXISTS {table};
+         CREATE TABLE {table} (
+             id BIGSERIAL PRIMARY KEY,
+             payload TEXT NOT NULL,
+             status TEXT NOT NULL DEFAULT 'pending',
+             run_at TIMESTAMP NOT NULL DEFAULT now(),
+             attempts INTEGER NOT NULL DEFAULT 0
+         );"
+    ))
+    .unwrap();
+}
+
+#[test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+fn concurrent_workers_claim_every_job_once() {
+    let mut conn = connect();
+    create_queue(&mut conn, "queue_test_jobs");
+    let repo = JobRepo::new(Arc::new(Mutex::new(connect())));
+    for n in 1..=200 {
+        let job = repo
+            .enqueue(NewJob {
+                payload: format!("job {}", n),
+            })
+            .unwrap();
+        assert_eq!((job.status.as_str(), job.attempts), ("pending", 0));
+    }
+
+    let workers: Vec<_> = (0..8)
+        .map(|_| {
+            std::thread::spawn(|| {
+                let repo = JobRepo::new(Arc::new(Mutex::new(connect())));
+                let mut claimed = Vec::new();
+                loop {
+                    let jobs = repo.dequeue(5).unwrap();
+                    if jobs.is_empty() {
+                        return claimed;
+                    }
+                    for job in jobs {
+                        assert_eq!(job.status, JobStatus::Running.as_str());
+                        repo.complete(job.id).unwrap();
+                        claimed.push(job.id);
+                    }
+                }
+            })
+        })
+        .collect();
+
+    let mut claimed: Vec<i64> = workers
+        .into_iter()
+        .flat_map(|worker| worker.join().unwrap())
+        .collect();
+    claimed.sort_unstable();
+    assert_eq!(claimed, (1..=200).collect::<Vec<_>>());
+
+    let jobs: Vec<(String, i32)> = queue_test_jobs::table
+        .select((queue_test_jobs::status, queue_test_jobs::attempts))
+        .load(&mut conn)
+        .unwrap();
+    assert!(jobs
+        .iter()
+        .all(|(status, attempts)| status == JobStatus::Done.as_str() && *attempts == 1));
+}
+
+#[test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+fn failed_jobs_back_off_and_stuck_jobs_are_reaped() {
+    let mut conn = connect();
+    create_queue(&mut conn, "queue_test_retries");
+    let repo = RetryRepo::new(Arc::new(Mutex::new(connect())));
+    let backoff = Backoff::exponential(Duration::from_secs(60)).max_attempts(3);
+    for payload in ["first", "second", "third"] {
+        repo.enqueue(NewRetry {
+            payload: payload.into(),
+        })
+        .unwrap();
+    }
+
+    let first = repo.dequeue(1).unwrap().remove(0);
+    let failed = repo.fail_with_backoff(first.id, &backoff).unwrap();
+    assert_eq!(failed.status, JobStatus::Pending.as_str());
+    assert!(failed.run_at > first.run_at + Duration::from_secs(59));
+
+    // The failed job is not due before its backoff, so only the others are claimed.
+    let others = repo.dequeue(10).unwrap();
+    assert_eq!(others.len(), 2);
+    assert!(others.iter().all(|job| job.id != first.id));
+
+    // Their workers never complete them; once reaped, they are claimed again.
+    std::thread::sleep(Duration::from_millis(10));
+    assert_eq!(repo.reap_stuck(Duration::from_millis(5), 3).unwrap(), 2);
+    let retried = repo.dequeue(10).unwrap();
+    assert_eq!(retried.len(), 2);
+    assert!(retried.iter().all(|job| job.attempts == 2));
+
+    // A job reaped after its last attempt is not retried again.
+    std::thread::sleep(Duration::from_millis(10));
+    assert_eq!(repo.reap_stuck(Duration::from_millis(5), 2).unwrap(), 2);
+    let reaped: Vec<String> = queue_test_retries::table
+        .filter(queue_test_retries::id.ne(first.id))
+        .select(queue_test_retries::status)
+        .load(&mut conn)
+        .unwrap();
+    assert_eq!(reaped, [JobStatus::Failed.as_str(); 2]);
+    assert!(repo.dequeue(10).unwrap().is_empty());
+
+    // Only running jobs are completed or failed.
+    assert!(matches!(repo.complete(first.id), Err(Error::NotFound)));
+    assert!(matches!(
+        repo.fail_with_backoff(first.id, &backoff),
+        Err(Error::NotFound)
+    ));
+}
+
+#[cfg(feature = "async")]
+mod asynchronous {
+    use super::*;
+    use diesel_async::{AsyncConnection, AsyncPgConnection};
+
+    diesel::table! {
+        queue_test_async_jobs {
+            id -> BigInt,
+            payload -> Text,
+            status -> Text,
+            run_at -> Timestamp,
+            attempts -> Integer,
+        }
+    }
+
+    pub mod db {
+        pub type DbPool = tokio::sync::Mutex<diesel_async::AsyncPgConnection>;
+    }
+
+    #[derive(Insertable)]
+    #[diesel(table_name = queue_test_async_jobs)]
+    pub struct NewJob {
+        pub payload: String,
+    }
+
+    #[derive(Queryable, QueryableByName, Repo, Debug)]
+    #[diesel(table_name = queue_test_async_jobs, check_for_backend(diesel::pg::Pg))]
+    #[repository(
+        mode = "async",
+        pool = db::DbPool,
+        queue(status = status, run_at = run_at, attempts = attempts)
+    )]
+    #[repo_type(new_type = NewJob)]
+    pub struct Job {
+        pub id: i64,
+        pub payload: String,
+        pub status: String,
+        pub run_at: SystemTime,
+        pub attempts: i32,
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres database at DATABASE_URL"]
+    async fn jobs_go_through_the_queue() {
+        create_queue(&mut connect(), "queue_test_async_jobs");
+        let url = std::env::var("DATABASE_URL").unwrap();
+        let pool = AsyncPgConnection::establish(&url).await.unwrap();
+        let repo = JobRepo::new(Arc::new(tokio::sync::Mutex::new(pool)));
+        let backoff = Backoff::exponential(Duration::from_secs(60)).max_attempts(1);
+
+        for payload in ["done", "failed", "stuck"] {
+            repo.enqueue(NewJob {
+                payload: payload.into(),
+            })
+            .await
+            .unwrap();
+        }
+        let jobs = repo.dequeue(3).await.unwrap();
+        assert_eq!(jobs.len(), 3);
+        let job = |payload: &str| jobs.iter().find(|job| job.payload == payload).unwrap();
+
+        repo.complete(job("done").id).await.unwrap();
+        let failed = repo
+            .fail_with_backoff(job("failed").id, &backoff)
+            .await
+            .unwrap();
+        assert_eq!(failed.status, JobStatus::Failed.as_str());
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(
+            repo.reap_stuck(Duration::from_millis(5), 2).await.unwrap(),
+            1
+        );
+        let retried = repo.dequeue(3).await.unwrap();
+        assert_eq!(retried.len(), 1);
+        assert_eq!((retried[0].id, retried[0].attempts), (job("stuck").id, 2));
+        // Running jobs hold the time they were claimed.
+        assert!(retried[0].run_at > job("stuck").run_at);
+        assert!(matches!(
+            repo.complete(job("done").id).await,
+            Err(Error::NotFound)
+        ));
+    }
+}
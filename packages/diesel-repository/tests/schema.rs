@@ -0,0 +1,153 @@
+//! Verifies repository tables against SQLite in memory and against the Postgres database
+//! at `DATABASE_URL`. Run the Postgres case with:
+//!
+//! ```sh
+//! DATABASE_URL=postgres://... cargo test -p diesel-repository --features postgres --test schema -- --ignored
+//! ```
+#![cfg(any(feature = "sqlite", feature = "postgres"))]
+
+use diesel::prelude::*;
+use diesel_repository::{Repo, SchemaCheck, SchemaError, SchemaIssue};
+
+diesel::table! {
+    schema_test_accounts {
+        id -> BigInt,
+        name -> Text,
+        nick -> Nullable<Text>,
+        score -> Double,
+        active -> Bool,
+    }
+}
+
+pub mod db {
+    pub struct DbPool;
+}
+
+#[allow(dead_code)]
+#[derive(Queryable, Repo)]
+#[diesel(table_name = schema_test_accounts)]
+#[repository(pool = db::DbPool, schema_check)]
+pub struct Account {
+    pub id: i64,
+    pub name: String,
+    pub nick: Option<String>,
+    #[diesel(column_name = score)]
+    pub rating: f64,
+    pub active: bool,
+}
+
+fn drift(result: Result<(), SchemaError>) -> Vec<SchemaIssue> {
+    match result {
+        Err(SchemaError::Drift(issues)) => issues,
+        other => panic!("expected drift, got {:?}", other),
+    }
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn sqlite_schema_drift() {
+    use diesel_repository::verify_schema;
+
+    let mut conn = SqliteConnection::establish(":memory:").unwrap();
+    let issues = drift(verify_schema(&mut conn, &[AccountRepo::SCHEMA]));
+    assert_eq!(
+        issues,
+        vec![SchemaIssue::MissingTable {
+            table: "schema_test_accounts"
+        }]
+    );
+
+    diesel::sql_query(
+        "CREATE TABLE schema_test_accounts (
+            id INTEGER PRIMARY KEY,
+            name VARCHAR(80) NOT NULL,
+            nick TEXT,
+            score REAL NOT NULL,
+            active BOOLEAN NOT NULL
+        )",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    verify_schema(&mut conn, &[AccountRepo::SCHEMA]).unwrap();
+
+    diesel::sql_query("DROP TABLE schema_test_accounts")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query(
+        "CREATE TABLE schema_test_accounts (
+            id INTEGER PRIMARY KEY,
+            name TEXT,
+            nick TEXT,
+            score TEXT NOT NULL
+        )",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    let issues = drift(verify_schema(&mut conn, &[AccountRepo::SCHEMA]));
+    assert_eq!(
+        issues,
+        vec![
+            SchemaIssue::NullabilityMismatch {
+                table: "schema_test_accounts",
+                column: "name",
+                nullable: false,
+            },
+            SchemaIssue::TypeMismatch {
+                table: "schema_test_accounts",
+                column: "score",
+                expected: "a type of Real affinity".to_string(),
+                found: "TEXT".to_string(),
+            },
+            SchemaIssue::MissingColumn {
+                table: "schema_test_accounts",
+                column: "active",
+            },
+        ]
+    );
+}
+
+#[cfg(feature = "postgres")]
+#[test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+fn postgres_schema_drift() {
+    use diesel_repository::verify_schema;
+
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL should name a Postgres database");
+    let mut conn = PgConnection::establish(&url).expect("DATABASE_URL should accept connections");
+    diesel::sql_query("DROP TABLE IF EXISTS schema_test_accounts")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query(
+        "CREATE TABLE schema_test_accounts (
+            id BIGSERIAL PRIMARY KEY,
+            name VARCHAR(80) NOT NULL,
+            nick TEXT NOT NULL,
+            score INTEGER NOT NULL,
+            active BOOLEAN NOT NULL
+        )",
+    )
+    .execute(&mut conn)
+    .unwrap();
+
+    let issues = drift(verify_schema(&mut conn, &[AccountRepo::SCHEMA]));
+    assert_eq!(
+        issues,
+        vec![
+            SchemaIssue::NullabilityMismatch {
+                table: "schema_test_accounts",
+                column: "nick",
+                nullable: true,
+            },
+            SchemaIssue::TypeMismatch {
+                table: "schema_test_accounts",
+                column: "score",
+                expected: "`float8`".to_string(),
+                found: "int4".to_string(),
+            },
+        ]
+    );
+    assert_eq!(
+        issues[0].to_string(),
+        "column `schema_test_accounts.nick` is NOT NULL but declared `Nullable`"
+    );
+}
@@ -0,0 +1,77 @@
+#![cfg(feature = "serde")]
+
+use diesel_repository::{PageRequest, Paged, Slice};
+use serde_json::json;
+
+#[test]
+fn paged_json_shape() {
+    let paged = Paged {
+        items: vec!["a", "b"],
+        total_count: 12,
+        page: 2,
+        per_page: 5,
+    };
+
+    assert_eq!(
+        serde_json::to_value(&paged).unwrap(),
+        json!({
+            "items": ["a", "b"],
+            "page": 2,
+            "per_page": 5,
+            "total_count": 12,
+            "total_pages": 3,
+            "has_next": true,
+            "has_previous": true,
+        })
+    );
+}
+
+#[test]
+fn slice_json_shape() {
+    let slice = Slice::from_overfetch(vec![1, 2, 3], 1, 2);
+
+    assert_eq!(
+        serde_json::to_value(&slice).unwrap(),
+        json!({
+            "items": [1, 2],
+            "page": 1,
+            "per_page": 2,
+            "has_next": true,
+            "has_previous": false,
+        })
+    );
+}
+
+#[test]
+fn page_request_round_trip() {
+    let request: PageRequest =
+        serde_json::from_value(json!({ "page": 3, "per_page": 20 })).unwrap();
+
+    assert_eq!(request, PageRequest::new(3, 20));
+    assert_eq!(request.offset(), 40);
+    assert_eq!(
+        serde_json::to_value(request).unwrap(),
+        json!({ "page": 3, "per_page": 20 })
+    );
+}
+
+#[test]
+fn page_request_rejects_invalid_pages() {
+    for (value, message) in [
+        (
+            json!({ "page": 0, "per_page": 20 }),
+            "page must be at least 1, got 0",
+        ),
+        (
+            json!({ "page": 1, "per_page": -5 }),
+            "per_page must be at least 1, got -5",
+        ),
+        (
+            json!({ "page": i64::MAX, "per_page": 20 }),
+            "beyond the largest offset",
+        ),
+    ] {
+        let err = serde_json::from_value::<PageRequest>(value).unwrap_err();
+        assert!(err.to_string().contains(message), "{}", err);
+    }
+}
@@ -0,0 +1,234 @@
+//! Applies `SessionContext`s to transactions of the Postgres database at `DATABASE_URL`, with
+//! row-level security policies reading them. Run with:
+//!
+//! ```sh
+//! DATABASE_URL=postgres://... cargo test -p diesel-repository --features postgres,async --test session -- --ignored
+//! ```
+#![cfg(feature = "postgres")]
+
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::sql_types::{Nullable, Text};
+use diesel_repository::{Count, FindAllPaged, Repo, Save, SessionContext};
+use std::sync::{Arc, Mutex};
+
+diesel::table! {
+    session_test_posts {
+        id -> Integer,
+        owner -> Text,
+    }
+}
+
+pub mod db {
+    pub type DbPool = std::sync::Mutex<diesel::PgConnection>;
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = session_test_posts)]
+pub struct NewPost {
+    pub owner: String,
+}
+
+#[derive(Queryable, Repo, Debug, PartialEq)]
+#[diesel(table_name = session_test_posts, check_for_backend(diesel::pg::Pg))]
+#[repository(pool = db::DbPool, session_context)]
+#[repo_type(new_type = NewPost)]
+#[crud_repo(save, count)]
+#[repo_sql(name = "owners", sql = "SELECT owner FROM session_test_posts ORDER BY id", row = Note)]
+#[paging_repo(find_all)]
+pub struct Post {
+    pub id: i32,
+    pub owner: String,
+}
+
+#[derive(QueryableByName, Debug)]
+pub struct Note {
+    #[diesel(sql_type = Text)]
+    pub owner: String,
+}
+
+#[derive(QueryableByName, Debug)]
+struct Setting {
+    #[diesel(sql_type = Nullable<Text>)]
+    value: Option<String>,
+}
+
+fn database_url() -> String {
+    std::env::var("DATABASE_URL").expect("DATABASE_URL should name a Postgres database")
+}
+
+fn connect() -> PgConnection {
+    PgConnection::establish(&database_url()).expect("DATABASE_URL should accept connections")
+}
+
+/// Creates `table` with two posts of alice and one of bob, readable and writable by `role`
+/// for the owner named in `app.current_user` only.
+fn create_posts(conn: &mut PgConnection, table: &str, role: &str) {
+    conn.batch_execute(&format!(
+        "DROP TABLE IF EXISTS {table};
+         CREATE TABLE {table} (id SERIAL PRIMARY KEY, owner TEXT NOT NULL);
+         INSERT INTO {table} (owner) VALUES ('alice'), ('alice'), ('bob');
+         ALTER TABLE {table} ENABLE ROW LEVEL SECURITY;
+         CREATE POLICY owner_only ON {table}
+             USING (owner = current_setting('app.current_user', true));
+         DO $$ BEGIN
+             IF NOT EXISTS (SELECT FROM pg_roles WHERE rolname = '{role}') THEN
+                 CREATE ROLE {role} NOLOGIN;
+             END IF;
+         END $$;
+         GRANT SELECT, INSERT ON {table} TO {role};
+         GRANT USAGE ON SEQUENCE {table}_id_seq TO {role};"
+    ))
+    .unwrap();
+}
+
+fn current_user(conn: &mut PgConnection) -> Option<String> {
+    diesel::sql_query("SELECT current_setting('app.current_user', true) AS value")
+        .get_result::<Setting>(conn)
+        .unwrap()
+        .value
+        .filter(|value| !value.is_empty())
+}
+
+#[test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+fn policies_see_the_settings_of_the_transaction() {
+    let mut conn = connect();
+    conn.batch_execute(
+        "DROP TABLE IF EXISTS session_test_notes;
+         CREATE TABLE session_test_notes (id SERIAL PRIMARY KEY, owner TEXT NOT NULL);
+         INSERT INTO session_test_notes (owner) VALUES ('alice'), ('alice'), ('bob');
+         ALTER TABLE session_test_notes ENABLE ROW LEVEL SECURITY;
+         CREATE POLICY owner_only ON session_test_notes
+             USING (owner = current_setting('app.current_user', true));
+         DO $$ BEGIN
+             IF NOT EXISTS (SELECT FROM pg_roles WHERE rolname = 'session_test_reader') THEN
+                 CREATE ROLE session_test_reader NOLOGIN;
+             END IF;
+         END $$;
+         GRANT SELECT ON session_test_notes TO session_test_reader;",
+    )
+    .unwrap();
+
+    let context = SessionContext::new()
+        .set("role", "session_test_reader")
+        .set("app.current_user", "alice");
+    let notes: Vec<Note> = conn
+        .transaction(|conn| {
+            context.apply(conn)?;
+            diesel::sql_query("SELECT owner FROM session_test_notes").load(conn)
+        })
+        .unwrap();
+    assert_eq!(notes.len(), 2);
+    assert!(notes.iter().all(|note| note.owner == "alice"));
+
+    // The settings end with the transaction, leaving the pooled connection as it was.
+    assert_eq!(current_user(&mut conn), None);
+}
+
+#[test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+fn applying_outside_a_transaction_fails() {
+    let mut conn = connect();
+    let context = SessionContext::new().set("app.current_user", "alice");
+
+    assert!(matches!(
+        context.apply(&mut conn),
+        Err(Error::NotInTransaction)
+    ));
+    assert_eq!(current_user(&mut conn), None);
+}
+
+#[test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+fn repository_views_apply_their_context() {
+    let mut conn = connect();
+    create_posts(&mut conn, "session_test_posts", "session_test_writer");
+    let repo = PostRepo::new(Arc::new(Mutex::new(connect())));
+    let view = repo.with_context(
+        SessionContext::new()
+            .set("role", "session_test_writer")
+            .set("app.current_user", "alice"),
+    );
+
+    assert_eq!(view.count(&|q| q).unwrap(), 2);
+    let page = view.find_all_paged(1, 10).unwrap();
+    assert_eq!(page.total_count, 2);
+    assert!(page.items.iter().all(|post| post.owner == "alice"));
+    let saved = view
+        .save(NewPost {
+            owner: "alice".into(),
+        })
+        .unwrap();
+    assert_eq!(saved.owner, "alice");
+    // The policy also checks new rows against the context.
+    assert!(view
+        .save(NewPost {
+            owner: "bob".into()
+        })
+        .is_err());
+    // Custom SQL of the view runs under the context too.
+    let owners = view.owners().unwrap();
+    assert_eq!(owners.len(), 3);
+    assert!(owners.iter().all(|note| note.owner == "alice"));
+
+    // The repository itself runs as the connection's user, which owns the table.
+    assert_eq!(repo.count(&|q| q).unwrap(), 4);
+    assert_eq!(current_user(&mut repo.pool.lock().unwrap()), None);
+}
+
+#[cfg(feature = "async")]
+mod asynchronous {
+    use super::*;
+    use diesel_async::{AsyncConnection, AsyncPgConnection};
+    use diesel_repository::asynchronous::Count;
+
+    diesel::table! {
+        session_test_async_posts {
+            id -> Integer,
+            owner -> Text,
+        }
+    }
+
+    pub mod db {
+        pub type DbPool = tokio::sync::Mutex<diesel_async::AsyncPgConnection>;
+    }
+
+    #[derive(Queryable, Repo, Debug, PartialEq)]
+    #[diesel(table_name = session_test_async_posts, check_for_backend(diesel::pg::Pg))]
+    #[repository(mode = "async", pool = db::DbPool, session_context)]
+    #[crud_repo(count)]
+    #[repo_sql(
+        name = "owners",
+        sql = "SELECT owner FROM session_test_async_posts",
+        row = Note
+    )]
+    pub struct Post {
+        pub id: i32,
+        pub owner: String,
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres database at DATABASE_URL"]
+    async fn repository_views_apply_their_context() {
+        create_posts(
+            &mut connect(),
+            "session_test_async_posts",
+            "session_test_async_reader",
+        );
+        let pool = AsyncPgConnection::establish(&database_url()).await.unwrap();
+        let repo = PostRepo::new(Arc::new(tokio::sync::Mutex::new(pool)));
+        let context = SessionContext::new()
+            .set("role", "session_test_async_reader")
+            .set("app.current_user", "bob");
+
+        let view = repo.with_context(context);
+        assert_eq!(view.count(&|q| q).await.unwrap(), 1);
+        let owners = view.owners().await.unwrap();
+        assert_eq!(owners.len(), 1);
+        assert_eq!(owners[0].owner, "bob");
+        assert_eq!(repo.count(&|q| q).await.unwrap(), 3);
+        assert_eq!(repo.owners().await.unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,158 @@
+//! Runs the `#[repo_sql]` methods of sync and async repositories on SQLite in memory, with
+//! every `returns` kind.
+#![cfg(feature = "sqlite")]
+
+use diesel::prelude::*;
+use diesel_repository::Repo;
+
+diesel::table! {
+    sql_test_accounts {
+        id -> Integer,
+        name -> Text,
+        active -> Bool,
+    }
+}
+
+const SCHEMA: &str = "CREATE TABLE sql_test_accounts (
+         id INTEGER PRIMARY KEY, name TEXT NOT NULL, active BOOLEAN NOT NULL
+     );
+     INSERT INTO sql_test_accounts VALUES (1, 'alice', 1), (2, 'bob', 0), (3, 'carol', 1);";
+
+#[derive(QueryableByName, Debug, PartialEq)]
+#[diesel(table_name = sql_test_accounts, check_for_backend(diesel::sqlite::Sqlite))]
+pub struct AccountName {
+    pub name: String,
+}
+
+mod sync {
+    use super::*;
+    use diesel::connection::SimpleConnection;
+    use std::sync::{Arc, Mutex};
+
+    pub mod db {
+        pub type DbPool = std::sync::Mutex<diesel::SqliteConnection>;
+    }
+
+    #[derive(Queryable, QueryableByName, Repo, Debug, PartialEq)]
+    #[diesel(table_name = sql_test_accounts, check_for_backend(diesel::sqlite::Sqlite))]
+    #[repository(pool = db::DbPool)]
+    #[repo_sql(
+        name = "find_active",
+        sql = "SELECT * FROM sql_test_accounts WHERE active = ? ORDER BY id",
+        args(active: bool)
+    )]
+    #[repo_sql(
+        name = "find_named",
+        sql = "SELECT * FROM sql_test_accounts WHERE name = ?",
+        args(name: String),
+        returns = Option
+    )]
+    #[repo_sql(
+        name = "name_of",
+        sql = "SELECT name FROM sql_test_accounts WHERE id = ?",
+        args(id: i32),
+        returns = One,
+        row = AccountName
+    )]
+    #[repo_sql(
+        name = "deactivate",
+        sql = "UPDATE sql_test_accounts SET active = 0 WHERE id <= ?",
+        args(id: i32),
+        returns = Execute
+    )]
+    pub struct Account {
+        pub id: i32,
+        pub name: String,
+        pub active: bool,
+    }
+
+    #[test]
+    fn custom_queries_run_with_their_arguments() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.batch_execute(SCHEMA).unwrap();
+        let repo = AccountRepo::new(Arc::new(Mutex::new(conn)));
+
+        let active = repo.find_active(true).unwrap();
+        assert_eq!(
+            active.iter().map(|account| account.id).collect::<Vec<_>>(),
+            [1, 3]
+        );
+        assert!(active.iter().all(|account| account.active));
+
+        let bob = repo.find_named("bob".into()).unwrap().unwrap();
+        assert_eq!((bob.id, bob.name.as_str(), bob.active), (2, "bob", false));
+        assert_eq!(repo.find_named("dave".into()).unwrap(), None);
+
+        assert_eq!(repo.name_of(3).unwrap().name, "carol");
+        assert!(matches!(
+            repo.name_of(9),
+            Err(diesel::result::Error::NotFound)
+        ));
+
+        assert_eq!(repo.deactivate(2).unwrap(), 2);
+        assert_eq!(repo.find_active(true).unwrap().len(), 1);
+    }
+}
+
+#[cfg(feature = "async")]
+mod asynchronous {
+    use super::*;
+    use diesel::connection::SimpleConnection;
+    use diesel_async::sync_connection_wrapper::SyncConnectionWrapper;
+    use std::sync::Arc;
+
+    pub mod db {
+        pub type DbPool = tokio::sync::Mutex<
+            diesel_async::sync_connection_wrapper::SyncConnectionWrapper<diesel::SqliteConnection>,
+        >;
+    }
+
+    #[derive(Queryable, QueryableByName, Repo, Debug, PartialEq)]
+    #[diesel(table_name = sql_test_accounts, check_for_backend(diesel::sqlite::Sqlite))]
+    #[repository(mode = "async", pool = db::DbPool)]
+    #[repo_sql(
+        name = "find_named",
+        sql = "SELECT * FROM sql_test_accounts WHERE name = ?",
+        args(name: String),
+        returns = Option
+    )]
+    #[repo_sql(
+        name = "names",
+        sql = "SELECT name FROM sql_test_accounts ORDER BY name DESC",
+        row = AccountName
+    )]
+    #[repo_sql(
+        name = "rename",
+        sql = "UPDATE sql_test_accounts SET name = ? WHERE id = ?",
+        args(name: String, id: i32),
+        returns = Execute
+    )]
+    pub struct Account {
+        pub id: i32,
+        pub name: String,
+        pub active: bool,
+    }
+
+    #[tokio::test]
+    async fn custom_queries_run_with_their_arguments() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.batch_execute(SCHEMA).unwrap();
+        let repo = AccountRepo::new(Arc::new(tokio::sync::Mutex::new(
+            SyncConnectionWrapper::new(conn),
+        )));
+
+        assert_eq!(repo.rename("bobby".into(), 2).await.unwrap(), 1);
+        let bobby = repo.find_named("bobby".into()).await.unwrap().unwrap();
+        assert_eq!((bobby.id, bobby.active), (2, false));
+        assert_eq!(repo.find_named("bob".into()).await.unwrap(), None);
+
+        let names: Vec<_> = repo
+            .names()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|row| row.name)
+            .collect();
+        assert_eq!(names, ["carol", "bobby", "alice"]);
+    }
+}
@@ -0,0 +1,152 @@
+//! Follows the changes of tables through the Postgres database at `DATABASE_URL`. Run with:
+//!
+//! ```sh
+//! DATABASE_URL=postgres://... cargo test -p diesel-repository --features subscribe --test subscribe -- --ignored
+//! ```
+#![cfg(feature = "subscribe")]
+
+use diesel::prelude::*;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl, SimpleAsyncConnection};
+use diesel_repository::asynchronous::Subscribe;
+use diesel_repository::{install_notify_trigger, Change, ChangeListener, ChangeStream, Repo};
+use futures_util::future::BoxFuture;
+use futures_util::StreamExt;
+use std::sync::Arc;
+use std::time::Duration;
+
+diesel::table! {
+    subscribe_test_notes {
+        id -> BigInt,
+        body -> Text,
+    }
+}
+
+diesel::table! {
+    subscribe_test_posts {
+        id -> BigInt,
+        body -> Text,
+    }
+}
+
+pub mod db {
+    pub type DbPool = tokio::sync::Mutex<diesel_async::AsyncPgConnection>;
+}
+
+#[derive(Queryable, Debug, PartialEq, Eq)]
+struct Note {
+    id: i64,
+    body: String,
+}
+
+#[derive(Queryable, Repo, Debug, PartialEq, Eq)]
+#[diesel(table_name = subscribe_test_posts, check_for_backend(diesel::pg::Pg))]
+#[repository(mode = "async", pool = db::DbPool, subscribe)]
+pub struct Post {
+    pub id: i64,
+    pub body: String,
+}
+
+fn database_url() -> String {
+    std::env::var("DATABASE_URL").expect("DATABASE_URL should name a Postgres database")
+}
+
+fn load(conn: &mut AsyncPgConnection, id: i64) -> BoxFuture<'_, QueryResult<Option<Note>>> {
+    Box::pin(async move {
+        subscribe_test_notes::table
+            .find(id)
+            .first(conn)
+            .await
+            .optional()
+    })
+}
+
+async fn next<T>(changes: &mut ChangeStream<T, i64>) -> Change<T, i64> {
+    tokio::time::timeout(Duration::from_secs(5), changes.next())
+        .await
+        .expect("a change within 5 seconds")
+        .expect("an open stream")
+        .unwrap()
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn changes_are_streamed_in_commit_order() {
+    let url = database_url();
+    let mut conn = AsyncPgConnection::establish(&url).await.unwrap();
+    conn.batch_execute(
+        "DROP TABLE IF EXISTS subscribe_test_notes;
+         CREATE TABLE subscribe_test_notes (id BIGSERIAL PRIMARY KEY, body TEXT NOT NULL);",
+    )
+    .await
+    .unwrap();
+
+    let mut listener = ChangeListener::connect(&url, "subscribe_test_notes_changes")
+        .await
+        .unwrap();
+    install_notify_trigger(
+        listener.connection(),
+        "subscribe_test_notes",
+        "id",
+        "subscribe_test_notes_changes",
+    )
+    .await
+    .unwrap();
+    let mut changes = listener.changes(load);
+
+    conn.batch_execute(
+        "INSERT INTO subscribe_test_notes (body) VALUES ('draft');
+         UPDATE subscribe_test_notes SET body = 'final' WHERE id = 1;
+         INSERT INTO subscribe_test_notes (body) VALUES ('gone');
+         DELETE FROM subscribe_test_notes WHERE id = 2;",
+    )
+    .await
+    .unwrap();
+
+    // Rows are loaded when their notice arrives, so both changes of the first note see
+    // its final state, and the deleted note's insert is skipped.
+    let final_note = || Note {
+        id: 1,
+        body: "final".to_string(),
+    };
+    assert_eq!(next(&mut changes).await, Change::Insert(final_note()));
+    assert_eq!(next(&mut changes).await, Change::Update(final_note()));
+    assert_eq!(next(&mut changes).await, Change::Delete(2));
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn repositories_stream_the_changes_of_their_table() {
+    let url = database_url();
+    let mut conn = AsyncPgConnection::establish(&url).await.unwrap();
+    conn.batch_execute(
+        "DROP TABLE IF EXISTS subscribe_test_posts;
+         CREATE TABLE subscribe_test_posts (id BIGSERIAL PRIMARY KEY, body TEXT NOT NULL);",
+    )
+    .await
+    .unwrap();
+    let pool = AsyncPgConnection::establish(&url).await.unwrap();
+    let repo = PostRepo::new(Arc::new(tokio::sync::Mutex::new(pool)));
+    assert_eq!(PostRepo::CHANNEL, "subscribe_test_posts_changes");
+
+    repo.install_trigger().await.unwrap();
+    // Installing the trigger again replaces it.
+    repo.install_trigger().await.unwrap();
+    let mut changes = repo.changes(&url).await.unwrap();
+
+    conn.batch_execute(
+        "INSERT INTO subscribe_test_posts (body) VALUES ('hello');
+         DELETE FROM subscribe_test_posts WHERE id = 1;
+         INSERT INTO subscribe_test_posts (body) VALUES ('again');
+         UPDATE subscribe_test_posts SET body = 'edited' WHERE id = 2;",
+    )
+    .await
+    .unwrap();
+
+    let edited = || Post {
+        id: 2,
+        body: "edited".to_string(),
+    };
+    assert_eq!(next(&mut changes).await, Change::Delete(1));
+    assert_eq!(next(&mut changes).await, Change::Insert(edited()));
+    assert_eq!(next(&mut changes).await, Change::Update(edited()));
+}
@@ -0,0 +1,46 @@
+use diesel::{table, Queryable};
+use diesel_repository::Repo;
+
+table! {
+    accounts {
+        id -> Text,
+        name -> Text,
+    }
+}
+
+table! {
+    posts {
+        id -> Integer,
+        owner_id -> Text,
+    }
+}
+
+pub mod db {
+    pub struct DbPool;
+}
+
+#[derive(Queryable, Repo)]
+#[diesel(table_name = crate::accounts)]
+#[repository(pool = db::DbPool)]
+pub struct Account {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Queryable, Repo)]
+#[diesel(table_name = crate::posts)]
+#[repository(pool = db::DbPool, belongs_to(account = AccountRepo))]
+pub struct Post {
+    pub id: i32,
+    pub owner_id: String,
+}
+
+#[derive(Queryable, Repo)]
+#[diesel(table_name = crate::posts)]
+#[repository(pool = db::DbPool, name = DraftRepo, belongs_to(owner(repo = AccountRepo, foreign_key = ownr_id)))]
+pub struct Draft {
+    pub id: i32,
+    pub owner_id: String,
+}
+
+fn main() {}
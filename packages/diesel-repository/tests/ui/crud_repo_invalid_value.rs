@@ -0,0 +1,24 @@
+use diesel::{table, Queryable};
+use diesel_repository::Repo;
+
+table! {
+    accounts {
+        id -> Text,
+        name -> Text,
+    }
+}
+
+pub mod db {
+    pub struct DbPool;
+}
+
+#[derive(Queryable, Repo)]
+#[repository(pool = db::DbPool, table_name = crate::accounts)]
+#[repo_type(id_type = String)]
+#[crud_repo(save = "yes")]
+pub struct Account {
+    pub id: String,
+    pub name: String,
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+use diesel::table;
+use diesel_repository::Repo;
+
+table! {
+    accounts {
+        id -> Text,
+        name -> Text,
+    }
+}
+
+pub mod db {
+    pub struct DbPool;
+}
+
+#[derive(Repo)]
+#[repository(pool = db::DbPool, table_name = crate::accounts)]
+#[repo_type(id_type = String)]
+pub enum Account {
+    Active { id: String },
+    Closed { id: String },
+}
+
+fn main() {}
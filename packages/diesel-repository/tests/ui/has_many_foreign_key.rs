@@ -0,0 +1,25 @@
+use diesel::{table, Queryable};
+use diesel_repository::Repo;
+
+table! {
+    accounts {
+        id -> Text,
+        name -> Text,
+    }
+}
+
+pub mod db {
+    pub struct DbPool;
+}
+
+pub struct PostRepo;
+
+#[derive(Queryable, Repo)]
+#[diesel(table_name = crate::accounts)]
+#[repository(pool = db::DbPool, has_many(posts(repo = PostRepo, foreign_key = author_id)))]
+pub struct Account {
+    pub id: String,
+    pub name: String,
+}
+
+fn main() {}
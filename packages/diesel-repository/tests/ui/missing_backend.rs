@@ -0,0 +1,24 @@
+use diesel::{table, Queryable};
+use diesel_repository::Repo;
+
+table! {
+    accounts {
+        id -> Text,
+        name -> Text,
+    }
+}
+
+pub mod db {
+    pub struct DbPool;
+}
+
+#[derive(Queryable, Repo)]
+#[diesel(table_name = crate::accounts)]
+#[repository(pool = db::DbPool)]
+#[crud_repo(find_query)]
+pub struct Account {
+    pub id: String,
+    pub name: String,
+}
+
+fn main() {}
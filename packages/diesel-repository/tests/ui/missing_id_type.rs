@@ -0,0 +1,22 @@
+use diesel::{table, Queryable};
+use diesel_repository::Repo;
+
+table! {
+    accounts {
+        id -> Text,
+        name -> Text,
+    }
+}
+
+pub mod db {
+    pub struct DbPool;
+}
+
+#[derive(Queryable, Repo)]
+#[repository(pool = db::DbPool, table_name = crate::accounts)]
+pub struct Account {
+    pub account_id: String,
+    pub name: String,
+}
+
+fn main() {}
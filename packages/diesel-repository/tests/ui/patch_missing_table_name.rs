@@ -0,0 +1,9 @@
+use diesel_repository::Patch;
+
+#[derive(Patch)]
+pub struct Account {
+    pub id: String,
+    pub name: String,
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+use diesel_repository::Patch;
+
+diesel::table! {
+    accounts {
+        id -> Text,
+        name -> Text,
+    }
+}
+
+#[derive(Patch)]
+#[patch(table_name = crate::accounts)]
+pub struct Account {
+    pub id: String,
+    #[patch(skipp)]
+    pub name: String,
+}
+
+fn main() {}
@@ -0,0 +1,31 @@
+use diesel::{table, Queryable, Selectable};
+use diesel_repository::Repo;
+
+table! {
+    accounts {
+        id -> Text,
+        name -> Text,
+    }
+}
+
+pub mod db {
+    pub type DbPool = std::sync::Mutex<diesel::SqliteConnection>;
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = crate::accounts, check_for_backend(diesel::sqlite::Sqlite))]
+pub struct AccountSummary {
+    pub id: String,
+    pub email: String,
+}
+
+#[derive(Queryable, Repo)]
+#[diesel(table_name = crate::accounts, check_for_backend(diesel::sqlite::Sqlite))]
+#[repository(pool = db::DbPool, projections(AccountSummary))]
+#[crud_repo(find_one)]
+pub struct Account {
+    pub id: String,
+    pub name: String,
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+use diesel::{table, Queryable};
+use diesel_repository::Repo;
+
+table! {
+    accounts {
+        id -> Text,
+        name -> Text,
+    }
+}
+
+pub mod db {
+    pub struct DbPool;
+}
+
+#[derive(Queryable, Repo)]
+#[repository = "db::DbPool"]
+#[repo_type(id_type = String)]
+pub struct Account {
+    pub id: String,
+    pub name: String,
+}
+
+fn main() {}
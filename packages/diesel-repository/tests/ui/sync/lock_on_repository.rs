@@ -0,0 +1,31 @@
+use diesel::{table, Queryable};
+use diesel_repository::{Lock, Repo};
+
+table! {
+    accounts {
+        id -> Text,
+        name -> Text,
+    }
+}
+
+pub mod db {
+    pub type DbPool = std::sync::Mutex<diesel::SqliteConnection>;
+}
+
+#[derive(Queryable, Repo)]
+#[diesel(table_name = crate::accounts, check_for_backend(diesel::sqlite::Sqlite))]
+#[repository(pool = db::DbPool)]
+#[crud_repo(find_one, lock)]
+pub struct Account {
+    pub id: String,
+    pub name: String,
+}
+
+fn is_lock<L: Lock<Account, String>>(_: &L) {}
+
+// Locking selects live on `InTransaction`, never on the repository itself.
+fn lock(repo: &AccountRepo) {
+    is_lock(repo);
+}
+
+fn main() {}
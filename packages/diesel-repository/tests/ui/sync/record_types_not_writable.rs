@@ -0,0 +1,26 @@
+use diesel::{table, Queryable};
+use diesel_repository::Repo;
+
+table! {
+    accounts {
+        id -> Text,
+        name -> Text,
+    }
+}
+
+pub mod db {
+    pub type DbPool = std::sync::Mutex<diesel::SqliteConnection>;
+}
+
+// `new_type` and `update_type` default to the entity, which Diesel can neither insert nor
+// update with.
+#[derive(Queryable, Repo)]
+#[diesel(table_name = crate::accounts, check_for_backend(diesel::sqlite::Sqlite))]
+#[repository(pool = db::DbPool)]
+#[crud_repo(save, update)]
+pub struct Account {
+    pub id: String,
+    pub name: String,
+}
+
+fn main() {}
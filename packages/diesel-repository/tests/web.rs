@@ -0,0 +1,124 @@
+//! Sends requests through the `PageParams` extractors and `Paged` responses of axum and
+//! actix-web.
+#![cfg(any(feature = "axum", feature = "actix"))]
+
+use diesel_repository::{PageParams, PageSpec, Paged};
+
+struct AccountPages;
+
+impl PageSpec for AccountPages {
+    const SORT_KEYS: &'static [&'static str] = &["name"];
+    const MAX_PER_PAGE: i64 = 50;
+}
+
+/// Answers with the requested page of 35 numbered accounts.
+fn accounts(params: &PageParams<AccountPages>) -> Paged<i64> {
+    let first = (params.page - 1) * params.per_page;
+    Paged {
+        items: (first..35.min(first + params.per_page)).collect(),
+        total_count: 35,
+        page: params.page,
+        per_page: params.per_page,
+    }
+}
+
+#[cfg(feature = "axum")]
+mod axum_requests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use axum::routing::get;
+    use axum::Router;
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new().route(
+            "/accounts",
+            get(|params: PageParams<AccountPages>| async move { accounts(&params) }),
+        )
+    }
+
+    async fn body(response: axum::response::Response) -> String {
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn responds_with_the_page_and_its_links() {
+        let request = Request::get("/accounts?sort=name&page=2&per_page=10")
+            .body(Body::empty())
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["x-total-count"], "35");
+        assert_eq!(
+            response.headers()[header::LINK],
+            "<?page=1&per_page=10>; rel=\"first\", <?page=1&per_page=10>; rel=\"prev\", \
+             <?page=3&per_page=10>; rel=\"next\", <?page=4&per_page=10>; rel=\"last\""
+        );
+        let json: serde_json::Value = serde_json::from_str(&body(response).await).unwrap();
+        assert_eq!(json["items"][0], 10);
+        assert_eq!(json["total_pages"], 4);
+    }
+
+    #[tokio::test]
+    async fn rejects_per_page_above_the_max() {
+        let request = Request::get("/accounts?per_page=51")
+            .body(Body::empty())
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(body(response).await, "`per_page` must be at most 50");
+    }
+}
+
+#[cfg(feature = "actix")]
+mod actix_requests {
+    use super::*;
+    use actix_web::http::{header, StatusCode};
+    use actix_web::test::{call_service, init_service, read_body, TestRequest};
+    use actix_web::{web, App};
+
+    async fn list(params: PageParams<AccountPages>) -> Paged<i64> {
+        accounts(&params)
+    }
+
+    #[test]
+    fn responds_with_the_page_and_its_links() {
+        actix_rt::System::new().block_on(async {
+            let app = init_service(App::new().route("/accounts", web::get().to(list))).await;
+            let request = TestRequest::get()
+                .uri("/accounts?sort=name&page=2&per_page=10")
+                .to_request();
+            let response = call_service(&app, request).await;
+
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.headers().get("x-total-count").unwrap(), "35");
+            assert_eq!(
+                response.headers().get(header::LINK).unwrap(),
+                "</accounts?sort=name&page=1&per_page=10>; rel=\"first\", \
+                 </accounts?sort=name&page=1&per_page=10>; rel=\"prev\", \
+                 </accounts?sort=name&page=3&per_page=10>; rel=\"next\", \
+                 </accounts?sort=name&page=4&per_page=10>; rel=\"last\""
+            );
+            let json: serde_json::Value =
+                serde_json::from_slice(&read_body(response).await).unwrap();
+            assert_eq!(json["items"][0], 10);
+        });
+    }
+
+    #[test]
+    fn rejects_per_page_above_the_max() {
+        actix_rt::System::new().block_on(async {
+            let app = init_service(App::new().route("/accounts", web::get().to(list))).await;
+            let request = TestRequest::get().uri("/accounts?per_page=51").to_request();
+            let response = call_service(&app, request).await;
+
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            assert_eq!(read_body(response).await, "`per_page` must be at most 50");
+        });
+    }
+}